@@ -1,4 +1,4 @@
-use crate::{lexer::TokenKind, value::Value};
+use crate::{commons::Loc, lexer::TokenKind, value::Value};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum UnaryOp {
@@ -15,6 +15,9 @@ pub enum BinOp {
     Equal,
     Greater,
     Less,
+
+    And,
+    Or,
 }
 
 impl std::fmt::Display for BinOp {
@@ -27,6 +30,8 @@ impl std::fmt::Display for BinOp {
             BinOp::Equal => f.write_str("=="),
             BinOp::Greater => f.write_str(">"),
             BinOp::Less => f.write_str("<"),
+            BinOp::And => f.write_str("&&"),
+            BinOp::Or => f.write_str("||"),
         }
     }
 }
@@ -43,6 +48,8 @@ impl TryFrom<TokenKind> for BinOp {
             TokenKind::EqualEqual => Ok(BinOp::Equal),
             TokenKind::Greater => Ok(BinOp::Greater),
             TokenKind::Less => Ok(BinOp::Less),
+            TokenKind::And => Ok(BinOp::And),
+            TokenKind::Or => Ok(BinOp::Or),
             _ => Err(()),
         }
     }
@@ -65,6 +72,31 @@ pub enum Expression {
         function: String,
         args: Vec<Expression>,
     },
+    AddressOf(Box<Expression>),
+    Deref(Box<Expression>),
+    Array(Vec<Expression>),
+    Index {
+        array: Box<Expression>,
+        index: Box<Expression>,
+    },
+    Struct {
+        name: String,
+        fields: Vec<(String, Expression)>,
+    },
+    Field {
+        base: Box<Expression>,
+        field: String,
+    },
+    Variant {
+        name: String,
+        variant: String,
+        args: Vec<Expression>,
+    },
+    MethodCall {
+        receiver: Box<Expression>,
+        method: String,
+        args: Vec<Expression>,
+    },
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -73,38 +105,141 @@ pub struct FunctionArgs {
     pub annotation: String,
 }
 
+#[derive(Debug, PartialEq, Clone)]
+pub struct EnumVariant {
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Pattern {
+    Wildcard,
+    Variant {
+        name: String,
+        variant: String,
+        bindings: Vec<String>,
+    },
+    Range {
+        start: i128,
+        end: i128,
+        inclusive: bool,
+    },
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct GenericParam {
+    pub name: String,
+    pub bounds: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MethodSignature {
+    pub name: String,
+    pub args: Vec<FunctionArgs>,
+    pub return_type: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub body: Vec<Statement>,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Statement {
-    Expression(Expression),
+    Expression(Expression, Loc),
     Invite {
         name: String,
+        args: Option<Vec<FunctionArgs>>,
+        variadic: bool,
+        return_type: Option<String>,
+        loc: Loc,
     },
     Eternal {
         name: String,
         annotation: Option<String>,
+        loc: Loc,
     },
     Vow {
         name: String,
         annotation: Option<String>,
+        loc: Loc,
     },
     Assignment {
         name: String,
         value: Expression,
+        loc: Loc,
+    },
+    Store {
+        target: Expression,
+        value: Expression,
+        loc: Loc,
     },
     Foreseen {
         condition: Expression,
         then_branch: Vec<Statement>,
         else_branch: Option<Vec<Statement>>,
+        loc: Loc,
     },
     Until {
         condition: Expression,
         body: Vec<Statement>,
+        loc: Loc,
+    },
+    Struct {
+        name: String,
+        fields: Vec<FunctionArgs>,
+        loc: Loc,
+    },
+    Enum {
+        name: String,
+        variants: Vec<EnumVariant>,
+        loc: Loc,
+    },
+    Match {
+        value: Expression,
+        arms: Vec<MatchArm>,
+        loc: Loc,
     },
     SpellCard {
         name: String,
+        generics: Vec<GenericParam>,
         args: Vec<FunctionArgs>,
         return_type: Option<String>,
         body: Vec<Statement>,
+        loc: Loc,
+    },
+    Offer(Option<Expression>, Loc),
+    Trait {
+        name: String,
+        methods: Vec<MethodSignature>,
+        loc: Loc,
     },
-    Offer(Option<Expression>),
+    Impl {
+        ty: String,
+        trait_name: Option<String>,
+        methods: Vec<Statement>,
+        loc: Loc,
+    },
+}
+
+impl Statement {
+    pub fn loc(&self) -> Loc {
+        match self {
+            Statement::Expression(_, loc) | Statement::Offer(_, loc) => *loc,
+            Statement::Invite { loc, .. }
+            | Statement::Eternal { loc, .. }
+            | Statement::Vow { loc, .. }
+            | Statement::Assignment { loc, .. }
+            | Statement::Store { loc, .. }
+            | Statement::Foreseen { loc, .. }
+            | Statement::Until { loc, .. }
+            | Statement::Struct { loc, .. }
+            | Statement::Enum { loc, .. }
+            | Statement::Match { loc, .. }
+            | Statement::SpellCard { loc, .. }
+            | Statement::Trait { loc, .. }
+            | Statement::Impl { loc, .. } => *loc,
+        }
+    }
 }
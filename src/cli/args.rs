@@ -25,10 +25,78 @@ pub enum Command {
         #[arg(short, long)]
         src: String,
 
+        #[arg(
+            short,
+            long = "library",
+            help = "Shared library to resolve the invited symbol from"
+        )]
+        library: Vec<String>,
+
+        #[arg(
+            long,
+            help = "Print every executed instruction with its operand and result to stderr"
+        )]
+        trace: bool,
+
+        #[arg(
+            long,
+            help = "Print every spellcard entry and exit with its argument and return value to stderr"
+        )]
+        trace_calls: bool,
+
+        #[arg(long, help = "Maximum number of instruction to execute")]
+        fuel: Option<u64>,
+
+        #[arg(long, help = "Maximum number of spellcard call alive at the same time")]
+        max_call_depth: Option<usize>,
+
+        #[arg(long, help = "Maximum size in bytes of the data and heap segment")]
+        max_memory: Option<usize>,
+
+        #[arg(
+            long,
+            help = "Only allow the invited symbol given by --allow to be called"
+        )]
+        sandbox: bool,
+
+        #[arg(long, help = "Invited symbol the program may call, imply --sandbox")]
+        allow: Vec<String>,
+
+        #[arg(
+            short = 'g',
+            long,
+            help = "Compile with debug info and runtime check such as array bounds"
+        )]
+        debug: bool,
+
         #[arg(short, long, help = "increase verbosity of output")]
         verbose: bool,
     },
 
+    #[command(
+        about = "Start interactive session",
+        long_about = "Start interactive session that evaluate each input inside a VM"
+    )]
+    Repl,
+
+    #[command(
+        about = "Debug file inside a VM",
+        long_about = "Run file inside a VM with line breakpoint, stepping and local inspection"
+    )]
+    Debug {
+        #[arg(required = true)]
+        src: PathBuf,
+    },
+
+    #[command(
+        about = "Disassemble bytecode file",
+        long_about = "Print the header, data, import table and instruction of the compiled bytecode file"
+    )]
+    Disasm {
+        #[arg(required = true)]
+        src: PathBuf,
+    },
+
     #[command(
         name = "cc",
         about = "Compile file in specified architecture",
@@ -53,6 +121,13 @@ pub enum Command {
         #[arg(short = 'c', long, help = "Compile to object file only")]
         compile_and_assemble_only: bool,
 
+        #[arg(
+            short = 'g',
+            long,
+            help = "Compile with debug info and runtime check such as array bounds"
+        )]
+        debug: bool,
+
         #[arg(short, long, help = "increase verbosity of output")]
         verbose: bool,
 
@@ -37,13 +37,17 @@ fn print_cmd(cmd: &Command) {
     );
 }
 
-pub fn build_ast(src_code: String) -> Result<(Vec<Op>, Compiler), Box<dyn Error>> {
+pub fn build_ast(src_code: String, debug: bool) -> Result<(Vec<Op>, Compiler), Box<dyn Error>> {
     let chars = src_code.as_str().chars().collect::<Vec<_>>();
     let lexer = Lexer::new(&chars);
     let mut parser = RemiParser::new(lexer);
     let ast = parser.parse()?;
     let mut compiler = Compiler::new();
+    compiler.debug = debug;
     let stmt = compiler.compile(ast)?;
+    for warning in compiler.warnings.iter() {
+        eprintln!("Warning: {}", warning);
+    }
 
     Ok((stmt, compiler))
 }
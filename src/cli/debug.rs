@@ -0,0 +1,468 @@
+use std::{
+    collections::BTreeSet,
+    error::Error,
+    io::{BufRead, Write},
+};
+
+use crate::{
+    commons::Loc,
+    compiler::{Compiler, Scope, Type},
+    lexer::Lexer,
+    parser::parser::Parser as RemiParser,
+    value::Value,
+    vm::{Program, VM, VMError},
+};
+
+const HELP: &str = "\
+break <line>   set a breakpoint on the statement at line
+delete <line>  remove the breakpoint at line
+run            start the program from the beginning
+continue       run until the next breakpoint
+step           execute until the next statement, entering spellcard
+next           execute until the next statement in the current spellcard
+finish         execute until the current spellcard offer
+print <name>   show the value of a local
+locals         show every local of the current frame
+backtrace      show the call stack
+list           show the source around the current line
+help           show this message
+quit           exit the debugger";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Step {
+    Continue,
+    Into,
+    Over,
+    Out,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    NotStarted,
+    Global,
+    Main,
+    Exited,
+}
+
+pub struct Debugger {
+    source: Vec<String>,
+    compiler: Compiler,
+    vm: VM,
+    breakpoints: BTreeSet<usize>,
+    state: State,
+}
+
+impl Debugger {
+    pub fn new(src: &str) -> Result<Self, Box<dyn Error>> {
+        let chars = src.chars().collect::<Vec<_>>();
+        let ast = RemiParser::new(Lexer::new(&chars)).parse()?;
+        let mut compiler = Compiler::new();
+        compiler.debug = true;
+        let ops = compiler.compile(ast)?;
+        let program = Program::new(&compiler, ops)?;
+
+        Ok(Self {
+            source: src.lines().map(|line| line.to_owned()).collect(),
+            compiler,
+            vm: VM::new(program),
+            breakpoints: BTreeSet::new(),
+            state: State::NotStarted,
+        })
+    }
+
+    fn running(&self) -> bool {
+        matches!(self.state, State::Global | State::Main)
+    }
+
+    fn current(&self) -> Option<Loc> {
+        if !self.running() {
+            return None;
+        }
+        self.vm.program().line(self.vm.pc())
+    }
+
+    fn function_name(&self, function: Option<usize>) -> &str {
+        match function {
+            Some(idx) => &self.vm.program().functions[idx].name,
+            None => "__global",
+        }
+    }
+
+    fn start(&mut self) -> Result<(), VMError> {
+        self.vm.start()?;
+        self.state = State::Global;
+        Ok(())
+    }
+
+    fn resume(&mut self, mut step: Step) -> Result<Option<u64>, VMError> {
+        let mut depth = self.vm.depth();
+        loop {
+            let value = match self.vm.step() {
+                Ok(value) => value,
+                Err(err) => {
+                    self.state = State::Exited;
+                    return Err(err);
+                }
+            };
+            if let Some(value) = value {
+                match self.state {
+                    State::Global if self.vm.program().function("main").is_some() => {
+                        self.vm.start_call("main", &[])?;
+                        self.state = State::Main;
+                        depth = self.vm.depth();
+                        if step != Step::Continue {
+                            step = Step::Into;
+                        }
+                    }
+                    _ => {
+                        self.state = State::Exited;
+                        return Ok(Some(value));
+                    }
+                }
+            }
+
+            let Some(loc) = self.vm.program().line(self.vm.pc()) else {
+                continue;
+            };
+            let stop = self.breakpoints.contains(&loc.row)
+                || match step {
+                    Step::Continue => false,
+                    Step::Into => true,
+                    Step::Over => self.vm.depth() <= depth,
+                    Step::Out => self.vm.depth() < depth,
+                };
+            if stop {
+                return Ok(None);
+            }
+        }
+    }
+
+    fn stopped(&self, value: Option<u64>) -> String {
+        match value {
+            Some(code) => format!("Program exited with code {}", code as i64),
+            None => self.position(),
+        }
+    }
+
+    fn position(&self) -> String {
+        let Some(loc) = self.current() else {
+            return "The program is not being run".to_owned();
+        };
+        let frame = self.vm.backtrace().remove(0);
+        format!(
+            "{}() at line {}\n{:>4}    {}",
+            self.function_name(frame.function),
+            loc.row,
+            loc.row,
+            self.source.get(loc.row - 1).map_or("", |line| line.trim())
+        )
+    }
+
+    fn scope(&self) -> Option<&Scope> {
+        let frame = self.vm.backtrace().remove(0);
+        let name = self.function_name(frame.function);
+        self.compiler.spellcard_scope.get(name)
+    }
+
+    fn locals(&self) -> Vec<(&str, usize)> {
+        self.scope()
+            .map(|scope| scope.locals())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(name, _)| !name.starts_with("__"))
+            .collect()
+    }
+
+    fn local(&self, idx: usize) -> Result<String, VMError> {
+        let raw = self.vm.local(self.vm.depth() - 1, idx)?;
+        let ty = self.scope().map_or(Type::I32, |scope| scope.slot_type(idx));
+        let value = match ty {
+            Type::CStr => {
+                let str = self.vm.memory().read_cstr(raw)?;
+                Some(Value::String(String::from_utf8_lossy(str).into_owned()))
+            }
+            ref ty => ty.value(raw),
+        };
+        Ok(value.map_or(format!("<{}>", ty), |value| value.pretty()))
+    }
+
+    fn print(&self, name: &str) -> Result<String, Box<dyn Error>> {
+        if !self.running() {
+            return Ok("The program is not being run".to_owned());
+        }
+        match self.locals().into_iter().find(|(local, _)| *local == name) {
+            Some((_, idx)) => Ok(format!("{} = {}", name, self.local(idx)?)),
+            None => Ok(format!("No local named {} in the current frame", name)),
+        }
+    }
+
+    fn backtrace(&self) -> String {
+        if !self.running() {
+            return "The program is not being run".to_owned();
+        }
+        self.vm
+            .backtrace()
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| {
+                let name = self.function_name(frame.function);
+                match self
+                    .vm
+                    .program()
+                    .lines
+                    .iter()
+                    .rev()
+                    .find(|(pc, _)| *pc <= frame.pc)
+                {
+                    Some((_, loc)) => format!("#{} {}() at line {}", i, name, loc.row),
+                    None => format!("#{} {}()", i, name),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn list(&self) -> String {
+        let row = self.current().map_or(1, |loc| loc.row);
+        let start = row.saturating_sub(5).max(1);
+        let end = (row + 5).min(self.source.len());
+        (start..=end)
+            .map(|i| {
+                let marker = match (
+                    self.current().is_some() && i == row,
+                    self.breakpoints.contains(&i),
+                ) {
+                    (true, _) => ">",
+                    (false, true) => "*",
+                    (false, false) => " ",
+                };
+                format!("{} {:>4}    {}", marker, i, self.source[i - 1])
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn execute(&mut self, command: &str) -> Result<String, Box<dyn Error>> {
+        let mut words = command.split_whitespace();
+        let Some(name) = words.next() else {
+            return Ok(String::new());
+        };
+        let arg = words.next();
+        let line = arg.map(|line| line.parse::<usize>());
+
+        match name {
+            "break" | "b" => match line {
+                Some(Ok(line)) if self.vm.program().lines.iter().any(|(_, l)| l.row == line) => {
+                    self.breakpoints.insert(line);
+                    Ok(format!("Breakpoint at line {}", line))
+                }
+                Some(Ok(line)) => Ok(format!("No statement at line {}", line)),
+                _ => Ok("Usage: break <line>".to_owned()),
+            },
+            "delete" | "d" => match line {
+                Some(Ok(line)) if self.breakpoints.remove(&line) => {
+                    Ok(format!("Deleted breakpoint at line {}", line))
+                }
+                Some(Ok(line)) => Ok(format!("No breakpoint at line {}", line)),
+                _ => Ok("Usage: delete <line>".to_owned()),
+            },
+            "run" | "r" => {
+                self.start()?;
+                let value = match self.current() {
+                    Some(loc) if self.breakpoints.contains(&loc.row) => None,
+                    _ => self.resume(Step::Continue)?,
+                };
+                Ok(self.stopped(value))
+            }
+            "continue" | "c" => {
+                if !self.running() {
+                    return Ok("The program is not being run".to_owned());
+                }
+                let value = self.resume(Step::Continue)?;
+                Ok(self.stopped(value))
+            }
+            "step" | "s" | "next" | "n" | "finish" | "f" => {
+                let step = match name {
+                    "step" | "s" => Step::Into,
+                    "next" | "n" => Step::Over,
+                    _ => Step::Out,
+                };
+                if self.state == State::Exited {
+                    return Ok("The program is not being run, use run to start it again".to_owned());
+                }
+                if !self.running() {
+                    self.start()?;
+                    if self.current().is_some() {
+                        return Ok(self.position());
+                    }
+                }
+                let value = self.resume(step)?;
+                Ok(self.stopped(value))
+            }
+            "print" | "p" => match arg {
+                Some(local) => self.print(local),
+                None => Ok("Usage: print <name>".to_owned()),
+            },
+            "locals" => {
+                if !self.running() {
+                    return Ok("The program is not being run".to_owned());
+                }
+                let mut buf = vec![];
+                for (name, idx) in self.locals() {
+                    buf.push(format!("{} = {}", name, self.local(idx)?));
+                }
+                Ok(buf.join("\n"))
+            }
+            "backtrace" | "bt" => Ok(self.backtrace()),
+            "list" | "l" => Ok(self.list()),
+            "help" | "h" => Ok(HELP.to_owned()),
+            _ => Ok(format!(
+                "Unknown command {}, type help for the list of command",
+                name
+            )),
+        }
+    }
+
+    pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        let stdin = std::io::stdin();
+        let mut lines = stdin.lock().lines();
+        let mut stdout = std::io::stdout();
+        println!("Remi debugger, type help for the list of command");
+
+        loop {
+            print!("(remi) ");
+            stdout.flush()?;
+            let Some(line) = lines.next() else {
+                break;
+            };
+            let line = line?;
+            if matches!(line.trim(), "quit" | "q") {
+                break;
+            }
+            match self.execute(&line) {
+                Ok(out) if out.is_empty() => {}
+                Ok(out) => println!("{}", out),
+                Err(err) => eprintln!("Error: {}", err),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SRC: &str = "\
+spellcard add_number(a: i32, b: i32) i32 {
+    vow c = a + b;
+    offer c;
+}
+
+spellcard main() i32 {
+    vow a = 0;
+    until a < 3 {
+        a = a + 1;
+    }
+    vow b = add_number(a, 66);
+    offer b;
+}";
+
+    #[test]
+    fn stop_on_breakpoint() {
+        let mut debugger = Debugger::new(SRC).unwrap();
+        debugger.execute("break 9").unwrap();
+        debugger.execute("run").unwrap();
+        assert_eq!(debugger.current().unwrap().row, 9);
+        assert_eq!(debugger.execute("print a").unwrap(), "a = 0");
+
+        debugger.execute("continue").unwrap();
+        assert_eq!(debugger.execute("print a").unwrap(), "a = 1");
+
+        debugger.execute("delete 9").unwrap();
+        assert_eq!(
+            debugger.execute("continue").unwrap(),
+            "Program exited with code 69"
+        );
+    }
+
+    #[test]
+    fn step_into_over_and_out() {
+        let mut debugger = Debugger::new(SRC).unwrap();
+        debugger.execute("break 11").unwrap();
+        debugger.execute("run").unwrap();
+
+        debugger.execute("step").unwrap();
+        assert_eq!(debugger.current().unwrap().row, 2);
+        assert_eq!(
+            debugger.execute("backtrace").unwrap(),
+            "#0 add_number() at line 2\n#1 main() at line 11\n#2 __global()"
+        );
+        assert_eq!(debugger.execute("locals").unwrap(), "a = 3\nb = 66\nc = 0");
+
+        debugger.execute("finish").unwrap();
+        assert_eq!(debugger.current().unwrap().row, 12);
+        assert_eq!(debugger.execute("print b").unwrap(), "b = 69");
+
+        let mut debugger = Debugger::new(SRC).unwrap();
+        debugger.execute("break 11").unwrap();
+        debugger.execute("run").unwrap();
+        debugger.execute("next").unwrap();
+        assert_eq!(debugger.current().unwrap().row, 12);
+    }
+
+    #[test]
+    fn step_through_loop() {
+        let mut debugger = Debugger::new(SRC).unwrap();
+        let mut rows = vec![];
+        debugger.execute("step").unwrap();
+        while let Some(loc) = debugger.current() {
+            rows.push(loc.row);
+            debugger.execute("next").unwrap();
+        }
+        assert_eq!(rows, vec![7, 8, 9, 8, 9, 8, 9, 8, 11, 12]);
+    }
+
+    #[test]
+    fn step_past_the_end() {
+        let mut debugger = Debugger::new(SRC).unwrap();
+        debugger.execute("break 12").unwrap();
+        debugger.execute("run").unwrap();
+        assert_eq!(
+            debugger.execute("next").unwrap(),
+            "Program exited with code 69"
+        );
+        for command in ["step", "next", "finish"] {
+            assert_eq!(
+                debugger.execute(command).unwrap(),
+                "The program is not being run, use run to start it again"
+            );
+            assert!(debugger.current().is_none());
+        }
+
+        debugger.execute("run").unwrap();
+        assert_eq!(debugger.current().unwrap().row, 12);
+    }
+
+    #[test]
+    fn inspect_typed_locals() {
+        let src = "\
+spellcard main() i32 {
+    vow ratio = 1.5;
+    vow half: f32 = 0.25;
+    vow ok = 2 > 1;
+    vow big: u64 = 18446744073709551615;
+    vow name = \"Remilia\";
+    offer 0;
+}";
+        let mut debugger = Debugger::new(src).unwrap();
+        debugger.execute("break 7").unwrap();
+        debugger.execute("run").unwrap();
+        assert_eq!(debugger.execute("print ratio").unwrap(), "ratio = 1.5");
+        assert_eq!(debugger.execute("print ok").unwrap(), "ok = true");
+        assert_eq!(
+            debugger.execute("locals").unwrap(),
+            "ratio = 1.5\nhalf = 0.25\nok = true\nbig = 18446744073709551615\nname = \"Remilia\""
+        );
+    }
+}
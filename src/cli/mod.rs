@@ -6,6 +6,7 @@ use crate::{
     compiler::Compiler,
     op::Op,
     target::Target,
+    vm::{EXTENSION, Limits, Program, Tracer, VM},
 };
 
 use super::cli::args::Args;
@@ -19,6 +20,8 @@ use std::{
 
 mod args;
 mod build;
+mod debug;
+mod repl;
 
 pub struct CLI {
     args: Args,
@@ -47,7 +50,64 @@ impl CLI {
 
     pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
         match self.args.command.clone() {
-            args::Command::Run { .. } => todo!(),
+            args::Command::Run {
+                src,
+                library,
+                trace,
+                trace_calls,
+                fuel,
+                max_call_depth,
+                max_memory,
+                sandbox,
+                allow,
+                debug,
+                ..
+            } => {
+                let mut buf = vec![];
+                let mut file = File::open(&src)?;
+                file.read_to_end(&mut buf)?;
+
+                let program = if Program::is_bytecode(&buf) {
+                    Program::decode(&buf)?
+                } else {
+                    let (op, compiler) = build_ast(String::from_utf8(buf)?, debug)?;
+                    Program::new(&compiler, op)?
+                };
+                let limits = Limits {
+                    fuel,
+                    max_call_depth,
+                    max_memory,
+                    allowed_imports: (sandbox || !allow.is_empty()).then_some(allow),
+                };
+                let mut vm = VM::with_limits(program, limits)?;
+                for library in library.iter() {
+                    vm.load_library(library)?;
+                }
+                if trace || trace_calls {
+                    vm.set_tracer(Tracer::stderr(trace, trace_calls));
+                }
+                if let Some(code) = vm.run()? {
+                    std::process::exit(code as i32);
+                }
+                Ok(())
+            }
+            args::Command::Repl => repl::Repl::new().run(),
+            args::Command::Debug { src } => {
+                let mut buf = String::new();
+                let mut file = File::open(&src)?;
+                file.read_to_string(&mut buf)?;
+
+                debug::Debugger::new(&buf)?.run()
+            }
+            args::Command::Disasm { src } => {
+                let mut buf = vec![];
+                let mut file = File::open(&src)?;
+                file.read_to_end(&mut buf)?;
+
+                let program = Program::decode(&buf)?;
+                println!("{}", program.disassemble());
+                Ok(())
+            }
             args::Command::Compile {
                 target,
                 out,
@@ -57,6 +117,7 @@ impl CLI {
                 compile_only,
                 compile_and_assemble_only,
                 dump,
+                debug,
             } => {
                 let src_file = src
                     .iter()
@@ -71,9 +132,10 @@ impl CLI {
 
                 let ast = src_file
                     .iter()
-                    .map(|(code, path)| (build_ast(code.clone()).unwrap(), path))
+                    .map(|(code, path)| (build_ast(code.clone(), debug).unwrap(), path))
                     .collect::<Vec<_>>();
 
+                let out_arg = out.clone();
                 let out = out.unwrap_or(String::from("a.out"));
                 let mut obj_temp = vec![];
                 let mut asm_temp = vec![];
@@ -121,7 +183,18 @@ impl CLI {
                                 }
                             }
                         }
-                        Target::Bytecode => todo!(),
+                        Target::Bytecode => {
+                            let single = ast.len() == 1;
+                            for ((op, compiler), original_path) in ast {
+                                let bytecode_file = match &out_arg {
+                                    Some(out) if single => PathBuf::from(out),
+                                    _ => original_path.with_extension(EXTENSION),
+                                };
+                                let program = Program::new(&compiler, op)?;
+                                let mut file = File::create(&bytecode_file)?;
+                                file.write_all(&program.encode())?;
+                            }
+                        }
                     },
                     None => {
                         #[cfg(target_os = "windows")]
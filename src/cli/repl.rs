@@ -0,0 +1,313 @@
+use std::{
+    error::Error,
+    io::{BufRead, Write},
+};
+
+use crate::{
+    ast::{Expression, Statement},
+    commons::Loc,
+    compiler::{Compiler, Type},
+    lexer::Lexer,
+    op::Op,
+    parser::parser::Parser as RemiParser,
+    value::Value,
+    vm::{Program, VM},
+};
+
+const HELP: &str = "\
+:ast   show the AST of the last input
+:ir    show the lowered op of the last input
+:help  show this message
+:quit  exit the repl";
+
+pub struct Repl {
+    compiler: Compiler,
+    vm: VM,
+    invites: Vec<Op>,
+    spellcards: Vec<(String, Vec<Op>)>,
+    last_ast: Vec<Statement>,
+    last_ops: Vec<Op>,
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Self {
+            compiler: Compiler::new(),
+            vm: VM::new(Program::default()),
+            invites: vec![],
+            spellcards: vec![],
+            last_ast: vec![],
+            last_ops: vec![],
+        }
+    }
+
+    fn parse(src: &str) -> Result<Vec<Statement>, Box<dyn Error>> {
+        let chars = src.chars().collect::<Vec<_>>();
+        let ast = RemiParser::new(Lexer::new(&chars)).parse();
+        match ast {
+            Ok(ast) => Ok(ast),
+            Err(err) => {
+                let expr = RemiParser::new(Lexer::new(&chars)).parse_expression();
+                match expr {
+                    Ok(expr) => Ok(vec![Statement::Expression(expr, Loc::new(1, 1))]),
+                    Err(_) => Err(Box::new(err)),
+                }
+            }
+        }
+    }
+
+    fn load(
+        &mut self,
+        declaration: Vec<Statement>,
+        statement: Vec<Statement>,
+    ) -> Result<(), Box<dyn Error>> {
+        let declaration = self.compiler.compile(declaration)?;
+        let mut statement = self.compiler.compile(statement)?;
+        self.last_ops = declaration.clone();
+        self.last_ops.extend(statement.iter().cloned());
+
+        // NOTE : Generic instance is kept like a declaration so later input can call it
+        let instances = match statement
+            .iter()
+            .position(|op| matches!(op, Op::Function(_)))
+        {
+            Some(idx) => statement.split_off(idx),
+            None => vec![],
+        };
+        for op in declaration.into_iter().chain(instances) {
+            match op {
+                Op::Invite { .. } => self.invites.push(op),
+                Op::Function(name) => {
+                    self.spellcards.retain(|(spellcard, _)| *spellcard != name);
+                    self.spellcards
+                        .push((name.clone(), vec![Op::Function(name)]));
+                }
+                op => match self.spellcards.last_mut() {
+                    Some((_, body)) => body.push(op),
+                    None => unreachable!("Declaration only produce invite and spellcard"),
+                },
+            }
+        }
+
+        let mut ops = statement;
+        ops.extend(self.invites.iter().cloned());
+        for (_, body) in self.spellcards.iter() {
+            ops.extend(body.iter().cloned());
+        }
+        let program = Program::new(&self.compiler, ops)?;
+        self.vm.load(program)?;
+        Ok(())
+    }
+
+    pub fn eval(&mut self, src: &str) -> Result<Option<Value>, Box<dyn Error>> {
+        let ast = Self::parse(src)?;
+        self.last_ast = ast.clone();
+
+        let (declaration, mut statement): (Vec<_>, Vec<_>) = ast.into_iter().partition(|stmt| {
+            matches!(
+                stmt,
+                Statement::SpellCard { .. } | Statement::Invite { .. } | Statement::Impl { .. }
+            )
+        });
+
+        let mut print = false;
+        if let Some(Statement::Expression(expr, _)) = statement.last() {
+            print = match expr {
+                Expression::Call { function, .. } => self
+                    .compiler
+                    .spellcard
+                    .get(function)
+                    .is_none_or(|f| f.return_type != "void"),
+                _ => true,
+            };
+            if let Some(Statement::Expression(expr, loc)) = statement.pop() {
+                statement.push(Statement::Offer(Some(expr), loc));
+            }
+        }
+
+        // NOTE : Restore the compiler so a failed input leave no symbol the VM never loaded
+        let snapshot = (
+            self.compiler.clone(),
+            self.invites.clone(),
+            self.spellcards.clone(),
+        );
+        if let Err(err) = self.load(declaration, statement) {
+            (self.compiler, self.invites, self.spellcards) = snapshot;
+            return Err(err);
+        }
+        let raw = self.vm.eval()?;
+        if !print {
+            return Ok(None);
+        }
+
+        Ok(match self.compiler.checker.global_offer() {
+            Some(Type::CStr) => {
+                let str = self.vm.memory().read_cstr(raw)?;
+                Some(Value::String(String::from_utf8_lossy(str).into_owned()))
+            }
+            Some(ty) => ty.value(raw),
+            None => None,
+        })
+    }
+
+    pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        let stdin = std::io::stdin();
+        let mut lines = stdin.lock().lines();
+        let mut stdout = std::io::stdout();
+        println!("Remi REPL, type :help for the list of command");
+
+        loop {
+            print!("remi> ");
+            stdout.flush()?;
+            let Some(line) = lines.next() else {
+                break;
+            };
+            let mut src = line?;
+            match src.trim() {
+                "" => continue,
+                ":quit" | ":q" => break,
+                ":help" => {
+                    println!("{}", HELP);
+                    continue;
+                }
+                ":ast" => {
+                    for stmt in self.last_ast.iter() {
+                        println!("{:#?}", stmt);
+                    }
+                    continue;
+                }
+                ":ir" => {
+                    for op in self.last_ops.iter() {
+                        println!("{}", op);
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+
+            // NOTE : Keep reading until every block is closed
+            while src.matches('{').count() > src.matches('}').count() {
+                print!("...   ");
+                stdout.flush()?;
+                let Some(line) = lines.next() else {
+                    break;
+                };
+                src.push('\n');
+                src.push_str(&line?);
+            }
+
+            match self.eval(&src) {
+                Ok(Some(value)) => println!("{}", value.pretty()),
+                Ok(None) => {}
+                Err(err) => eprintln!("Error: {}", err),
+            }
+            for warning in self.compiler.warnings.drain(..) {
+                eprintln!("Warning: {}", warning);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{bool, i32, string};
+
+    #[test]
+    fn eval_expression() {
+        let mut repl = Repl::new();
+        assert_eq!(repl.eval("35 + 34").unwrap(), Some(i32!(69)));
+        assert_eq!(repl.eval("2 * (12 + 4);").unwrap(), Some(i32!(32)));
+    }
+
+    #[test]
+    fn eval_expression_by_type() {
+        let mut repl = Repl::new();
+        assert_eq!(repl.eval("3.5 * 2.0").unwrap(), Some(Value::F64(7.0)));
+        assert_eq!(repl.eval("vow x: f32 = 1.5;").unwrap(), None);
+        assert_eq!(repl.eval("x").unwrap(), Some(Value::F32(1.5)));
+        assert_eq!(repl.eval("4 > 3").unwrap(), Some(bool!(true)));
+        assert_eq!(
+            repl.eval("\"Remilia\"").unwrap(),
+            Some(string!("Remilia".to_owned()))
+        );
+        assert_eq!(
+            repl.eval("vow big: u64 = 18446744073709551615;").unwrap(),
+            None
+        );
+        assert_eq!(repl.eval("big").unwrap(), Some(Value::U64(u64::MAX)));
+        assert_eq!(Value::F64(7.0).pretty(), "7.0");
+        assert_eq!(bool!(true).pretty(), "true");
+    }
+
+    #[test]
+    fn keep_global_across_input() {
+        let mut repl = Repl::new();
+        assert_eq!(repl.eval("vow a = 60;").unwrap(), None);
+        assert_eq!(repl.eval("vow b = \"Remilia\";").unwrap(), None);
+        assert_eq!(repl.eval("a = a + 9;").unwrap(), None);
+        assert_eq!(repl.eval("a").unwrap(), Some(i32!(69)));
+    }
+
+    #[test]
+    fn define_and_call_spellcard() {
+        let mut repl = Repl::new();
+        let src = "
+spellcard add_number(a: i32, b: i32) i32 {
+    offer a + b;
+}";
+        assert_eq!(repl.eval(src).unwrap(), None);
+        assert_eq!(repl.eval("vow a = add_number(1, 2);").unwrap(), None);
+        assert_eq!(repl.eval("add_number(a, 66)").unwrap(), Some(i32!(69)));
+    }
+
+    #[test]
+    fn keep_generic_instance_across_input() {
+        let mut repl = Repl::new();
+        let src = "
+spellcard max<T>(a: T, b: T) T {
+    foreseen a > b {
+        offer a;
+    }
+    offer b;
+}";
+        assert_eq!(repl.eval(src).unwrap(), None);
+        assert_eq!(repl.eval("vow a = max(60, 9);").unwrap(), None);
+        assert_eq!(repl.eval("max(a, 69)").unwrap(), Some(i32!(69)));
+    }
+
+    #[test]
+    fn recover_from_error() {
+        let mut repl = Repl::new();
+        repl.eval("vow a = 69;").unwrap();
+        assert!(repl.eval("b = 1;").is_err());
+        assert_eq!(repl.eval("a").unwrap(), Some(i32!(69)));
+
+        assert!(
+            repl.eval("spellcard f() i32 { offer 1; } vow z = nope;")
+                .is_err()
+        );
+        assert!(!repl.compiler.spellcard.contains_key("f"));
+        assert_eq!(repl.eval("spellcard f() i32 { offer 2; }").unwrap(), None);
+        assert_eq!(repl.eval("f()").unwrap(), Some(i32!(2)));
+    }
+
+    #[test]
+    fn recover_from_load_error() {
+        let mut repl = Repl::new();
+        assert_eq!(repl.eval("struct P { x: i32, y: i32 }").unwrap(), None);
+        assert!(repl.eval("invite make() P; vow q = 69;").is_err());
+        assert!(!repl.compiler.spellcard.contains_key("make"));
+        assert!(repl.invites.is_empty());
+        assert!(repl.eval("q").is_err());
+        assert_eq!(repl.eval("vow q = 60;").unwrap(), None);
+        assert_eq!(repl.eval("q + 9").unwrap(), Some(i32!(69)));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{compiler::Compiler, op::Arg};
+use crate::{compiler::Compiler, op::Arg, value::Value};
 
 use super::{Codegen, CodegenError};
 
@@ -39,11 +39,13 @@ impl Codegen for IRCodegen {
                 )),
                 crate::op::Op::BinOp {
                     binop,
+                    ty,
                     offset,
                     lhs,
                     rhs,
                 } => body.push(format!(
-                    "        BinOp({:#04x} {} {} {})",
+                    "        BinOp<{}>({:#04x} {} {} {})",
+                    ty,
                     offset,
                     dump_args(&lhs),
                     binop,
@@ -56,7 +58,9 @@ impl Codegen for IRCodegen {
                 )),
                 crate::op::Op::Function(name) => body.push(format!("    {}():", name)),
                 crate::op::Op::Label(name) => body.push(format!("    {}:", name)),
-                crate::op::Op::Call { result, name, args } => {
+                crate::op::Op::Call {
+                    result, name, args, ..
+                } => {
                     let args = args.iter().map(dump_args).collect::<Vec<_>>().join(", ");
                     body.push(format!(
                         "        Call({:#04x}, {}, [{}])",
@@ -71,15 +75,50 @@ impl Codegen for IRCodegen {
                 crate::op::Op::JmpIfNot { name, arg } => {
                     body.push(format!("        Jne({}, {})", name, dump_args(&arg)))
                 }
+                crate::op::Op::AddrOf { offset, local } => body.push(format!(
+                    "        AddrOf({:#04x}, Local({:#04x}))",
+                    offset, local
+                )),
+                crate::op::Op::Load { offset, ty, ptr } => body.push(format!(
+                    "        Load<{}>({:#04x}, {})",
+                    ty,
+                    offset,
+                    dump_args(&ptr)
+                )),
+                crate::op::Op::Store { ty, ptr, arg } => body.push(format!(
+                    "        Store<{}>({}, {})",
+                    ty,
+                    dump_args(&ptr),
+                    dump_args(&arg)
+                )),
+                crate::op::Op::Element {
+                    offset,
+                    base,
+                    index,
+                    size,
+                } => body.push(format!(
+                    "        Element({:#04x}, {} + {} * {})",
+                    offset,
+                    dump_args(&base),
+                    dump_args(&index),
+                    size
+                )),
+                crate::op::Op::BoundsCheck { index, length } => body.push(format!(
+                    "        BoundsCheck({} < {})",
+                    dump_args(&index),
+                    length
+                )),
+                crate::op::Op::Loc(loc) => body.push(format!("    ; {}", loc)),
             }
         }
         Ok(body.join("\n"))
     }
 }
 
-fn dump_args(arg: &Arg) -> String {
+pub fn dump_args(arg: &Arg) -> String {
     match arg {
         Arg::Local(offset) => format!("Local({:#04x})", offset),
+        Arg::Literal(Value::Bool(bool)) => format!("Literal({})", bool),
         Arg::Literal(value) => format!("Literal({})", value.str()),
         Arg::DataOffset(offset) => format!("DataOffset({:#04x})", offset),
     }
@@ -1,4 +1,4 @@
-use crate::compiler::Compiler;
+use crate::{compiler::Compiler, value::Value};
 
 use super::{Codegen, CodegenError};
 
@@ -62,7 +62,7 @@ impl Codegen for JavascriptCodegen {
                         code.push(format!("    _{} = _{}", offset, local));
                     }
                     crate::op::Arg::Literal(value) => {
-                        code.push(format!("    _{} = {}", offset, value.str()));
+                        code.push(format!("    _{} = {}", offset, literal(&value)));
                     }
                     crate::op::Arg::DataOffset(offset) => {
                         code.push(format!("    _{} = readString({})", offset, offset))
@@ -72,7 +72,7 @@ impl Codegen for JavascriptCodegen {
                 crate::op::Op::Call { name, args, .. } => {
                     let mapped = args.iter().map(|a| match a {
                         crate::op::Arg::Local(offset) => format!("_{}", offset),
-                        crate::op::Arg::Literal(value) => format!("{}", value.str()),
+                        crate::op::Arg::Literal(value) => literal(value),
                         crate::op::Arg::DataOffset(offset) => {
                             format!("readString({})", offset)
                         }
@@ -85,8 +85,9 @@ impl Codegen for JavascriptCodegen {
                     code.push(format!("    {}({});", name, arg));
                 }
                 crate::op::Op::Ret(_arg) => {}
+                crate::op::Op::Loc(_loc) => {}
                 other => Err(CodegenError::Unsupported {
-                    op: other,
+                    op: Box::new(other),
                     message: format!("Not supported"),
                 })?,
             }
@@ -95,3 +96,10 @@ impl Codegen for JavascriptCodegen {
         Ok(code.join("\n"))
     }
 }
+
+fn literal(value: &Value) -> String {
+    match value {
+        Value::Bool(bool) => bool.to_string(),
+        value => value.str(),
+    }
+}
@@ -1,13 +1,22 @@
 use crate::{
-    compiler::Compiler,
+    compiler::{Compiler, Signature, Type},
     op::{Arg, Op},
 };
 
-use super::{Codegen, CodegenError, utils::align_mem};
+use super::{
+    Codegen, CodegenError,
+    utils::{
+        Location, align_mem, bounds_check_rax, element_rax, extend_rax, float_binop, immediate,
+        load_rax, promote_rax, read_return, store_rcx,
+    },
+};
 
 pub struct LinuxX86_64;
 
 const REGISTER: [&str; 6] = ["rdi", "rsi", "rdx", "rcx", "r8", "r9"];
+const FLOAT_REGISTER: [&str; 8] = [
+    "xmm0", "xmm1", "xmm2", "xmm3", "xmm4", "xmm5", "xmm6", "xmm7",
+];
 
 impl LinuxX86_64 {
     pub fn new() -> Self {
@@ -45,6 +54,8 @@ impl Codegen for LinuxX86_64 {
         let mut code: Vec<String> = vec![];
         self.generate_prolog(&compiler, &mut code);
         let mut offset = 0;
+        let mut signature = None;
+        let mut params = vec![];
         for op in stmt {
             match op {
                 Op::StackAlloc(count) => {
@@ -62,11 +73,10 @@ impl Codegen for LinuxX86_64 {
                             code.push(format!("    mov rcx, [rbp-{}]", (id + 1) * 8));
                             code.push(format!("    mov [rbp-{}], rcx", (offset + 1) * 8));
                         }
-                        Arg::Literal(value) => code.push(format!(
-                            "    mov qword [rbp-{}], {}",
-                            (offset + 1) * 8,
-                            value.str()
-                        )),
+                        Arg::Literal(value) => {
+                            code.push(format!("    mov rcx, {}", immediate(&value)));
+                            code.push(format!("    mov qword [rbp-{}], rcx", (offset + 1) * 8));
+                        }
                         Arg::DataOffset(data) => {
                             code.push(format!("    mov rcx, eternal+{}", data));
                             code.push(format!("    mov qword [rbp-{}], rcx", (offset + 1) * 8));
@@ -76,6 +86,20 @@ impl Codegen for LinuxX86_64 {
                     code.push(format!(""));
                 }
                 Op::Function(name) => {
+                    signature = compiler.checker.function(&name);
+                    let types = signature
+                        .and_then(|s| s.params.as_deref())
+                        .unwrap_or(&[])
+                        .iter()
+                        .map(|ty| {
+                            if ty.is_aggregate() {
+                                Type::U64
+                            } else {
+                                ty.clone()
+                            }
+                        })
+                        .collect::<Vec<_>>();
+                    params = classify(&types).into_iter().flatten().collect();
                     offset = 0;
                     code.push(format!("{}:", name));
                     code.push(format!("    ; Prolog"));
@@ -85,51 +109,79 @@ impl Codegen for LinuxX86_64 {
                 Op::Label(name) => {
                     code.push(format!("{}:", name));
                 }
-                Op::Call { result, name, args } => {
+                Op::Call {
+                    result,
+                    name,
+                    args,
+                    types,
+                } => {
                     code.push(format!("    ; Calling"));
-                    let mut stack_size = 0;
-                    if args.len() > REGISTER.len() {
-                        let stack_count = args.len() - REGISTER.len();
-                        stack_size = align_mem(stack_count * 8);
+                    let fixed = compiler
+                        .checker
+                        .function(&name)
+                        .and_then(|s| s.params.as_ref())
+                        .map_or(0, Vec::len);
+                    let promote = types
+                        .iter()
+                        .enumerate()
+                        .map(|(i, ty)| i >= fixed && *ty == Type::F32)
+                        .collect::<Vec<_>>();
+                    let types = types
+                        .into_iter()
+                        .zip(promote.iter())
+                        .map(|(ty, promote)| if *promote { Type::F64 } else { ty })
+                        .collect::<Vec<_>>();
+                    let locations = classify(&types);
+
+                    let stack = locations
+                        .iter()
+                        .flatten()
+                        .filter(|l| matches!(l, Location::Stack(_)))
+                        .count();
+                    let stack_size = align_mem(stack * 8);
+                    if stack > 0 {
                         code.push(format!("    sub rsp, {}", stack_size));
-                        for (i, arg) in args.iter().skip(REGISTER.len()).enumerate() {
-                            match arg {
-                                Arg::Local(offset) => code.push(format!(
-                                    "    mov qword [rsp+{}], [rbp-{}]",
-                                    i * 8,
-                                    (offset + 1) * 8
-                                )),
-                                Arg::Literal(value) => code.push(format!(
-                                    "    mov qword [rsp+{}], {}",
-                                    i * 8,
-                                    value.str()
-                                )),
-                                Arg::DataOffset(offset) => code.push(format!(
-                                    "    mov qword [rsp+{}], eternal+{}",
-                                    i * 8,
-                                    offset
-                                )),
-                            }
-                        }
                     }
-                    for (reg, arg) in REGISTER.iter().zip(args.iter()) {
-                        match arg {
-                            Arg::Local(id) => {
-                                code.push(format!("    mov rax, [rbp-{}]", (id + 1) * 8));
-                                code.push(format!("    mov {}, rax", reg));
-                            }
-                            Arg::Literal(value) => {
-                                code.push(format!("    mov {}, {}", reg, value.str()))
+                    let floats = locations
+                        .iter()
+                        .flatten()
+                        .filter(|l| matches!(l, Location::Float(_)))
+                        .count();
+                    for (((arg, ty), promote), locations) in
+                        args.into_iter().zip(&types).zip(promote).zip(locations)
+                    {
+                        let aggregate = ty.is_aggregate();
+                        if aggregate {
+                            code.push(arg_to_reg(arg, "r11"));
+                        } else {
+                            code.push(arg_to_reg(arg, "rax"));
+                        }
+                        if promote {
+                            code.extend(promote_rax().map(str::to_owned));
+                        }
+                        for (k, location) in locations.into_iter().enumerate() {
+                            if aggregate {
+                                code.push(format!("    mov rax, [r11+{}]", k * 8));
                             }
-                            Arg::DataOffset(offset) => {
-                                code.push(format!("    mov rax, eternal+{}", offset));
-                                code.push(format!("    mov {}, rax", reg));
+                            match location {
+                                Location::Register(reg) => {
+                                    code.push(format!("    mov {}, rax", reg))
+                                }
+                                Location::Float(reg) => code.push(format!("    movq {}, rax", reg)),
+                                Location::Stack(i) => {
+                                    code.push(format!("    mov [rsp+{}], rax", i * 8))
+                                }
                             }
                         }
                     }
+                    // NOTE : Variadic function read the count of vector register from al
+                    if compiler.spellcard.get(&name).is_some_and(|f| f.variadic) {
+                        code.push(format!("    mov eax, {}", floats));
+                    }
                     code.push(format!("    call {}", name));
+                    code.extend(read_return(&compiler, &name));
                     code.push(format!("    mov [rbp-{}], rax", (result + 1) * 8));
-                    if args.len() > 5 {
+                    if stack > 0 {
                         code.push(format!("    add rsp, {}", stack_size));
                     }
                     code.push(format!(""));
@@ -138,6 +190,9 @@ impl Codegen for LinuxX86_64 {
                     code.push(format!("    ; Epilog"));
                     if let Some(arg) = arg {
                         code.push(arg_to_reg(arg, "rax"));
+                        if signature.is_some_and(|s: &Signature| s.return_type.is_float()) {
+                            code.push("    movq xmm0, rax".to_owned());
+                        }
                     }
 
                     if offset > 0 {
@@ -148,19 +203,14 @@ impl Codegen for LinuxX86_64 {
                 }
                 Op::UnaryNot { offset, arg } => {
                     code.push("    xor rbx, rbx".to_owned());
-                    match arg {
-                        Arg::Local(offset) => {
-                            code.push(format!("    cmp rax, [rbp-{}]", (offset + 1) * 8))
-                        }
-                        Arg::Literal(value) => code.push(format!("    cmp rax, {}", value.str())),
-                        Arg::DataOffset(offset) => code.push(format!("    cmp rax, [{}]", offset)),
-                    }
-                    code.push("    test al, al".to_owned());
+                    code.push(arg_to_reg(arg, "rax"));
+                    code.push("    test rax, rax".to_owned());
                     code.push("    setz bl".to_owned());
                     code.push(format!("    mov [rbp-{}], rbx", (offset + 1) * 8));
                 }
                 Op::BinOp {
                     binop,
+                    ty,
                     offset,
                     lhs,
                     rhs,
@@ -168,108 +218,93 @@ impl Codegen for LinuxX86_64 {
                     code.push(format!("    ; Bin Op {}", binop));
 
                     code.push(arg_to_reg(lhs, "rax"));
-                    match binop {
-                        crate::ast::BinOp::Add => {
-                            match rhs {
-                                Arg::Local(offset) => {
-                                    code.push(format!("    add rax, [rbp-{}]", (offset + 1) * 8))
-                                }
-                                Arg::Literal(value) => {
-                                    code.push(format!("    add rax, {}", value.str()))
-                                }
-                                Arg::DataOffset(offset) => {
-                                    code.push(format!("    add rax, [{}]", offset))
-                                }
+                    code.push(arg_to_reg(rhs, "rcx"));
+                    let set = if ty.is_float() {
+                        float_binop(&binop, &ty, &mut code)
+                    } else {
+                        match binop {
+                            crate::ast::BinOp::Add => {
+                                code.push("    add rax, rcx".to_owned());
+                                None
                             }
-                            code.push(format!("    mov [rbp-{}], rax", (offset + 1) * 8));
-                        }
-                        crate::ast::BinOp::Sub => {
-                            match rhs {
-                                Arg::Local(offset) => {
-                                    code.push(format!("    sub rax, [rbp-{}]", (offset + 1) * 8))
-                                }
-                                Arg::Literal(value) => {
-                                    code.push(format!("    sub rax, {}", value.str()))
-                                }
-                                Arg::DataOffset(offset) => {
-                                    code.push(format!("    sub rax, [{}]", offset))
-                                }
+                            crate::ast::BinOp::Sub => {
+                                code.push("    sub rax, rcx".to_owned());
+                                None
                             }
-                            code.push(format!("    mov [rbp-{}], rax", (offset + 1) * 8));
-                        }
-                        crate::ast::BinOp::Mul => {
-                            match rhs {
-                                Arg::Local(offset) => {
-                                    code.push(format!("    imul rax, [rbp-{}]", (offset + 1) * 8))
-                                }
-                                Arg::Literal(value) => {
-                                    code.push(format!("    imul rax, {}", value.str()))
-                                }
-                                Arg::DataOffset(offset) => {
-                                    code.push(format!("    imul rax, [{}]", offset))
-                                }
+                            crate::ast::BinOp::Mul => {
+                                code.push("    imul rax, rcx".to_owned());
+                                None
                             }
-                            code.push(format!("    mov [rbp-{}], rax", (offset + 1) * 8));
-                        }
-                        crate::ast::BinOp::Div => {
-                            code.push(arg_to_reg(rhs, "rbx"));
-                            code.push(format!("    xor rdx, rdx"));
-                            code.push(format!("    div rbx"));
-                            code.push(format!("    mov [rbp-{}], rax", (offset + 1) * 8));
-                        }
-                        crate::ast::BinOp::Equal => {
-                            code.push("    xor rbx, rbx".to_owned());
-                            match rhs {
-                                Arg::Local(offset) => {
-                                    code.push(format!("    cmp rax, [rbp-{}]", (offset + 1) * 8))
-                                }
-                                Arg::Literal(value) => {
-                                    code.push(format!("    cmp rax, {}", value.str()))
-                                }
-                                Arg::DataOffset(offset) => {
-                                    code.push(format!("    cmp rax, [{}]", offset))
-                                }
+                            crate::ast::BinOp::Div if ty.is_signed() => {
+                                code.push("    cqo".to_owned());
+                                code.push("    idiv rcx".to_owned());
+                                None
                             }
-                            code.push("    setz bl".to_owned());
-                            code.push(format!("    mov [rbp-{}], rbx", (offset + 1) * 8));
-                        }
-                        crate::ast::BinOp::Greater => {
-                            code.push("    xor rbx, rbx".to_owned());
-                            match rhs {
-                                Arg::Local(offset) => {
-                                    code.push(format!("    cmp rax, [rbp-{}]", (offset + 1) * 8))
-                                }
-                                Arg::Literal(value) => {
-                                    code.push(format!("    cmp rax, {}", value.str()))
-                                }
-                                Arg::DataOffset(offset) => {
-                                    code.push(format!("    cmp rax, [{}]", offset))
-                                }
+                            crate::ast::BinOp::Div => {
+                                code.push("    xor rdx, rdx".to_owned());
+                                code.push("    div rcx".to_owned());
+                                None
+                            }
+                            crate::ast::BinOp::Equal => Some("sete"),
+                            crate::ast::BinOp::Greater if ty.is_signed() => Some("setg"),
+                            crate::ast::BinOp::Greater => Some("seta"),
+                            crate::ast::BinOp::Less if ty.is_signed() => Some("setl"),
+                            crate::ast::BinOp::Less => Some("setb"),
+                            crate::ast::BinOp::And | crate::ast::BinOp::Or => {
+                                unreachable!("Logical operator is lowered into jump")
                             }
-                            code.push("    setg bl".to_owned());
-                            code.push(format!("    mov [rbp-{}], rbx", (offset + 1) * 8));
                         }
-                        crate::ast::BinOp::Less => {
-                            code.push("    xor rbx, rbx".to_owned());
-                            match rhs {
-                                Arg::Local(offset) => {
-                                    code.push(format!("    cmp rax, [rbp-{}]", (offset + 1) * 8))
-                                }
-                                Arg::Literal(value) => {
-                                    code.push(format!("    cmp rax, {}", value.str()))
-                                }
-                                Arg::DataOffset(offset) => {
-                                    code.push(format!("    cmp rax, [{}]", offset))
-                                }
+                    };
+                    match set {
+                        Some(set) => {
+                            if !ty.is_float() {
+                                code.push("    cmp rax, rcx".to_owned());
                             }
-                            code.push("    setl bl".to_owned());
+                            code.push(format!("    {} bl", set));
+                            code.push("    movzx ebx, bl".to_owned());
                             code.push(format!("    mov [rbp-{}], rbx", (offset + 1) * 8));
                         }
+                        None => {
+                            code.extend(extend_rax(&ty).map(str::to_owned));
+                            code.push(format!("    mov [rbp-{}], rax", (offset + 1) * 8));
+                        }
                     }
 
                     code.push(format!(""));
                 }
+                Op::AddrOf { offset, local } => {
+                    code.push(format!("    lea rax, [rbp-{}]", (local + 1) * 8));
+                    code.push(format!("    mov [rbp-{}], rax", (offset + 1) * 8));
+                }
+                Op::Load { offset, ty, ptr } => {
+                    code.push(arg_to_reg(ptr, "rax"));
+                    code.push(load_rax(&ty).to_owned());
+                    code.push(format!("    mov [rbp-{}], rax", (offset + 1) * 8));
+                }
+                Op::Store { ty, ptr, arg } => {
+                    code.push("    ; Store".to_owned());
+                    code.push(arg_to_reg(ptr, "rax"));
+                    code.push(arg_to_reg(arg, "rcx"));
+                    code.push(store_rcx(&ty).to_owned());
+                    code.push(String::new());
+                }
+                Op::Element {
+                    offset,
+                    base,
+                    index,
+                    size,
+                } => {
+                    code.push(arg_to_reg(base, "rax"));
+                    code.push(arg_to_reg(index, "rcx"));
+                    code.extend(element_rax(size));
+                    code.push(format!("    mov [rbp-{}], rax", (offset + 1) * 8));
+                }
+                Op::BoundsCheck { index, length } => {
+                    code.push(arg_to_reg(index, "rax"));
+                    code.extend(bounds_check_rax(length));
+                }
                 Op::Jmp { name } => code.push(format!("    jmp {}", name)),
+                Op::Loc(loc) => code.push(format!("    ; line {}", loc.row)),
                 Op::JmpIfNot { name, arg } => {
                     code.push(format!("    ; Jump if not"));
                     code.push(arg_to_reg(arg, "rax"));
@@ -278,35 +313,35 @@ impl Codegen for LinuxX86_64 {
                     code.push(format!(""));
                 }
                 Op::ParamAssign { offset, ref arg } => {
-                    if let Some(reg) = REGISTER.get(offset) {
-                        match arg {
-                            Arg::Local(offset) => {
-                                code.push(format!("    mov [rbp-{}], {}", (offset + 1) * 8, reg))
-                            }
-                            _ => {
-                                return Err(super::CodegenError::InvalidOperation {
-                                    message: format!(
-                                        "Function parameter can only assign on local variable"
-                                    ),
-                                });
-                            }
+                    let Arg::Local(local) = arg else {
+                        return Err(super::CodegenError::InvalidOperation {
+                            message: format!(
+                                "Function parameter can only assign on local variable"
+                            ),
+                        });
+                    };
+                    match params.get(offset) {
+                        Some(Location::Register(reg)) => {
+                            code.push(format!("    mov [rbp-{}], {}", (local + 1) * 8, reg))
                         }
-                    } else {
-                        match arg {
-                            Arg::Local(local) => {
-                                code.push(format!(
-                                    "    mov rax, [rbp+16+{}]",
-                                    (offset - REGISTER.len()) * 8
-                                ));
-                                code.push(format!("    mov qword [rbp-{}], rax", (local + 1) * 8,));
-                            }
-                            _ => {
-                                return Err(super::CodegenError::InvalidOperation {
-                                    message: format!(
-                                        "Function parameter can only assign on local variable"
-                                    ),
-                                });
+                        Some(Location::Float(reg)) => {
+                            code.push(format!("    movq rax, {}", reg));
+                            if let Some(ty) = signature
+                                .and_then(|s| s.params.as_ref())
+                                .and_then(|params| params.get(offset))
+                            {
+                                code.extend(extend_rax(ty).map(str::to_owned));
                             }
+                            code.push(format!("    mov [rbp-{}], rax", (local + 1) * 8));
+                        }
+                        Some(Location::Stack(i)) => {
+                            code.push(format!("    mov rax, [rbp+16+{}]", i * 8));
+                            code.push(format!("    mov qword [rbp-{}], rax", (local + 1) * 8));
+                        }
+                        None => {
+                            return Err(super::CodegenError::InvalidOperation {
+                                message: format!("Parameter {} is not declared", offset),
+                            });
                         }
                     }
                 }
@@ -319,7 +354,52 @@ impl Codegen for LinuxX86_64 {
 fn arg_to_reg(arg: Arg, reg: &str) -> String {
     match arg {
         Arg::Local(offset) => format!("    mov {}, [rbp-{}]", reg, (offset + 1) * 8),
-        Arg::Literal(value) => format!("    mov {}, {}", reg, value.str()),
-        Arg::DataOffset(offset) => format!("    mov {}, [eternal+{}]", reg, offset),
+        Arg::Literal(value) => format!("    mov {}, {}", reg, immediate(&value)),
+        Arg::DataOffset(offset) => format!("    mov {}, eternal+{}", reg, offset),
     }
 }
+
+// NOTE : SysV count each register class on its own, struct over 16 byte goes on the stack
+fn classify(types: &[Type]) -> Vec<Vec<Location>> {
+    let (mut ints, mut floats, mut stack) = (0, 0, 0);
+    types
+        .iter()
+        .map(|ty| {
+            let eightbytes = match ty.is_aggregate() {
+                true => (0..ty.size().div_ceil(8))
+                    .map(|k| {
+                        ty.scalars()
+                            .iter()
+                            .filter(|(offset, _)| offset / 8 == k)
+                            .all(|(_, ty)| ty.is_float())
+                    })
+                    .collect::<Vec<_>>(),
+                false => vec![ty.is_float()],
+            };
+            let float = eightbytes.iter().filter(|float| **float).count();
+            let int = eightbytes.len() - float;
+            if ty.size() > 16
+                || ints + int > REGISTER.len()
+                || floats + float > FLOAT_REGISTER.len()
+            {
+                stack += eightbytes.len();
+                return (stack - eightbytes.len()..stack)
+                    .map(Location::Stack)
+                    .collect();
+            }
+            eightbytes
+                .into_iter()
+                .map(|float| match float {
+                    true => {
+                        floats += 1;
+                        Location::Float(FLOAT_REGISTER[floats - 1])
+                    }
+                    false => {
+                        ints += 1;
+                        Location::Register(REGISTER[ints - 1])
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
@@ -19,7 +19,7 @@ pub trait Codegen {
 
 #[derive(Debug)]
 pub enum CodegenError {
-    Unsupported { op: Op, message: String },
+    Unsupported { op: Box<Op>, message: String },
     InvalidOperation { message: String },
 }
 
@@ -1,3 +1,164 @@
+use crate::{
+    ast::BinOp,
+    compiler::{Compiler, FunctionStorage, FunctionSymbol, Type},
+    value::Value,
+};
+
+pub enum Location {
+    Register(&'static str),
+    Float(&'static str),
+    Stack(usize),
+}
+
 pub fn align_mem(size: usize) -> usize {
     (size + 15) & !15
 }
+
+pub fn immediate(value: &Value) -> String {
+    match value {
+        Value::F32(_) | Value::F64(_) => format!("{:#x}", value.as_raw().unwrap()),
+        value => value.str(),
+    }
+}
+
+// NOTE : Every local get a qword slot at [rbp-(n+1)*8] like in the VM, so the
+// slot layout from `Type::slots` is shared by every backend, a sized integer
+// is kept normalized in its slot by extending it each time it is produced
+pub fn extend_rax(ty: &Type) -> Option<&'static str> {
+    match ty {
+        Type::I8 => Some("    movsx rax, al"),
+        Type::I16 => Some("    movsx rax, ax"),
+        Type::I32 => Some("    movsxd rax, eax"),
+        Type::U8 | Type::Bool => Some("    movzx eax, al"),
+        Type::U16 => Some("    movzx eax, ax"),
+        Type::U32 | Type::F32 => Some("    mov eax, eax"),
+        _ => None,
+    }
+}
+
+pub fn load_rax(ty: &Type) -> &'static str {
+    match ty {
+        Type::I8 => "    movsx rax, byte [rax]",
+        Type::I16 => "    movsx rax, word [rax]",
+        Type::I32 => "    movsxd rax, dword [rax]",
+        Type::U8 | Type::Bool | Type::Void => "    movzx eax, byte [rax]",
+        Type::U16 => "    movzx eax, word [rax]",
+        Type::U32 | Type::F32 => "    mov eax, dword [rax]",
+        _ => "    mov rax, qword [rax]",
+    }
+}
+
+pub fn store_rcx(ty: &Type) -> &'static str {
+    match ty.size() {
+        1 => "    mov byte [rax], cl",
+        2 => "    mov word [rax], cx",
+        4 => "    mov dword [rax], ecx",
+        _ => "    mov qword [rax], rcx",
+    }
+}
+
+pub fn element_rax(size: usize) -> Vec<String> {
+    match size {
+        1 | 2 | 4 | 8 => vec![format!("    lea rax, [rax+rcx*{}]", size)],
+        size => vec![
+            format!("    imul rcx, rcx, {}", size),
+            "    add rax, rcx".to_owned(),
+        ],
+    }
+}
+
+// NOTE : Negative index wrap into a big unsigned, so one comparison cover both side
+pub fn bounds_check_rax(length: usize) -> [String; 4] {
+    [
+        format!("    cmp rax, {}", length),
+        "    jb @f".to_owned(),
+        "    ud2".to_owned(),
+        "@@:".to_owned(),
+    ]
+}
+
+pub fn promote_rax() -> [&'static str; 3] {
+    [
+        "    movq xmm15, rax",
+        "    cvtss2sd xmm15, xmm15",
+        "    movq rax, xmm15",
+    ]
+}
+
+pub fn float_binop(binop: &BinOp, ty: &Type, code: &mut Vec<String>) -> Option<&'static str> {
+    let suffix = if *ty == Type::F32 { "ss" } else { "sd" };
+    code.push("    movq xmm0, rax".to_owned());
+    code.push("    movq xmm1, rcx".to_owned());
+    let instruction = match binop {
+        BinOp::Add => "add",
+        BinOp::Sub => "sub",
+        BinOp::Mul => "mul",
+        BinOp::Div => "div",
+        BinOp::And | BinOp::Or => unreachable!("Logical operator is lowered into jump"),
+        // NOTE : NaN set ZF, PF and CF, so `<` swap its operand for `seta` and `==` check PF
+        BinOp::Equal => {
+            code.push(format!("    ucomi{} xmm0, xmm1", suffix));
+            code.push("    sete bl".to_owned());
+            code.push("    setnp cl".to_owned());
+            code.push("    and bl, cl".to_owned());
+            return Some("setnz");
+        }
+        BinOp::Greater => {
+            code.push(format!("    ucomi{} xmm0, xmm1", suffix));
+            return Some("seta");
+        }
+        BinOp::Less => {
+            code.push(format!("    ucomi{} xmm1, xmm0", suffix));
+            return Some("seta");
+        }
+    };
+    code.push(format!("    {}{} xmm0, xmm1", instruction, suffix));
+    code.push("    movq rax, xmm0".to_owned());
+    None
+}
+
+pub fn read_return(compiler: &Compiler, name: &str) -> Vec<String> {
+    let Some(signature) = compiler.checker.function(name) else {
+        return vec![];
+    };
+    let ty = &signature.return_type;
+    let external = matches!(
+        compiler.spellcard.get(name),
+        Some(FunctionSymbol {
+            storage: FunctionStorage::External,
+            ..
+        })
+    );
+
+    let mut code = vec![];
+    if ty.is_float() {
+        code.push("    movq rax, xmm0".to_owned());
+    }
+    if ty.is_float() || external {
+        code.extend(extend_rax(ty).map(str::to_owned));
+    }
+    code
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn float_compare_unordered() {
+        let mut code = vec![];
+        assert_eq!(
+            float_binop(&BinOp::Less, &Type::F64, &mut code),
+            Some("seta")
+        );
+        assert_eq!(code.last().unwrap(), "    ucomisd xmm1, xmm0");
+
+        let mut code = vec![];
+        assert_eq!(
+            float_binop(&BinOp::Equal, &Type::F32, &mut code),
+            Some("setnz")
+        );
+        assert!(code.contains(&"    ucomiss xmm0, xmm1".to_owned()));
+        assert!(code.contains(&"    setnp cl".to_owned()));
+    }
+}
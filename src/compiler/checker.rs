@@ -0,0 +1,1164 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    ast::{BinOp, Expression, GenericParam, MethodSignature, Pattern, Statement},
+    commons::Loc,
+    value::Value,
+};
+
+use super::{CompilerError, Type, mangle, method_symbol};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub params: Option<Vec<Type>>,
+    pub return_type: Type,
+}
+
+type Locals = HashMap<String, Option<Type>>;
+
+struct Context<'a> {
+    function: Option<(&'a str, &'a Type)>,
+}
+
+#[derive(Clone)]
+pub struct TypeChecker {
+    functions: HashMap<String, Signature>,
+    declared: HashMap<String, Type>,
+    globals: Locals,
+    templates: HashMap<String, Statement>,
+    pending: Vec<Statement>,
+    traits: HashMap<String, Vec<MethodSignature>>,
+    impls: HashSet<(String, String)>,
+    global_offer: Option<Type>,
+}
+
+impl TypeChecker {
+    pub fn new() -> Self {
+        Self {
+            functions: HashMap::new(),
+            declared: HashMap::new(),
+            globals: HashMap::new(),
+            templates: HashMap::new(),
+            pending: vec![],
+            traits: HashMap::new(),
+            impls: HashSet::new(),
+            global_offer: None,
+        }
+    }
+
+    pub fn function(&self, name: &str) -> Option<&Signature> {
+        self.functions.get(name)
+    }
+
+    // Type of the last `offer` outside of any spellcard in the last checked input
+    pub fn global_offer(&self) -> Option<&Type> {
+        self.global_offer.as_ref()
+    }
+
+    pub fn check(&mut self, ast: &mut Vec<Statement>) -> Result<(), CompilerError> {
+        // NOTE : A failed input should not leave half of its declaration behind
+        let functions = self.functions.clone();
+        let declared = self.declared.clone();
+        let templates = self.templates.clone();
+        let traits = self.traits.clone();
+        let impls = self.impls.clone();
+        let mut globals = self.globals.clone();
+        match self.check_program(&mut globals, ast) {
+            Ok(()) => {
+                self.globals = globals;
+                Ok(())
+            }
+            Err(err) => {
+                self.functions = functions;
+                self.declared = declared;
+                self.templates = templates;
+                self.traits = traits;
+                self.impls = impls;
+                self.pending.clear();
+                Err(err)
+            }
+        }
+    }
+
+    fn check_program(
+        &mut self,
+        globals: &mut Locals,
+        ast: &mut Vec<Statement>,
+    ) -> Result<(), CompilerError> {
+        let context = Context { function: None };
+        self.global_offer = None;
+        self.check_block(globals, &context, ast)?;
+        // NOTE : An instance can instantiate another generic spellcard in turn
+        while let Some(mut instance) = self.pending.pop() {
+            self.check_block(
+                &mut HashMap::new(),
+                &context,
+                std::slice::from_mut(&mut instance),
+            )?;
+            ast.push(instance);
+        }
+        Ok(())
+    }
+
+    pub fn resolve_type(&self, name: &str) -> Option<Type> {
+        Type::resolve_with(name, &|name| self.declared.get(name).cloned())
+    }
+
+    fn resolve(&self, name: &str, loc: Loc) -> Result<Type, CompilerError> {
+        self.resolve_type(name).ok_or(CompilerError::UnknownType {
+            found: name.to_owned(),
+            loc,
+        })
+    }
+
+    fn expect(
+        statement: String,
+        expected: &Type,
+        found: &Type,
+        loc: Loc,
+    ) -> Result<(), CompilerError> {
+        if expected == found {
+            return Ok(());
+        }
+        Err(CompilerError::TypeMissmatch {
+            statement,
+            expected: expected.to_string(),
+            found: found.to_string(),
+            loc,
+        })
+    }
+
+    fn expect_scalar(statement: String, found: &Type, loc: Loc) -> Result<(), CompilerError> {
+        if !found.is_aggregate() {
+            return Ok(());
+        }
+        Err(CompilerError::TypeMissmatch {
+            statement,
+            expected: "scalar".to_owned(),
+            found: found.to_string(),
+            loc,
+        })
+    }
+
+    fn check_pattern(
+        locals: &mut Locals,
+        ty: &Type,
+        pattern: &Pattern,
+        loc: Loc,
+    ) -> Result<(), CompilerError> {
+        match pattern {
+            Pattern::Wildcard => Ok(()),
+            Pattern::Variant {
+                name,
+                variant,
+                bindings,
+            } => {
+                if !matches!(ty, Type::Enum { name: expected, .. } if expected == name) {
+                    return Err(CompilerError::TypeMissmatch {
+                        statement: "pattern of match".to_owned(),
+                        expected: ty.to_string(),
+                        found: name.clone(),
+                        loc,
+                    });
+                }
+                let (_, fields) = ty.variant(variant).ok_or(CompilerError::UnknownVariant {
+                    variant: variant.clone(),
+                    ty: name.clone(),
+                    loc,
+                })?;
+                if bindings.len() != fields.len() {
+                    return Err(CompilerError::ArityMismatch {
+                        name: format!("{}::{}", name, variant),
+                        expected: fields.len(),
+                        found: bindings.len(),
+                        variadic: false,
+                        loc,
+                    });
+                }
+                for (binding, (_, field)) in bindings.iter().zip(fields) {
+                    if binding != "_" {
+                        locals.insert(binding.clone(), Some(field));
+                    }
+                }
+                Ok(())
+            }
+            Pattern::Range { start, end, .. } => {
+                if !ty.is_integer() {
+                    return Err(CompilerError::TypeMissmatch {
+                        statement: "pattern of match".to_owned(),
+                        expected: ty.to_string(),
+                        found: "integer".to_owned(),
+                        loc,
+                    });
+                }
+                for value in [*start, *end] {
+                    if ty.literal(value).is_none() {
+                        return Err(CompilerError::LiteralOutOfRange {
+                            value,
+                            ty: ty.to_string(),
+                            loc,
+                        });
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn uncovered(ty: &Type, patterns: &[&Pattern]) -> Vec<String> {
+        if patterns
+            .iter()
+            .any(|pattern| **pattern == Pattern::Wildcard)
+        {
+            return vec![];
+        }
+        if let Type::Enum { name, variants } = ty {
+            return variants
+                .iter()
+                .filter(|(variant, _)| {
+                    !patterns.iter().any(|pattern| {
+                        matches!(pattern, Pattern::Variant { variant: covered, .. } if covered == variant)
+                    })
+                })
+                .map(|(variant, _)| format!("{}::{}", name, variant))
+                .collect();
+        }
+        let Some((min, max)) = ty.bounds() else {
+            return vec!["_".to_owned()];
+        };
+        let mut ranges = patterns
+            .iter()
+            .filter_map(|pattern| match pattern {
+                Pattern::Range {
+                    start,
+                    end,
+                    inclusive,
+                } => Some((*start, if *inclusive { *end } else { end - 1 })),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        ranges.sort();
+
+        let range = |start: i128, end: i128| match start == end {
+            true => start.to_string(),
+            false => format!("{}..={}", start, end),
+        };
+        let mut missing = vec![];
+        let mut next = min;
+        for (start, end) in ranges {
+            if start > next {
+                missing.push(range(next, start - 1));
+            }
+            next = next.max(end + 1);
+        }
+        if next <= max {
+            missing.push(range(next, max));
+        }
+        missing
+    }
+
+    fn declare(&mut self, ast: &mut [Statement]) -> Result<(), CompilerError> {
+        for statement in ast.iter() {
+            let ty = match statement {
+                Statement::Trait { name, methods, .. } => {
+                    self.traits.insert(name.clone(), methods.clone());
+                    continue;
+                }
+                Statement::Struct { name, fields, loc } => Type::Struct {
+                    name: name.clone(),
+                    fields: fields
+                        .iter()
+                        .map(|field| {
+                            Ok((field.name.clone(), self.resolve(&field.annotation, *loc)?))
+                        })
+                        .collect::<Result<_, _>>()?,
+                },
+                Statement::Enum {
+                    name,
+                    variants,
+                    loc,
+                } => Type::Enum {
+                    name: name.clone(),
+                    variants: variants
+                        .iter()
+                        .map(|variant| {
+                            let fields = variant
+                                .fields
+                                .iter()
+                                .map(|field| self.resolve(field, *loc))
+                                .collect::<Result<_, _>>()?;
+                            Ok((variant.name.clone(), fields))
+                        })
+                        .collect::<Result<_, _>>()?,
+                },
+                _ => continue,
+            };
+            self.declared.insert(ty.to_string(), ty);
+        }
+        for statement in ast.iter_mut() {
+            if let Statement::Impl {
+                ty,
+                trait_name,
+                methods,
+                loc,
+            } = statement
+            {
+                self.implement(ty, trait_name.as_deref(), methods, *loc)?;
+                self.declare(methods)?;
+            }
+        }
+        for statement in ast.iter() {
+            let (name, args, return_type, default, loc) = match statement {
+                Statement::Invite {
+                    name,
+                    args,
+                    return_type,
+                    loc,
+                    ..
+                } => (name, args.as_ref(), return_type, Type::I32, loc),
+                Statement::SpellCard { name, generics, .. } if !generics.is_empty() => {
+                    self.templates.insert(name.clone(), statement.clone());
+                    continue;
+                }
+                Statement::SpellCard {
+                    name,
+                    args,
+                    return_type,
+                    loc,
+                    ..
+                } => (name, Some(args), return_type, Type::Void, loc),
+                _ => continue,
+            };
+            let params = match args {
+                Some(args) => Some(
+                    args.iter()
+                        .map(|arg| self.resolve(&arg.annotation, *loc))
+                        .collect::<Result<Vec<_>, _>>()?,
+                ),
+                None => None,
+            };
+            let return_type = match return_type {
+                Some(return_type) => self.resolve(return_type, *loc)?,
+                None => default,
+            };
+            self.functions.insert(
+                name.clone(),
+                Signature {
+                    params,
+                    return_type,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    fn check_block(
+        &mut self,
+        locals: &mut Locals,
+        context: &Context,
+        ast: &mut [Statement],
+    ) -> Result<(), CompilerError> {
+        self.declare(ast)?;
+        for statement in ast.iter_mut() {
+            match statement {
+                Statement::Expression(expr, loc) => {
+                    self.check_expression(locals, expr, None, *loc)?;
+                }
+                Statement::Invite { .. }
+                | Statement::Struct { .. }
+                | Statement::Enum { .. }
+                | Statement::Trait { .. } => {}
+                Statement::Impl { methods, .. } => {
+                    self.check_block(&mut HashMap::new(), context, methods)?;
+                }
+                Statement::SpellCard { generics, .. } if !generics.is_empty() => {}
+                Statement::Eternal {
+                    name,
+                    annotation,
+                    loc,
+                }
+                | Statement::Vow {
+                    name,
+                    annotation,
+                    loc,
+                } => {
+                    let ty = match annotation {
+                        Some(annotation) => Some(self.resolve(annotation, *loc)?),
+                        None => None,
+                    };
+                    locals.insert(name.clone(), ty);
+                }
+                Statement::Assignment { name, value, loc } => {
+                    let hint = locals.get(name).cloned().flatten();
+                    let found = self.check_expression(locals, value, hint.as_ref(), *loc)?;
+                    let target = locals
+                        .get_mut(name)
+                        .ok_or(CompilerError::UndefinedVariable {
+                            found: name.clone(),
+                            loc: *loc,
+                        })?;
+                    let statement = format!("assignment of {}", name);
+                    match target {
+                        Some(expected) => Self::expect(statement, expected, &found, *loc)?,
+                        None if found == Type::Void => {
+                            return Err(CompilerError::TypeMissmatch {
+                                statement,
+                                expected: "value".to_owned(),
+                                found: found.to_string(),
+                                loc: *loc,
+                            });
+                        }
+                        None => *target = Some(found),
+                    }
+                }
+                Statement::Store { target, value, loc } => {
+                    if !matches!(
+                        target,
+                        Expression::Deref(_) | Expression::Index { .. } | Expression::Field { .. }
+                    ) {
+                        return Err(CompilerError::NotAddressable { loc: *loc });
+                    }
+                    let expected = self.check_expression(locals, target, None, *loc)?;
+                    let found = self.check_expression(locals, value, Some(&expected), *loc)?;
+                    Self::expect("store".to_owned(), &expected, &found, *loc)?;
+                }
+                Statement::Foreseen {
+                    condition,
+                    then_branch,
+                    else_branch,
+                    loc,
+                } => {
+                    let found = self.check_expression(locals, condition, None, *loc)?;
+                    Self::expect("foreseen condition".to_owned(), &Type::Bool, &found, *loc)?;
+                    self.check_block(locals, context, then_branch)?;
+                    if let Some(else_branch) = else_branch {
+                        self.check_block(locals, context, else_branch)?;
+                    }
+                }
+                Statement::Until {
+                    condition,
+                    body,
+                    loc,
+                } => {
+                    let found = self.check_expression(locals, condition, None, *loc)?;
+                    Self::expect("until condition".to_owned(), &Type::Bool, &found, *loc)?;
+                    self.check_block(locals, context, body)?;
+                }
+                Statement::Match { value, arms, loc } => {
+                    let ty = self.check_expression(locals, value, None, *loc)?;
+                    for arm in arms.iter_mut() {
+                        // NOTE : A binding only live inside its arm, the outer local come back after
+                        let shadowed = match &arm.pattern {
+                            Pattern::Variant { bindings, .. } => bindings
+                                .iter()
+                                .map(|binding| (binding.clone(), locals.get(binding).cloned()))
+                                .collect(),
+                            _ => vec![],
+                        };
+                        Self::check_pattern(locals, &ty, &arm.pattern, *loc)?;
+                        self.check_block(locals, context, &mut arm.body)?;
+                        for (binding, outer) in shadowed {
+                            match outer {
+                                Some(outer) => locals.insert(binding, outer),
+                                None => locals.remove(&binding),
+                            };
+                        }
+                    }
+                    let patterns = arms.iter().map(|arm| &arm.pattern).collect::<Vec<_>>();
+                    let missing = Self::uncovered(&ty, &patterns);
+                    if !missing.is_empty() {
+                        return Err(CompilerError::NonExhaustiveMatch { missing, loc: *loc });
+                    }
+                }
+                Statement::SpellCard {
+                    name,
+                    args,
+                    return_type,
+                    body,
+                    loc,
+                    ..
+                } => {
+                    let mut scope = HashMap::new();
+                    for arg in args.iter() {
+                        let ty = self.resolve(&arg.annotation, *loc)?;
+                        if let Type::Array(..) = ty {
+                            let statement = format!("parameter {} of {}", arg.name, name);
+                            Self::expect_scalar(statement, &ty, *loc)?;
+                        }
+                        scope.insert(arg.name.clone(), Some(ty));
+                    }
+                    let return_type = match return_type {
+                        Some(return_type) => self.resolve(return_type, *loc)?,
+                        None => Type::Void,
+                    };
+                    Self::expect_scalar(format!("offer of {}", name), &return_type, *loc)?;
+
+                    let context = Context {
+                        function: Some((name, &return_type)),
+                    };
+                    self.check_block(&mut scope, &context, body)?;
+                }
+                Statement::Offer(value, loc) => {
+                    let hint = context.function.map(|(_, ty)| ty);
+                    let found = match value {
+                        Some(value) => self.check_expression(locals, value, hint, *loc)?,
+                        None => Type::Void,
+                    };
+                    match context.function {
+                        Some((name, expected)) => {
+                            Self::expect(format!("offer of {}", name), expected, &found, *loc)?
+                        }
+                        None => self.global_offer = Some(found),
+                    }
+                }
+            }
+        }
+
+        for statement in ast.iter_mut() {
+            if let Statement::Eternal {
+                name, annotation, ..
+            }
+            | Statement::Vow {
+                name, annotation, ..
+            } = statement
+                && annotation.is_none()
+                && let Some(Some(ty)) = locals.get(name)
+            {
+                *annotation = Some(ty.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    fn substitute(annotation: &str, bound: &HashMap<String, Type>) -> String {
+        if let Some(pointee) = annotation.strip_prefix('*') {
+            return format!("*{}", Self::substitute(pointee, bound));
+        }
+        if let Some(array) = annotation
+            .strip_prefix('[')
+            .and_then(|a| a.strip_suffix(']'))
+            && let Some((element, length)) = array.rsplit_once(';')
+        {
+            return format!("[{};{}]", Self::substitute(element.trim(), bound), length);
+        }
+        match bound.get(annotation) {
+            Some(ty) => ty.to_string(),
+            None => annotation.to_owned(),
+        }
+    }
+
+    fn substitute_block(ast: &mut [Statement], bound: &HashMap<String, Type>) {
+        for statement in ast {
+            match statement {
+                Statement::Eternal { annotation, .. } | Statement::Vow { annotation, .. } => {
+                    if let Some(annotation) = annotation {
+                        *annotation = Self::substitute(annotation, bound);
+                    }
+                }
+                Statement::Foreseen {
+                    then_branch,
+                    else_branch,
+                    ..
+                } => {
+                    Self::substitute_block(then_branch, bound);
+                    if let Some(else_branch) = else_branch {
+                        Self::substitute_block(else_branch, bound);
+                    }
+                }
+                Statement::Until { body, .. } => Self::substitute_block(body, bound),
+                Statement::Match { arms, .. } => arms
+                    .iter_mut()
+                    .for_each(|arm| Self::substitute_block(&mut arm.body, bound)),
+                _ => {}
+            }
+        }
+    }
+
+    fn implement(
+        &mut self,
+        ty: &str,
+        trait_name: Option<&str>,
+        methods: &mut [Statement],
+        loc: Loc,
+    ) -> Result<(), CompilerError> {
+        let ty = self.resolve(ty, loc)?;
+        let bound = HashMap::from([("Self".to_owned(), ty.clone())]);
+        let mut names = vec![];
+        for method in methods.iter_mut() {
+            let Statement::SpellCard {
+                name,
+                args,
+                return_type,
+                body,
+                ..
+            } = method
+            else {
+                unreachable!("Impl only hold spellcard")
+            };
+            for arg in args.iter_mut() {
+                arg.annotation = Self::substitute(&arg.annotation, &bound);
+            }
+            if let Some(return_type) = return_type {
+                *return_type = Self::substitute(return_type, &bound);
+            }
+            Self::substitute_block(body, &bound);
+            names.push(name.clone());
+            *name = method_symbol(&ty, name);
+        }
+
+        let Some(trait_name) = trait_name else {
+            return Ok(());
+        };
+        let required = self
+            .traits
+            .get(trait_name)
+            .cloned()
+            .ok_or(CompilerError::UnknownTrait {
+                found: trait_name.to_owned(),
+                loc,
+            })?;
+        for signature in required {
+            let Some(Statement::SpellCard {
+                args, return_type, ..
+            }) = names
+                .iter()
+                .position(|name| *name == signature.name)
+                .map(|i| &methods[i])
+            else {
+                return Err(CompilerError::MissingMethod {
+                    method: signature.name,
+                    ty: ty.to_string(),
+                    trait_name: trait_name.to_owned(),
+                    loc,
+                });
+            };
+            let name = format!("{}::{}", trait_name, signature.name);
+            if args.len() != signature.args.len() {
+                return Err(CompilerError::ArityMismatch {
+                    name,
+                    expected: signature.args.len(),
+                    found: args.len(),
+                    variadic: false,
+                    loc,
+                });
+            }
+            for (i, (expected, found)) in signature.args.iter().zip(args).enumerate() {
+                let expected =
+                    self.resolve(&Self::substitute(&expected.annotation, &bound), loc)?;
+                let found = self.resolve(&found.annotation, loc)?;
+                let statement = format!("parameter {} of {}", i + 1, name);
+                Self::expect(statement, &expected, &found, loc)?;
+            }
+            let expected = signature.return_type.as_deref().unwrap_or("void");
+            let expected = self.resolve(&Self::substitute(expected, &bound), loc)?;
+            let found = self.resolve(return_type.as_deref().unwrap_or("void"), loc)?;
+            Self::expect(format!("offer of {}", name), &expected, &found, loc)?;
+        }
+        self.impls.insert((ty.to_string(), trait_name.to_owned()));
+        Ok(())
+    }
+
+    fn unify(
+        annotation: &str,
+        found: &Type,
+        generics: &[GenericParam],
+        bound: &mut HashMap<String, Type>,
+    ) {
+        if let Some(pointee) = annotation.strip_prefix('*') {
+            if let Type::Pointer(found) = found {
+                Self::unify(pointee, found, generics, bound);
+            }
+            return;
+        }
+        if let Some(array) = annotation
+            .strip_prefix('[')
+            .and_then(|a| a.strip_suffix(']'))
+            && let Some((element, _)) = array.rsplit_once(';')
+        {
+            if let Type::Array(found, _) = found {
+                Self::unify(element.trim(), found, generics, bound);
+            }
+            return;
+        }
+        if generics.iter().any(|generic| generic.name == annotation)
+            && !bound.contains_key(annotation)
+        {
+            bound.insert(annotation.to_owned(), found.clone());
+        }
+    }
+
+    fn instantiate(
+        &mut self,
+        locals: &Locals,
+        template: &Statement,
+        args: &mut [Expression],
+        loc: Loc,
+    ) -> Result<(String, Type), CompilerError> {
+        let Statement::SpellCard {
+            name,
+            generics,
+            args: params,
+            ..
+        } = template
+        else {
+            unreachable!("Only generic spellcard are registered as template")
+        };
+        if params.len() != args.len() {
+            return Err(CompilerError::ArityMismatch {
+                name: name.clone(),
+                expected: params.len(),
+                found: args.len(),
+                variadic: false,
+                loc,
+            });
+        }
+
+        let mut bound = HashMap::new();
+        for (i, (param, arg)) in params.iter().zip(args.iter_mut()).enumerate() {
+            let hint = self.resolve_type(&Self::substitute(&param.annotation, &bound));
+            let found = self.check_expression(locals, arg, hint.as_ref(), loc)?;
+            Self::unify(&param.annotation, &found, generics, &mut bound);
+            let annotation = Self::substitute(&param.annotation, &bound);
+            let statement = format!("argument {} of {}", i + 1, name);
+            match self.resolve_type(&annotation) {
+                Some(expected) => Self::expect(statement, &expected, &found, loc)?,
+                None => {
+                    return Err(CompilerError::TypeMissmatch {
+                        statement,
+                        expected: annotation,
+                        found: found.to_string(),
+                        loc,
+                    });
+                }
+            }
+        }
+        let types = generics
+            .iter()
+            .map(|generic| {
+                bound
+                    .get(&generic.name)
+                    .cloned()
+                    .ok_or(CompilerError::CannotInfer {
+                        generic: generic.name.clone(),
+                        name: name.clone(),
+                        loc,
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        for (generic, ty) in generics.iter().zip(&types) {
+            for trait_name in &generic.bounds {
+                if !self.traits.contains_key(trait_name) {
+                    return Err(CompilerError::UnknownTrait {
+                        found: trait_name.clone(),
+                        loc,
+                    });
+                }
+                if !self.impls.contains(&(ty.to_string(), trait_name.clone())) {
+                    return Err(CompilerError::UnsatisfiedBound {
+                        ty: ty.to_string(),
+                        trait_name: trait_name.clone(),
+                        loc,
+                    });
+                }
+            }
+        }
+
+        let symbol = mangle(name, &types);
+        if !self.functions.contains_key(&symbol) {
+            let mut instance = template.clone();
+            if let Statement::SpellCard {
+                name,
+                generics,
+                args,
+                return_type,
+                body,
+                ..
+            } = &mut instance
+            {
+                *name = symbol.clone();
+                generics.clear();
+                for arg in args.iter_mut() {
+                    arg.annotation = Self::substitute(&arg.annotation, &bound);
+                }
+                if let Some(return_type) = return_type {
+                    *return_type = Self::substitute(return_type, &bound);
+                }
+                Self::substitute_block(body, &bound);
+            }
+            self.declare(std::slice::from_mut(&mut instance))?;
+            self.pending.push(instance);
+        }
+        Ok((symbol.clone(), self.functions[&symbol].return_type.clone()))
+    }
+
+    fn is_untyped_literal(expr: &Expression) -> bool {
+        matches!(
+            expr,
+            Expression::Literal(value) if value.as_int().is_some()
+                || value.as_float().is_some()
+                || matches!(value, Value::Pointer(_))
+        )
+    }
+
+    fn check_expression(
+        &mut self,
+        locals: &Locals,
+        expr: &mut Expression,
+        hint: Option<&Type>,
+        loc: Loc,
+    ) -> Result<Type, CompilerError> {
+        match expr {
+            Expression::Literal(value) => match value.as_int() {
+                Some(int) => {
+                    let ty = hint
+                        .filter(|ty| ty.is_integer())
+                        .cloned()
+                        .unwrap_or(Type::I32);
+                    *value = ty.literal(int).ok_or(CompilerError::LiteralOutOfRange {
+                        value: int,
+                        ty: ty.to_string(),
+                        loc,
+                    })?;
+                    Ok(ty)
+                }
+                None => match value.as_float() {
+                    Some(float) => {
+                        let ty = hint.filter(|ty| ty.is_float()).unwrap_or(&Type::F64);
+                        *value = ty.float(float).unwrap();
+                        Ok(ty.clone())
+                    }
+                    None => match (value, hint) {
+                        (Value::Pointer(_), Some(ty @ Type::Pointer(_))) => Ok(ty.clone()),
+                        (value, _) => Ok(Type::of(value)),
+                    },
+                },
+            },
+            Expression::Variable(name) => match locals.get(name) {
+                Some(Some(ty)) => Ok(ty.clone()),
+                Some(None) => Err(CompilerError::UninferredType {
+                    name: name.clone(),
+                    loc,
+                }),
+                None => Err(CompilerError::UndefinedVariable {
+                    found: name.clone(),
+                    loc,
+                }),
+            },
+            Expression::Unary { op, arg } => {
+                let found = self.check_expression(locals, arg, Some(&Type::Bool), loc)?;
+                match op {
+                    crate::ast::UnaryOp::Not => {
+                        Self::expect("operand of !".to_owned(), &Type::Bool, &found, loc)?;
+                    }
+                }
+                Ok(Type::Bool)
+            }
+            Expression::Binary {
+                op: op @ (BinOp::And | BinOp::Or),
+                left,
+                right,
+            } => {
+                for operand in [left, right] {
+                    let found = self.check_expression(locals, operand, None, loc)?;
+                    Self::expect(format!("operand of {}", op), &Type::Bool, &found, loc)?;
+                }
+                Ok(Type::Bool)
+            }
+            Expression::Binary { op, left, right } => {
+                let arithmetic = matches!(op, BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div);
+                let hint = if arithmetic { hint } else { None };
+                let (lhs, rhs) =
+                    if Self::is_untyped_literal(left) && !Self::is_untyped_literal(right) {
+                        let rhs = self.check_expression(locals, right, hint, loc)?;
+                        let lhs = self.check_expression(locals, left, Some(&rhs), loc)?;
+                        (lhs, rhs)
+                    } else {
+                        let lhs = self.check_expression(locals, left, hint, loc)?;
+                        let rhs = self.check_expression(locals, right, Some(&lhs), loc)?;
+                        (lhs, rhs)
+                    };
+
+                if matches!(lhs, Type::Pointer(_)) && matches!(op, BinOp::Add | BinOp::Sub) {
+                    if !rhs.is_integer() {
+                        return Err(CompilerError::TypeMissmatch {
+                            statement: format!("right operand of {}", op),
+                            expected: "integer".to_owned(),
+                            found: rhs.to_string(),
+                            loc,
+                        });
+                    }
+                    return Ok(lhs);
+                }
+
+                let valid = lhs.is_integer()
+                    || lhs.is_float()
+                    || (!arithmetic && matches!(lhs, Type::Pointer(_)))
+                    || (*op == BinOp::Equal && lhs == Type::Bool);
+                if !valid {
+                    return Err(CompilerError::TypeMissmatch {
+                        statement: format!("left operand of {}", op),
+                        expected: "number".to_owned(),
+                        found: lhs.to_string(),
+                        loc,
+                    });
+                }
+                Self::expect(format!("right operand of {}", op), &lhs, &rhs, loc)?;
+                Ok(if arithmetic { lhs } else { Type::Bool })
+            }
+            Expression::MethodCall {
+                receiver,
+                method,
+                args,
+            } => {
+                let found = self.check_expression(locals, receiver, None, loc)?;
+                let owner = match &found {
+                    Type::Pointer(pointee) => pointee.as_ref(),
+                    ty => ty,
+                };
+                let symbol = method_symbol(owner, method);
+                let expected = match (self.functions.get(&symbol), self.templates.get(&symbol)) {
+                    (Some(signature), _) => signature
+                        .params
+                        .as_ref()
+                        .and_then(|params| params.first())
+                        .cloned(),
+                    (None, Some(Statement::SpellCard { args, .. })) => args
+                        .first()
+                        .and_then(|arg| self.resolve_type(&arg.annotation)),
+                    _ => {
+                        return Err(CompilerError::UnknownMethod {
+                            method: method.clone(),
+                            ty: owner.to_string(),
+                            loc,
+                        });
+                    }
+                };
+
+                let receiver = match expected {
+                    Some(expected) if expected == found => receiver.as_ref().clone(),
+                    Some(Type::Pointer(expected)) if *expected == found => {
+                        Expression::AddressOf(receiver.clone())
+                    }
+                    Some(expected) if Type::Pointer(Box::new(expected.clone())) == found => {
+                        Expression::Deref(receiver.clone())
+                    }
+                    expected => {
+                        return Err(CompilerError::TypeMissmatch {
+                            statement: format!("receiver of {}", method),
+                            expected: expected
+                                .map_or("self parameter".to_owned(), |ty| ty.to_string()),
+                            found: found.to_string(),
+                            loc,
+                        });
+                    }
+                };
+                let mut args = std::mem::take(args);
+                args.insert(0, receiver);
+                *expr = Expression::Call {
+                    function: symbol,
+                    args,
+                };
+                self.check_expression(locals, expr, hint, loc)
+            }
+            Expression::Call { function, args } => {
+                if let Some(template) = self.templates.get(function).cloned() {
+                    let (instance, return_type) = self.instantiate(locals, &template, args, loc)?;
+                    *function = instance;
+                    return Ok(return_type);
+                }
+                let signature = self.functions.get(function).cloned().ok_or(
+                    CompilerError::UnknownFunction {
+                        found: function.clone(),
+                        loc,
+                    },
+                )?;
+                for (i, arg) in args.iter_mut().enumerate() {
+                    let expected = signature.params.as_ref().and_then(|params| params.get(i));
+                    let found = self.check_expression(locals, arg, expected, loc)?;
+                    if let Some(expected) = expected {
+                        let statement = format!("argument {} of {}", i + 1, function);
+                        Self::expect(statement, expected, &found, loc)?;
+                    }
+                }
+                Ok(signature.return_type.clone())
+            }
+            Expression::AddressOf(expr) => match expr.as_mut() {
+                Expression::Variable(_) | Expression::Index { .. } | Expression::Field { .. } => {
+                    let ty = self.check_expression(locals, expr, None, loc)?;
+                    Ok(Type::Pointer(Box::new(ty)))
+                }
+                _ => Err(CompilerError::NotAddressable { loc }),
+            },
+            Expression::Deref(expr) => match self.check_expression(locals, expr, None, loc)? {
+                Type::Pointer(pointee) if *pointee != Type::Void => Ok(*pointee),
+                found => Err(CompilerError::TypeMissmatch {
+                    statement: "operand of *".to_owned(),
+                    expected: "pointer".to_owned(),
+                    found: found.to_string(),
+                    loc,
+                }),
+            },
+            Expression::Array(elements) => {
+                let (element, length) = match hint {
+                    Some(Type::Array(element, length)) => (Some(element.as_ref()), Some(*length)),
+                    _ => (None, None),
+                };
+                let mut element = element.cloned();
+                for value in elements.iter_mut() {
+                    let found = self.check_expression(locals, value, element.as_ref(), loc)?;
+                    match &element {
+                        Some(expected) => {
+                            Self::expect("element of array".to_owned(), expected, &found, loc)?
+                        }
+                        None => element = Some(found),
+                    }
+                }
+                let found = Type::Array(Box::new(element.unwrap_or(Type::Void)), elements.len());
+                if let Some(length) = length
+                    && length != elements.len()
+                {
+                    return Err(CompilerError::TypeMissmatch {
+                        statement: "array literal".to_owned(),
+                        expected: hint.unwrap().to_string(),
+                        found: found.to_string(),
+                        loc,
+                    });
+                }
+                Ok(found)
+            }
+            Expression::Index { array, index } => {
+                let (element, length) = match self.check_expression(locals, array, None, loc)? {
+                    Type::Array(element, length) => (*element, length),
+                    found => {
+                        return Err(CompilerError::TypeMissmatch {
+                            statement: "operand of []".to_owned(),
+                            expected: "array".to_owned(),
+                            found: found.to_string(),
+                            loc,
+                        });
+                    }
+                };
+                let found = self.check_expression(locals, index, Some(&Type::I64), loc)?;
+                if !found.is_integer() {
+                    return Err(CompilerError::TypeMissmatch {
+                        statement: "index of []".to_owned(),
+                        expected: "integer".to_owned(),
+                        found: found.to_string(),
+                        loc,
+                    });
+                }
+                if let Expression::Literal(value) = index.as_ref()
+                    && let Some(int) = value.as_int()
+                    && (int < 0 || int >= length as i128)
+                {
+                    return Err(CompilerError::IndexOutOfBounds {
+                        index: int,
+                        length,
+                        loc,
+                    });
+                }
+                Ok(element)
+            }
+            Expression::Struct { name, fields } => {
+                let ty = self
+                    .declared
+                    .get(name)
+                    .filter(|ty| matches!(ty, Type::Struct { .. }))
+                    .cloned()
+                    .ok_or(CompilerError::UnknownType {
+                        found: name.clone(),
+                        loc,
+                    })?;
+                for (field, value) in fields.iter_mut() {
+                    let (_, expected) = ty.field(field).ok_or(CompilerError::UnknownField {
+                        field: field.clone(),
+                        ty: name.clone(),
+                        loc,
+                    })?;
+                    let found = self.check_expression(locals, value, Some(&expected), loc)?;
+                    Self::expect(
+                        format!("field {} of {}", field, name),
+                        &expected,
+                        &found,
+                        loc,
+                    )?;
+                }
+                if let Type::Struct {
+                    fields: declared, ..
+                } = &ty
+                    && let Some((missing, _)) = declared
+                        .iter()
+                        .find(|(field, _)| !fields.iter().any(|(name, _)| name == field))
+                {
+                    return Err(CompilerError::MissingField {
+                        field: missing.clone(),
+                        ty: name.clone(),
+                        loc,
+                    });
+                }
+                Ok(ty)
+            }
+            Expression::Variant {
+                name,
+                variant,
+                args,
+            } => {
+                let ty = self
+                    .declared
+                    .get(name)
+                    .filter(|ty| matches!(ty, Type::Enum { .. }))
+                    .cloned()
+                    .ok_or(CompilerError::UnknownType {
+                        found: name.clone(),
+                        loc,
+                    })?;
+                let (_, fields) = ty.variant(variant).ok_or(CompilerError::UnknownVariant {
+                    variant: variant.clone(),
+                    ty: name.clone(),
+                    loc,
+                })?;
+                if args.len() != fields.len() {
+                    return Err(CompilerError::ArityMismatch {
+                        name: format!("{}::{}", name, variant),
+                        expected: fields.len(),
+                        found: args.len(),
+                        variadic: false,
+                        loc,
+                    });
+                }
+                for (arg, (_, expected)) in args.iter_mut().zip(fields) {
+                    let found = self.check_expression(locals, arg, Some(&expected), loc)?;
+                    let statement = format!("payload of {}::{}", name, variant);
+                    Self::expect(statement, &expected, &found, loc)?;
+                }
+                Ok(ty)
+            }
+            Expression::Field { base, field } => {
+                let ty = match self.check_expression(locals, base, None, loc)? {
+                    Type::Pointer(pointee) if matches!(*pointee, Type::Struct { .. }) => *pointee,
+                    ty => ty,
+                };
+                if !matches!(ty, Type::Struct { .. }) {
+                    return Err(CompilerError::TypeMissmatch {
+                        statement: format!("operand of .{}", field),
+                        expected: "struct".to_owned(),
+                        found: ty.to_string(),
+                        loc,
+                    });
+                }
+                let (_, found) = ty.field(field).ok_or(CompilerError::UnknownField {
+                    field: field.clone(),
+                    ty: ty.to_string(),
+                    loc,
+                })?;
+                Ok(found)
+            }
+        }
+    }
+}
+
+impl Default for TypeChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
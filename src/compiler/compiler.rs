@@ -1,21 +1,36 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
-    ast::{Expression, Statement},
+    ast::{BinOp, Expression, MatchArm, Pattern, Statement},
     commons::Loc,
-    i32,
     op::{Arg, Op},
     value::Value,
 };
 
 use super::{
-    CompilerError,
+    CompilerError, CompilerWarning, Type, TypeChecker, addressed, always_offer,
     symbol::{FunctionStorage, FunctionSymbol},
+    unreachable_statement,
 };
 
+#[derive(Clone)]
+struct Eternal {
+    declared: Loc,
+    depth: usize,
+    initialized: bool,
+    constant: Option<Arg>,
+}
+
+#[derive(Clone)]
 pub struct Scope {
     next_local: usize,
     locals: HashMap<String, usize>,
+    eternals: HashMap<String, Eternal>,
+    temps: Vec<usize>,
+    next_temp: usize,
+    types: Vec<Type>,
+    addressed: HashSet<String>,
+    depth: usize,
     label_count: usize,
 }
 
@@ -23,19 +38,52 @@ impl Scope {
     pub fn new() -> Self {
         Self {
             locals: HashMap::new(),
+            eternals: HashMap::new(),
+            temps: vec![],
+            next_temp: 0,
+            types: vec![],
+            addressed: HashSet::new(),
+            depth: 0,
             next_local: 0,
             label_count: 0,
         }
     }
 
-    pub fn alloc_local(&mut self, name: &str) -> usize {
-        if let Some(idx) = self.get_local(name) {
+    pub fn alloc_local(&mut self, name: &str, ty: Type) -> usize {
+        if let Some(idx) = self.get_local(name)
+            && self.types[idx].slots() == ty.slots()
+        {
+            self.types[idx] = ty;
             return idx;
         }
 
+        let id = self.alloc_hidden(ty);
+        self.locals.insert(name.to_owned(), id);
+        id
+    }
+
+    pub fn alloc_hidden(&mut self, ty: Type) -> usize {
+        let slots = ty.slots();
+        let id = self.next_local + slots - 1;
+        self.next_local += slots;
+        self.types.resize(id, Type::Void);
+        self.types.push(ty);
+        id
+    }
+
+    // NOTE : Temporary never share a slot, an operand has to outlive the other
+    pub fn alloc_temp(&mut self, ty: Type) -> usize {
+        if let Some(id) = self.temps.get(self.next_temp).copied() {
+            self.next_temp += 1;
+            self.types[id] = ty;
+            return id;
+        }
+
         let id = self.next_local;
         self.next_local += 1;
-        self.locals.insert(name.to_owned(), id);
+        self.types.push(ty);
+        self.temps.push(id);
+        self.next_temp += 1;
         id
     }
 
@@ -48,30 +96,113 @@ impl Scope {
     pub fn get_local(&self, name: &str) -> Option<usize> {
         self.locals.get(name).copied()
     }
+
+    pub fn slot_type(&self, idx: usize) -> Type {
+        self.types.get(idx).cloned().unwrap_or(Type::I32)
+    }
+
+    pub fn is_mutable(&self, name: &str) -> bool {
+        !self.eternals.contains_key(name)
+    }
+
+    pub fn get_constant(&self, name: &str) -> Option<&Arg> {
+        self.eternals
+            .get(name)
+            .and_then(|eternal| eternal.constant.as_ref())
+    }
+
+    pub fn local_count(&self) -> usize {
+        self.next_local
+    }
+
+    pub fn locals(&self) -> Vec<(&str, usize)> {
+        let mut locals = self
+            .locals
+            .iter()
+            .map(|(name, idx)| (name.as_str(), *idx))
+            .collect::<Vec<_>>();
+        locals.sort_by_key(|(_, idx)| *idx);
+        locals
+    }
 }
 
+#[derive(Clone)]
 pub struct Compiler {
+    pub debug: bool,
     pub eternal: HashMap<String, usize>,
     pub eternal_value: Vec<u8>,
     pub spellcard: HashMap<String, FunctionSymbol>,
     pub spellcard_scope: HashMap<String, Scope>,
+    pub checker: TypeChecker,
+    pub warnings: Vec<CompilerWarning>,
 }
 
 impl Compiler {
     pub fn new() -> Self {
         Self {
+            debug: false,
             eternal: HashMap::new(),
             eternal_value: vec![],
             spellcard: HashMap::new(),
             spellcard_scope: HashMap::new(),
+            checker: TypeChecker::new(),
+            warnings: vec![],
         }
     }
 
-    pub fn compile(&mut self, ast: Vec<Statement>) -> Result<Vec<Op>, CompilerError> {
-        let mut scope = Scope::new();
-        let op = self.compile_statement(&mut scope, ast)?;
+    pub fn compile(&mut self, mut ast: Vec<Statement>) -> Result<Vec<Op>, CompilerError> {
+        self.checker.check(&mut ast)?;
+
+        let mut scope = self
+            .spellcard_scope
+            .remove("__global")
+            .unwrap_or_else(Scope::new);
+        let op = self.compile_statement(&mut scope, ast);
         self.spellcard_scope.insert("__global".to_owned(), scope);
-        Ok(op)
+        op
+    }
+
+    fn declare(&mut self, ast: &[Statement]) {
+        for i in ast {
+            match i {
+                Statement::Invite {
+                    name,
+                    args,
+                    variadic,
+                    return_type,
+                    ..
+                } => {
+                    self.spellcard.insert(
+                        name.to_owned(),
+                        FunctionSymbol {
+                            args: args.iter().flatten().map(|a| a.name.to_owned()).collect(),
+                            storage: FunctionStorage::External,
+                            return_type: return_type.clone().unwrap_or("void".to_owned()),
+                            variadic: *variadic || args.is_none(),
+                        },
+                    );
+                }
+                Statement::SpellCard { generics, .. } if !generics.is_empty() => {}
+                Statement::Impl { methods, .. } => self.declare(methods),
+                Statement::SpellCard {
+                    name,
+                    args,
+                    return_type,
+                    ..
+                } => {
+                    self.spellcard.insert(
+                        name.clone(),
+                        FunctionSymbol {
+                            args: args.iter().map(|a| a.name.to_owned()).collect(),
+                            return_type: return_type.clone().unwrap_or("void".to_owned()),
+                            storage: FunctionStorage::Internal,
+                            variadic: false,
+                        },
+                    );
+                }
+                _ => {}
+            }
+        }
     }
 
     fn compile_statement(
@@ -79,50 +210,112 @@ impl Compiler {
         scope: &mut Scope,
         ast: Vec<Statement>,
     ) -> Result<Vec<Op>, CompilerError> {
+        self.declare(&ast);
+        scope.addressed.extend(addressed(&ast));
+        if let Some(loc) = unreachable_statement(&ast) {
+            self.warnings.push(CompilerWarning::UnreachableCode { loc });
+        }
         let mut ops = vec![];
         for i in ast {
+            // NOTE : Temporary only live until the end of its statement
+            scope.next_temp = 0;
+            // NOTE : Until emit the location after its label so every iteration hit it
+            if self.debug
+                && !matches!(
+                    i,
+                    Statement::Until { .. } | Statement::SpellCard { .. } | Statement::Impl { .. }
+                )
+            {
+                ops.push(Op::Loc(i.loc()));
+            }
             match i {
-                Statement::Expression(expr) => {
-                    let (_, mut op) = self.parse_expression(scope, expr)?;
+                Statement::Expression(expr, loc) => {
+                    let (_, mut op) = self.parse_expression(scope, expr, loc)?;
                     ops.append(&mut op);
                 }
-                Statement::Invite { name } => {
-                    self.spellcard.insert(
-                        name.to_owned(),
-                        FunctionSymbol {
-                            args: vec![],
-                            storage: FunctionStorage::External,
-                            return_type: "void".to_string(),
-                        },
-                    );
+                Statement::Invite { name, .. } => {
                     ops.push(Op::Invite { name });
                 }
-                Statement::Eternal { name, .. } => {
-                    let offset = scope.alloc_local(&name);
+                Statement::Struct { .. } | Statement::Enum { .. } => {}
+                Statement::Eternal {
+                    name,
+                    annotation,
+                    loc,
+                } => {
+                    let offset = scope.alloc_local(&name, self.resolve(annotation.as_deref()));
+                    scope.eternals.insert(
+                        name.clone(),
+                        Eternal {
+                            declared: loc,
+                            depth: scope.depth,
+                            initialized: false,
+                            constant: None,
+                        },
+                    );
                     scope.locals.insert(name, offset);
                 }
-                Statement::Vow { name, .. } => {
-                    let offset = scope.alloc_local(&name);
+                Statement::Vow {
+                    name, annotation, ..
+                } => {
+                    let offset = scope.alloc_local(&name, self.resolve(annotation.as_deref()));
+                    scope.eternals.remove(&name);
                     scope.locals.insert(name, offset);
                 }
-                Statement::Assignment { name, value } => {
+                Statement::Assignment { name, value, loc } => {
                     let offset = scope
                         .locals
                         .get(&name)
                         .ok_or(CompilerError::UndefinedVariable {
-                            found: name,
-                            loc: Loc::default(),
+                            found: name.clone(),
+                            loc,
                         })?
                         .clone();
-                    let (arg, mut op) = self.parse_expression(scope, value)?;
+                    let literal = matches!(value, Expression::Literal(_));
+                    let depth = scope.depth;
+                    let constant = literal && !scope.addressed.contains(&name);
+                    if let Some(eternal) = scope.eternals.get_mut(&name) {
+                        if eternal.initialized || eternal.depth != depth {
+                            return Err(CompilerError::EternalReassign {
+                                name,
+                                declared: eternal.declared,
+                                loc,
+                            });
+                        }
+                        eternal.initialized = true;
+                    }
+
+                    let ty = scope.slot_type(offset);
+                    if ty.is_aggregate() {
+                        let ptr = scope.alloc_temp(Type::Pointer(Box::new(ty)));
+                        ops.push(Op::AddrOf {
+                            offset: ptr,
+                            local: offset,
+                        });
+                        self.store(scope, Arg::Local(ptr), value, &mut ops, loc)?;
+                        continue;
+                    }
+
+                    let (arg, mut op) = self.parse_expression(scope, value, loc)?;
+                    // NOTE : Eternal whose address is taken can still change through the pointer
+                    if let Some(eternal) = scope.eternals.get_mut(&name)
+                        && constant
+                    {
+                        eternal.constant = Some(arg.clone());
+                    }
 
                     ops.append(&mut op);
                     ops.push(Op::EternalAssign { offset, arg });
                 }
+                Statement::Store { target, value, loc } => {
+                    let (ptr, mut op_ptr) = self.address(scope, target, loc)?;
+                    ops.append(&mut op_ptr);
+                    self.store(scope, ptr, value, &mut ops, loc)?;
+                }
                 Statement::Foreseen {
                     condition,
                     then_branch,
                     else_branch,
+                    loc,
                 } => {
                     let id = scope.alloc_label();
                     let end = format!(".L{}", id);
@@ -130,13 +323,13 @@ impl Compiler {
                     let id = scope.alloc_label();
                     let otherwise = format!(".L{}", id);
 
-                    let (arg, mut op_condition) = self.parse_expression(scope, condition)?;
+                    let (arg, mut op_condition) = self.parse_expression(scope, condition, loc)?;
                     ops.append(&mut op_condition);
 
-                    let mut then_body = self.compile_statement(scope, then_branch)?;
+                    let mut then_body = self.compile_block(scope, then_branch)?;
                     match else_branch {
                         Some(body) => {
-                            let mut else_body = self.compile_statement(scope, body)?;
+                            let mut else_body = self.compile_block(scope, body)?;
 
                             ops.push(Op::JmpIfNot {
                                 name: otherwise.clone(),
@@ -159,17 +352,24 @@ impl Compiler {
                     }
                     ops.push(Op::Label(end));
                 }
-                Statement::Until { condition, body } => {
+                Statement::Until {
+                    condition,
+                    body,
+                    loc,
+                } => {
                     let id = scope.alloc_label();
                     let start = format!(".L{}", id);
 
                     let id = scope.alloc_label();
                     let end = format!(".L{}", id);
 
-                    let (arg, mut op) = self.parse_expression(scope, condition)?;
-                    let mut body = self.compile_statement(scope, body)?;
+                    let (arg, mut op) = self.parse_expression(scope, condition, loc)?;
+                    let mut body = self.compile_block(scope, body)?;
 
                     ops.push(Op::Label(start.clone()));
+                    if self.debug {
+                        ops.push(Op::Loc(loc));
+                    }
                     ops.append(&mut op);
                     ops.push(Op::JmpIfNot {
                         name: end.clone(),
@@ -179,23 +379,57 @@ impl Compiler {
                     ops.push(Op::Jmp { name: start });
                     ops.push(Op::Label(end));
                 }
+                Statement::Match { value, arms, loc } => {
+                    self.compile_match(scope, value, arms, &mut ops, loc)?;
+                }
+                Statement::SpellCard { generics, .. } if !generics.is_empty() => {}
                 Statement::SpellCard {
                     name,
                     body,
                     args,
                     return_type,
+                    loc,
                     ..
                 } => {
+                    let offered = always_offer(&body);
                     let mut scope = Scope::new();
                     let mut temp = vec![];
                     for (i, arg) in args.iter().enumerate() {
-                        let id = scope.alloc_local(&arg.name);
+                        let ty = self.resolve(Some(&arg.annotation));
+                        if !ty.is_aggregate() {
+                            let id = scope.alloc_local(&arg.name, ty);
+                            temp.push(Op::ParamAssign {
+                                offset: i,
+                                arg: Arg::Local(id),
+                            });
+                            continue;
+                        }
+                        let src = scope.alloc_hidden(Type::Pointer(Box::new(ty.clone())));
                         temp.push(Op::ParamAssign {
                             offset: i,
-                            arg: Arg::Local(id),
+                            arg: Arg::Local(src),
+                        });
+                        let id = scope.alloc_local(&arg.name, ty.clone());
+                        let dst = scope.alloc_temp(Type::Pointer(Box::new(ty)));
+                        temp.push(Op::AddrOf {
+                            offset: dst,
+                            local: id,
                         });
+                        Self::copy(&mut scope, Arg::Local(dst), Arg::Local(src), &mut temp);
                     }
                     let mut body = self.compile_statement(&mut scope, body)?;
+                    if !offered {
+                        match return_type {
+                            Some(return_type) if return_type != "void" => {
+                                return Err(CompilerError::MissingOffer {
+                                    name,
+                                    return_type,
+                                    loc,
+                                });
+                            }
+                            _ => body.push(Op::Ret(None)),
+                        }
+                    }
                     ops.push(Op::Function(name.clone()));
                     if scope.next_local > 0 {
                         ops.push(Op::StackAlloc(scope.next_local));
@@ -203,18 +437,16 @@ impl Compiler {
                     ops.append(&mut temp);
                     ops.append(&mut body);
 
-                    self.spellcard.insert(
-                        name,
-                        FunctionSymbol {
-                            args: args.iter().map(|a| a.name.to_owned()).collect(),
-                            return_type: return_type.unwrap_or("void".to_owned()),
-                            storage: FunctionStorage::Internal,
-                        },
-                    );
+                    self.spellcard_scope.insert(name, scope);
+                }
+                Statement::Trait { .. } => {}
+                Statement::Impl { methods, .. } => {
+                    let mut method = self.compile_statement(scope, methods)?;
+                    ops.append(&mut method);
                 }
-                Statement::Offer(expression) => match expression {
+                Statement::Offer(expression, loc) => match expression {
                     Some(expression) => {
-                        let (arg, mut op) = self.parse_expression(scope, expression)?;
+                        let (arg, mut op) = self.parse_expression(scope, expression, loc)?;
                         ops.append(&mut op);
                         ops.push(Op::Ret(Some(arg)));
                     }
@@ -227,14 +459,434 @@ impl Compiler {
         Ok(ops)
     }
 
+    fn compile_block(
+        &mut self,
+        scope: &mut Scope,
+        ast: Vec<Statement>,
+    ) -> Result<Vec<Op>, CompilerError> {
+        scope.depth += 1;
+        let ops = self.compile_statement(scope, ast);
+        scope.depth -= 1;
+        ops
+    }
+
+    fn compile_match(
+        &mut self,
+        scope: &mut Scope,
+        value: Expression,
+        arms: Vec<MatchArm>,
+        ops: &mut Vec<Op>,
+        loc: Loc,
+    ) -> Result<(), CompilerError> {
+        let integer = arms
+            .iter()
+            .any(|arm| matches!(arm.pattern, Pattern::Range { .. }));
+        let variant = arms
+            .iter()
+            .any(|arm| matches!(arm.pattern, Pattern::Variant { .. }));
+        let (subject, ty) = match value {
+            value if integer => {
+                let (arg, mut op) = self.parse_expression(scope, value, loc)?;
+                ops.append(&mut op);
+                let ty = Self::arg_type(scope, &arg);
+                let subject = scope.alloc_hidden(ty.clone());
+                ops.push(Op::EternalAssign {
+                    offset: subject,
+                    arg,
+                });
+                (subject, ty)
+            }
+            value @ (Expression::Variable(_)
+            | Expression::Deref(_)
+            | Expression::Index { .. }
+            | Expression::Field { .. }) => {
+                let (ptr, mut op) = self.address(scope, value, loc)?;
+                ops.append(&mut op);
+                let ty = Self::pointee(scope, &ptr);
+                let subject = scope.alloc_hidden(Type::Pointer(Box::new(ty.clone())));
+                ops.push(Op::EternalAssign {
+                    offset: subject,
+                    arg: ptr,
+                });
+                (subject, ty)
+            }
+            Expression::Variant {
+                name,
+                variant,
+                args,
+            } => {
+                let ty = self.resolve(Some(&name));
+                let value = Expression::Variant {
+                    name,
+                    variant,
+                    args,
+                };
+                let ptr = self.spill(scope, ty.clone(), value, ops, loc)?;
+                let subject = scope.alloc_hidden(Type::Pointer(Box::new(ty.clone())));
+                ops.push(Op::EternalAssign {
+                    offset: subject,
+                    arg: ptr,
+                });
+                (subject, ty)
+            }
+            value => {
+                let (_, mut op) = self.parse_expression(scope, value, loc)?;
+                ops.append(&mut op);
+                (0, Type::Void)
+            }
+        };
+        let tag = match variant {
+            true => {
+                let tag = scope.alloc_hidden(Type::U32);
+                ops.push(Op::Load {
+                    offset: tag,
+                    ty: Type::U32,
+                    ptr: Arg::Local(subject),
+                });
+                tag
+            }
+            false => 0,
+        };
+
+        let end = format!(".L{}", scope.alloc_label());
+        for arm in arms {
+            let next = format!(".L{}", scope.alloc_label());
+            let mut shadowed = vec![];
+            match arm.pattern {
+                Pattern::Wildcard => {}
+                Pattern::Variant {
+                    variant, bindings, ..
+                } => {
+                    let (index, fields) = ty
+                        .variant(&variant)
+                        .ok_or(CompilerError::NotAddressable { loc })?;
+                    let hit = scope.alloc_temp(Type::Bool);
+                    ops.push(Op::BinOp {
+                        binop: BinOp::Equal,
+                        ty: Type::U32,
+                        offset: hit,
+                        lhs: Arg::Local(tag),
+                        rhs: Arg::Literal(Value::U32(index)),
+                    });
+                    ops.push(Op::JmpIfNot {
+                        name: next.clone(),
+                        arg: Arg::Local(hit),
+                    });
+                    for (binding, (start, field)) in bindings.into_iter().zip(fields) {
+                        if binding == "_" {
+                            continue;
+                        }
+                        let src = scope.alloc_temp(Type::Pointer(Box::new(field.clone())));
+                        ops.push(Op::Element {
+                            offset: src,
+                            base: Arg::Local(subject),
+                            index: Arg::Literal(Value::I64(start as i64)),
+                            size: 1,
+                        });
+                        // NOTE : Binding get a fresh slot so an outer local of the same name survive the arm
+                        shadowed.push((
+                            binding.clone(),
+                            scope.locals.get(&binding).copied(),
+                            scope.eternals.remove(&binding),
+                        ));
+                        let local = scope.alloc_hidden(field.clone());
+                        scope.locals.insert(binding, local);
+                        if !field.is_aggregate() {
+                            ops.push(Op::Load {
+                                offset: local,
+                                ty: field,
+                                ptr: Arg::Local(src),
+                            });
+                            continue;
+                        }
+                        let dst = scope.alloc_temp(Type::Pointer(Box::new(field)));
+                        ops.push(Op::AddrOf { offset: dst, local });
+                        Self::copy(scope, Arg::Local(dst), Arg::Local(src), ops);
+                    }
+                }
+                Pattern::Range {
+                    start,
+                    end: last,
+                    inclusive,
+                } => {
+                    let bound = |int: i128| ty.literal(int).map(Arg::Literal);
+                    let (start, last) =
+                        bound(start)
+                            .zip(bound(last))
+                            .ok_or(CompilerError::LiteralOutOfRange {
+                                value: start,
+                                ty: ty.to_string(),
+                                loc,
+                            })?;
+                    let checks = match inclusive {
+                        true if start == last => vec![(BinOp::Equal, start, false)],
+                        true => vec![(BinOp::Less, start, true), (BinOp::Greater, last, true)],
+                        false => vec![(BinOp::Less, start, true), (BinOp::Less, last, false)],
+                    };
+                    for (binop, rhs, negate) in checks {
+                        let mut hit = scope.alloc_temp(Type::Bool);
+                        ops.push(Op::BinOp {
+                            binop,
+                            ty: ty.clone(),
+                            offset: hit,
+                            lhs: Arg::Local(subject),
+                            rhs,
+                        });
+                        if negate {
+                            let arg = Arg::Local(hit);
+                            hit = scope.alloc_temp(Type::Bool);
+                            ops.push(Op::UnaryNot { offset: hit, arg });
+                        }
+                        ops.push(Op::JmpIfNot {
+                            name: next.clone(),
+                            arg: Arg::Local(hit),
+                        });
+                    }
+                }
+            }
+            let mut body = self.compile_block(scope, arm.body)?;
+            ops.append(&mut body);
+            for (binding, local, eternal) in shadowed {
+                match local {
+                    Some(local) => scope.locals.insert(binding.clone(), local),
+                    None => scope.locals.remove(&binding),
+                };
+                if let Some(eternal) = eternal {
+                    scope.eternals.insert(binding, eternal);
+                }
+            }
+            ops.push(Op::Jmp { name: end.clone() });
+            ops.push(Op::Label(next));
+        }
+        ops.push(Op::Label(end));
+        Ok(())
+    }
+
+    fn resolve(&self, annotation: Option<&str>) -> Type {
+        annotation
+            .and_then(|annotation| self.checker.resolve_type(annotation))
+            .unwrap_or(Type::I32)
+    }
+
+    fn arg_type(scope: &Scope, arg: &Arg) -> Type {
+        match arg {
+            Arg::Local(offset) => scope.slot_type(*offset),
+            Arg::Literal(value) => Type::of(value),
+            Arg::DataOffset(_) => Type::CStr,
+        }
+    }
+
+    fn pointee(scope: &Scope, ptr: &Arg) -> Type {
+        match Self::arg_type(scope, ptr) {
+            Type::Pointer(pointee) => *pointee,
+            _ => Type::I32,
+        }
+    }
+
+    fn address(
+        &mut self,
+        scope: &mut Scope,
+        expr: Expression,
+        loc: Loc,
+    ) -> Result<(Arg, Vec<Op>), CompilerError> {
+        match expr {
+            Expression::Variable(name) => {
+                let local = scope
+                    .get_local(&name)
+                    .ok_or(CompilerError::UndefinedVariable { found: name, loc })?;
+                let offset = scope.alloc_temp(Type::Pointer(Box::new(scope.slot_type(local))));
+                Ok((Arg::Local(offset), vec![Op::AddrOf { offset, local }]))
+            }
+            Expression::Deref(ptr) => self.parse_expression(scope, *ptr, loc),
+            Expression::Index { array, index } => {
+                let (base, mut ops) = self.address(scope, *array, loc)?;
+                let Type::Array(element, length) = Self::pointee(scope, &base) else {
+                    return Err(CompilerError::NotAddressable { loc });
+                };
+                let (index, mut op_index) = self.parse_expression(scope, *index, loc)?;
+                ops.append(&mut op_index);
+                if self.debug {
+                    ops.push(Op::BoundsCheck {
+                        index: index.clone(),
+                        length,
+                    });
+                }
+                let size = element.size();
+                let offset = scope.alloc_temp(Type::Pointer(element));
+                ops.push(Op::Element {
+                    offset,
+                    base,
+                    index,
+                    size,
+                });
+                Ok((Arg::Local(offset), ops))
+            }
+            Expression::Field { base, field } => {
+                let (ptr, mut ops) = match *base {
+                    base @ (Expression::Variable(_)
+                    | Expression::Deref(_)
+                    | Expression::Index { .. }
+                    | Expression::Field { .. }) => self.address(scope, base, loc)?,
+                    base => self.parse_expression(scope, base, loc)?,
+                };
+                let (base, ty) = match Self::pointee(scope, &ptr) {
+                    Type::Pointer(pointee) if matches!(*pointee, Type::Struct { .. }) => {
+                        let ty = Type::Pointer(pointee.clone());
+                        let offset = scope.alloc_temp(ty.clone());
+                        ops.push(Op::Load { offset, ty, ptr });
+                        (Arg::Local(offset), *pointee)
+                    }
+                    ty => (ptr, ty),
+                };
+                let (start, field) = ty
+                    .field(&field)
+                    .ok_or(CompilerError::NotAddressable { loc })?;
+                let offset = scope.alloc_temp(Type::Pointer(Box::new(field)));
+                ops.push(Op::Element {
+                    offset,
+                    base,
+                    index: Arg::Literal(Value::I64(start as i64)),
+                    size: 1,
+                });
+                Ok((Arg::Local(offset), ops))
+            }
+            _ => Err(CompilerError::NotAddressable { loc }),
+        }
+    }
+
+    fn store(
+        &mut self,
+        scope: &mut Scope,
+        ptr: Arg,
+        value: Expression,
+        ops: &mut Vec<Op>,
+        loc: Loc,
+    ) -> Result<(), CompilerError> {
+        let ty = Self::pointee(scope, &ptr);
+        match (value, &ty) {
+            (Expression::Array(elements), Type::Array(element, _)) => {
+                for (i, value) in elements.into_iter().enumerate() {
+                    let offset = scope.alloc_temp(Type::Pointer(element.clone()));
+                    ops.push(Op::Element {
+                        offset,
+                        base: ptr.clone(),
+                        index: Arg::Literal(Value::I64(i as i64)),
+                        size: element.size(),
+                    });
+                    self.store(scope, Arg::Local(offset), value, ops, loc)?;
+                }
+            }
+            (Expression::Struct { fields, .. }, Type::Struct { .. }) => {
+                for (field, value) in fields {
+                    let (start, field) = ty
+                        .field(&field)
+                        .ok_or(CompilerError::NotAddressable { loc })?;
+                    let offset = scope.alloc_temp(Type::Pointer(Box::new(field)));
+                    ops.push(Op::Element {
+                        offset,
+                        base: ptr.clone(),
+                        index: Arg::Literal(Value::I64(start as i64)),
+                        size: 1,
+                    });
+                    self.store(scope, Arg::Local(offset), value, ops, loc)?;
+                }
+            }
+            (Expression::Variant { variant, args, .. }, Type::Enum { .. }) => {
+                let (tag, fields) = ty
+                    .variant(&variant)
+                    .ok_or(CompilerError::NotAddressable { loc })?;
+                ops.push(Op::Store {
+                    ty: Type::U32,
+                    ptr: ptr.clone(),
+                    arg: Arg::Literal(Value::U32(tag)),
+                });
+                for (value, (start, field)) in args.into_iter().zip(fields) {
+                    let offset = scope.alloc_temp(Type::Pointer(Box::new(field)));
+                    ops.push(Op::Element {
+                        offset,
+                        base: ptr.clone(),
+                        index: Arg::Literal(Value::I64(start as i64)),
+                        size: 1,
+                    });
+                    self.store(scope, Arg::Local(offset), value, ops, loc)?;
+                }
+            }
+            (value, ty) if ty.is_aggregate() => {
+                let (src, mut op) = self.address(scope, value, loc)?;
+                ops.append(&mut op);
+                Self::copy(scope, ptr, src, ops);
+            }
+            (value, _) => {
+                let (arg, mut op) = self.parse_expression(scope, value, loc)?;
+                ops.append(&mut op);
+                ops.push(Op::Store { ty, ptr, arg });
+            }
+        }
+        Ok(())
+    }
+
+    fn spill(
+        &mut self,
+        scope: &mut Scope,
+        ty: Type,
+        value: Expression,
+        ops: &mut Vec<Op>,
+        loc: Loc,
+    ) -> Result<Arg, CompilerError> {
+        let local = scope.alloc_hidden(ty.clone());
+        let ptr = scope.alloc_temp(Type::Pointer(Box::new(ty)));
+        ops.push(Op::AddrOf { offset: ptr, local });
+        self.store(scope, Arg::Local(ptr), value, ops, loc)?;
+        Ok(Arg::Local(ptr))
+    }
+
+    // NOTE : The chunk divide the size, so a nested aggregate is never written past its end
+    fn copy(scope: &mut Scope, dst: Arg, src: Arg, ops: &mut Vec<Op>) {
+        let ty = Self::pointee(scope, &dst);
+        let chunk = match ty.size() {
+            size if size % 8 == 0 => Type::U64,
+            size if size % 4 == 0 => Type::U32,
+            size if size % 2 == 0 => Type::U16,
+            _ => Type::U8,
+        };
+        for i in 0..ty.size() / chunk.size() {
+            let from = scope.alloc_temp(Type::Pointer(Box::new(chunk.clone())));
+            let to = scope.alloc_temp(Type::Pointer(Box::new(chunk.clone())));
+            let value = scope.alloc_temp(chunk.clone());
+            let index = Arg::Literal(Value::I64(i as i64));
+            ops.push(Op::Element {
+                offset: from,
+                base: src.clone(),
+                index: index.clone(),
+                size: chunk.size(),
+            });
+            ops.push(Op::Load {
+                offset: value,
+                ty: chunk.clone(),
+                ptr: Arg::Local(from),
+            });
+            ops.push(Op::Element {
+                offset: to,
+                base: dst.clone(),
+                index,
+                size: chunk.size(),
+            });
+            ops.push(Op::Store {
+                ty: chunk.clone(),
+                ptr: Arg::Local(to),
+                arg: Arg::Local(value),
+            });
+        }
+    }
+
     fn parse_expression(
         &mut self,
         scope: &mut Scope,
         expr: Expression,
+        loc: Loc,
     ) -> Result<(Arg, Vec<Op>), CompilerError> {
         match expr {
             Expression::Literal(value) => match value {
-                Value::I32(val) => Ok((Arg::Literal(i32!(val)), vec![])),
                 Value::String(val) => {
                     let mut bytes = val.clone().into_bytes();
                     let offset = self.eternal_value.len();
@@ -243,20 +895,84 @@ impl Compiler {
                     self.eternal.insert(val, offset);
                     Ok((Arg::DataOffset(offset), vec![]))
                 }
+                value => Ok((Arg::Literal(value), vec![])),
             },
-            Expression::Variable(offset) => {
+            Expression::Variable(name) => {
+                if let Some(constant) = scope.get_constant(&name) {
+                    return Ok((constant.clone(), vec![]));
+                }
                 let offset = scope
-                    .get_local(&offset)
+                    .get_local(&name)
                     .ok_or(CompilerError::UndefinedVariable {
-                        found: offset,
-                        loc: Loc::default(),
+                        found: name.clone(),
+                        loc,
                     })?;
+                // NOTE : Store through a pointer only write the width of its type,
+                // so an addressed local narrower than its slot is read from memory
+                let ty = scope.slot_type(offset);
+                if scope.addressed.contains(&name) && ty.slots() == 1 && ty.size() < 8 {
+                    let ptr = scope.alloc_temp(Type::Pointer(Box::new(ty.clone())));
+                    let value = scope.alloc_temp(ty.clone());
+                    let ops = vec![
+                        Op::AddrOf {
+                            offset: ptr,
+                            local: offset,
+                        },
+                        Op::Load {
+                            offset: value,
+                            ty,
+                            ptr: Arg::Local(ptr),
+                        },
+                    ];
+                    return Ok((Arg::Local(value), ops));
+                }
                 Ok((Arg::Local(offset), vec![]))
             }
+            Expression::AddressOf(expr) => match *expr {
+                expr @ (Expression::Variable(_)
+                | Expression::Index { .. }
+                | Expression::Field { .. }) => self.address(scope, expr, loc),
+                _ => Err(CompilerError::NotAddressable { loc }),
+            },
+            expr @ (Expression::Index { .. } | Expression::Field { .. }) => {
+                let (ptr, mut ops) = self.address(scope, expr, loc)?;
+                let ty = Self::pointee(scope, &ptr);
+                let offset = scope.alloc_temp(ty.clone());
+                ops.push(Op::Load { offset, ty, ptr });
+                Ok((Arg::Local(offset), ops))
+            }
+            Expression::Array(elements) => Err(CompilerError::TypeMissmatch {
+                statement: "array literal".to_owned(),
+                expected: "scalar".to_owned(),
+                found: format!("{} element", elements.len()),
+                loc,
+            }),
+            Expression::Struct { name, .. } => Err(CompilerError::TypeMissmatch {
+                statement: "struct literal".to_owned(),
+                expected: "scalar".to_owned(),
+                found: name,
+                loc,
+            }),
+            Expression::Variant { name, .. } => Err(CompilerError::TypeMissmatch {
+                statement: "enum literal".to_owned(),
+                expected: "scalar".to_owned(),
+                found: name,
+                loc,
+            }),
+            Expression::MethodCall { .. } => {
+                unreachable!("Method call is turned into a call by the checker")
+            }
+            Expression::Deref(expr) => {
+                let (ptr, mut ops) = self.parse_expression(scope, *expr, loc)?;
+                let ty = Self::pointee(scope, &ptr);
+                let offset = scope.alloc_temp(ty.clone());
+                ops.push(Op::Load { offset, ty, ptr });
+                Ok((Arg::Local(offset), ops))
+            }
             Expression::Unary { op, arg } => {
                 let mut opsbin = vec![];
-                let (lhs, mut opl) = self.parse_expression(scope, *arg)?;
-                let offset = scope.alloc_local("__temp");
+                let (lhs, mut opl) = self.parse_expression(scope, *arg, loc)?;
+                let offset = scope.alloc_temp(Type::Bool);
                 opsbin.append(&mut opl);
                 match op {
                     crate::ast::UnaryOp::Not => {
@@ -265,16 +981,76 @@ impl Compiler {
                 }
                 Ok((Arg::Local(offset), opsbin))
             }
+            Expression::Binary {
+                op: op @ (BinOp::And | BinOp::Or),
+                left,
+                right,
+            } => {
+                let mut ops = vec![];
+                let end = format!(".L{}", scope.alloc_label());
+                let result = scope.alloc_temp(Type::Bool);
+                let (lhs, mut opl) = self.parse_expression(scope, *left, loc)?;
+                ops.append(&mut opl);
+                ops.push(Op::EternalAssign {
+                    offset: result,
+                    arg: lhs,
+                });
+                let decided = match op {
+                    BinOp::Or => {
+                        let offset = scope.alloc_temp(Type::Bool);
+                        ops.push(Op::UnaryNot {
+                            offset,
+                            arg: Arg::Local(result),
+                        });
+                        offset
+                    }
+                    _ => result,
+                };
+                ops.push(Op::JmpIfNot {
+                    name: end.clone(),
+                    arg: Arg::Local(decided),
+                });
+                let (rhs, mut opr) = self.parse_expression(scope, *right, loc)?;
+                ops.append(&mut opr);
+                ops.push(Op::EternalAssign {
+                    offset: result,
+                    arg: rhs,
+                });
+                ops.push(Op::Label(end));
+                Ok((Arg::Local(result), ops))
+            }
             Expression::Binary { op, left, right } => {
                 let mut opsbin = vec![];
-                let (lhs, mut opl) = self.parse_expression(scope, *left)?;
-                let (rhs, mut opr) = self.parse_expression(scope, *right)?;
-                let offset = scope.alloc_local("__temp");
+                let (lhs, mut opl) = self.parse_expression(scope, *left, loc)?;
+                let (rhs, mut opr) = self.parse_expression(scope, *right, loc)?;
+                let ty = Self::arg_type(scope, &lhs);
+                let offset = match op {
+                    BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div => {
+                        scope.alloc_temp(ty.clone())
+                    }
+                    BinOp::Equal | BinOp::Greater | BinOp::Less => scope.alloc_temp(Type::Bool),
+                    BinOp::And | BinOp::Or => unreachable!("Logical operator is lowered above"),
+                };
 
                 opsbin.append(&mut opl);
                 opsbin.append(&mut opr);
+                let rhs = match (&ty, &op) {
+                    (Type::Pointer(pointee), BinOp::Add | BinOp::Sub) if pointee.size() > 1 => {
+                        let scaled = scope.alloc_temp(Type::I64);
+                        opsbin.push(Op::BinOp {
+                            binop: BinOp::Mul,
+                            ty: Type::I64,
+                            offset: scaled,
+                            lhs: rhs,
+                            rhs: Arg::Literal(Value::I64(pointee.size() as i64)),
+                        });
+                        Arg::Local(scaled)
+                    }
+                    _ => rhs,
+                };
                 opsbin.push(Op::BinOp {
                     binop: op,
+                    ty,
                     offset,
                     lhs,
                     rhs,
@@ -283,27 +1059,56 @@ impl Compiler {
                 Ok((Arg::Local(offset), opsbin))
             }
             Expression::Call { function, args } => {
-                let _spellcard =
+                let spellcard =
                     self.spellcard
                         .get(&function)
                         .ok_or(CompilerError::UnknownFunction {
                             found: function.clone(),
-                            loc: Loc::default(),
+                            loc,
                         })?;
+                let expected = spellcard.args.len();
+                if args.len() < expected || (!spellcard.variadic && args.len() > expected) {
+                    return Err(CompilerError::ArityMismatch {
+                        name: function,
+                        expected,
+                        found: args.len(),
+                        variadic: spellcard.variadic,
+                        loc,
+                    });
+                }
+
+                let internal = matches!(spellcard.storage, FunctionStorage::Internal);
+                let signature = self.checker.function(&function);
+                let params = signature
+                    .and_then(|signature| signature.params.clone())
+                    .unwrap_or_default();
+                let ty = signature.map_or(Type::I32, |signature| signature.return_type.clone());
 
                 let mut ops = vec![];
                 let mut args_expr = vec![];
-                for expr in args {
-                    let (arg, mut op) = self.parse_expression(scope, expr)?;
+                let mut types = vec![];
+                for (i, expr) in args.into_iter().enumerate() {
+                    if let Some(param) = params.get(i).filter(|param| param.is_aggregate()) {
+                        let ptr = self.spill(scope, param.clone(), expr, &mut ops, loc)?;
+                        types.push(match internal {
+                            true => Type::Pointer(Box::new(param.clone())),
+                            false => param.clone(),
+                        });
+                        args_expr.push(ptr);
+                        continue;
+                    }
+                    let (arg, mut op) = self.parse_expression(scope, expr, loc)?;
                     ops.append(&mut op);
+                    types.push(Self::arg_type(scope, &arg));
                     args_expr.push(arg);
                 }
 
-                let offset = scope.alloc_local("__temp");
+                let offset = scope.alloc_temp(ty);
                 ops.push(Op::Call {
                     result: offset,
                     name: function,
                     args: args_expr,
+                    types,
                 });
 
                 Ok((Arg::Local(offset), ops))
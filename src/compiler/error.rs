@@ -17,6 +17,88 @@ pub enum CompilerError {
         found: String,
         loc: Loc,
     },
+    UnknownType {
+        found: String,
+        loc: Loc,
+    },
+    UninferredType {
+        name: String,
+        loc: Loc,
+    },
+    EternalReassign {
+        name: String,
+        declared: Loc,
+        loc: Loc,
+    },
+    ArityMismatch {
+        name: String,
+        expected: usize,
+        found: usize,
+        variadic: bool,
+        loc: Loc,
+    },
+    MissingOffer {
+        name: String,
+        return_type: String,
+        loc: Loc,
+    },
+    LiteralOutOfRange {
+        value: i128,
+        ty: String,
+        loc: Loc,
+    },
+    NotAddressable {
+        loc: Loc,
+    },
+    IndexOutOfBounds {
+        index: i128,
+        length: usize,
+        loc: Loc,
+    },
+    UnknownField {
+        field: String,
+        ty: String,
+        loc: Loc,
+    },
+    MissingField {
+        field: String,
+        ty: String,
+        loc: Loc,
+    },
+    UnknownVariant {
+        variant: String,
+        ty: String,
+        loc: Loc,
+    },
+    NonExhaustiveMatch {
+        missing: Vec<String>,
+        loc: Loc,
+    },
+    CannotInfer {
+        generic: String,
+        name: String,
+        loc: Loc,
+    },
+    UnknownTrait {
+        found: String,
+        loc: Loc,
+    },
+    UnknownMethod {
+        method: String,
+        ty: String,
+        loc: Loc,
+    },
+    MissingMethod {
+        method: String,
+        ty: String,
+        trait_name: String,
+        loc: Loc,
+    },
+    UnsatisfiedBound {
+        ty: String,
+        trait_name: String,
+        loc: Loc,
+    },
 }
 
 impl Error for CompilerError {}
@@ -40,6 +122,100 @@ impl std::fmt::Display for CompilerError {
                 "Undefined function symbol of {} at {}",
                 found, loc
             )),
+            CompilerError::UnknownType { found, loc } => {
+                f.write_fmt(format_args!("Unknown type {} at {}", found, loc))
+            }
+            CompilerError::UninferredType { name, loc } => f.write_fmt(format_args!(
+                "Type of {} is not known before its first assignment at {}",
+                name, loc
+            )),
+            CompilerError::EternalReassign {
+                name,
+                declared,
+                loc,
+            } => f.write_fmt(format_args!(
+                "Cannot assign eternal {} at {}, it is declared at {}",
+                name, loc, declared
+            )),
+            CompilerError::ArityMismatch {
+                name,
+                expected,
+                found,
+                variadic,
+                loc,
+            } => f.write_fmt(format_args!(
+                "{} expect {}{} argument, but found {}, at {}",
+                name,
+                if *variadic { "at least " } else { "" },
+                expected,
+                found,
+                loc
+            )),
+            CompilerError::MissingOffer {
+                name,
+                return_type,
+                loc,
+            } => f.write_fmt(format_args!(
+                "Spellcard {} at {} can end without offering {}",
+                name, loc, return_type
+            )),
+            CompilerError::LiteralOutOfRange { value, ty, loc } => f.write_fmt(format_args!(
+                "Literal {} does not fit in {} at {}",
+                value, ty, loc
+            )),
+            CompilerError::NotAddressable { loc } => f.write_fmt(format_args!(
+                "Expression at {} does not designate a memory location",
+                loc
+            )),
+            CompilerError::IndexOutOfBounds { index, length, loc } => f.write_fmt(format_args!(
+                "Index {} is out of bounds for array of length {} at {}",
+                index, length, loc
+            )),
+            CompilerError::UnknownField { field, ty, loc } => f.write_fmt(format_args!(
+                "Struct {} has no field named {} at {}",
+                ty, field, loc
+            )),
+            CompilerError::MissingField { field, ty, loc } => f.write_fmt(format_args!(
+                "Missing field {} in literal of struct {} at {}",
+                field, ty, loc
+            )),
+            CompilerError::UnknownVariant { variant, ty, loc } => f.write_fmt(format_args!(
+                "Enum {} has no variant named {} at {}",
+                ty, variant, loc
+            )),
+            CompilerError::NonExhaustiveMatch { missing, loc } => f.write_fmt(format_args!(
+                "Match at {} does not cover {}",
+                loc,
+                missing.join(", ")
+            )),
+            CompilerError::CannotInfer { generic, name, loc } => f.write_fmt(format_args!(
+                "Cannot infer type parameter {} of {} at {}",
+                generic, name, loc
+            )),
+            CompilerError::UnknownTrait { found, loc } => {
+                f.write_fmt(format_args!("Unknown trait {} at {}", found, loc))
+            }
+            CompilerError::UnknownMethod { method, ty, loc } => f.write_fmt(format_args!(
+                "Type {} has no method named {} at {}",
+                ty, method, loc
+            )),
+            CompilerError::MissingMethod {
+                method,
+                ty,
+                trait_name,
+                loc,
+            } => f.write_fmt(format_args!(
+                "Implementation of {} for {} is missing method {} at {}",
+                trait_name, ty, method, loc
+            )),
+            CompilerError::UnsatisfiedBound {
+                ty,
+                trait_name,
+                loc,
+            } => f.write_fmt(format_args!(
+                "Type {} does not implement {} at {}",
+                ty, trait_name, loc
+            )),
         }
     }
 }
@@ -0,0 +1,115 @@
+use std::collections::HashSet;
+
+use crate::{
+    ast::{Expression, Statement},
+    commons::Loc,
+};
+
+// NOTE : `until` is assumed to be able to exit since its condition is not evaluated
+pub fn always_offer(ast: &[Statement]) -> bool {
+    ast.iter().any(|stmt| match stmt {
+        Statement::Offer(..) => true,
+        Statement::Foreseen {
+            then_branch,
+            else_branch: Some(else_branch),
+            ..
+        } => always_offer(then_branch) && always_offer(else_branch),
+        Statement::Match { arms, .. } => {
+            !arms.is_empty() && arms.iter().all(|arm| always_offer(&arm.body))
+        }
+        _ => false,
+    })
+}
+
+pub fn unreachable_statement(ast: &[Statement]) -> Option<Loc> {
+    let idx = ast
+        .iter()
+        .position(|stmt| always_offer(std::slice::from_ref(stmt)))?;
+    ast[idx + 1..]
+        .iter()
+        .find(|stmt| {
+            !matches!(
+                stmt,
+                Statement::SpellCard { .. }
+                    | Statement::Invite { .. }
+                    | Statement::Struct { .. }
+                    | Statement::Enum { .. }
+                    | Statement::Trait { .. }
+                    | Statement::Impl { .. }
+            )
+        })
+        .map(|stmt| stmt.loc())
+}
+
+pub fn addressed(ast: &[Statement]) -> HashSet<String> {
+    fn expression(expr: &Expression, names: &mut HashSet<String>) {
+        match expr {
+            Expression::AddressOf(expr) => match expr.as_ref() {
+                Expression::Variable(name) => {
+                    names.insert(name.clone());
+                }
+                expr => expression(expr, names),
+            },
+            Expression::Unary { arg, .. }
+            | Expression::Deref(arg)
+            | Expression::Field { base: arg, .. } => expression(arg, names),
+            Expression::Binary { left, right, .. } => {
+                expression(left, names);
+                expression(right, names);
+            }
+            Expression::MethodCall { receiver, args, .. } => {
+                expression(receiver, names);
+                args.iter().for_each(|arg| expression(arg, names))
+            }
+            Expression::Call { args, .. }
+            | Expression::Array(args)
+            | Expression::Variant { args, .. } => {
+                args.iter().for_each(|arg| expression(arg, names))
+            }
+            Expression::Index { array, index } => {
+                expression(array, names);
+                expression(index, names);
+            }
+            Expression::Struct { fields, .. } => fields
+                .iter()
+                .for_each(|(_, value)| expression(value, names)),
+            Expression::Literal(_) | Expression::Variable(_) => {}
+        }
+    }
+
+    let mut names = HashSet::new();
+    for stmt in ast {
+        match stmt {
+            Statement::Expression(expr, _)
+            | Statement::Assignment { value: expr, .. }
+            | Statement::Offer(Some(expr), _) => expression(expr, &mut names),
+            Statement::Store { target, value, .. } => {
+                expression(target, &mut names);
+                expression(value, &mut names);
+            }
+            Statement::Foreseen {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                expression(condition, &mut names);
+                names.extend(addressed(then_branch));
+                names.extend(addressed(else_branch.as_deref().unwrap_or_default()));
+            }
+            Statement::Until {
+                condition, body, ..
+            } => {
+                expression(condition, &mut names);
+                names.extend(addressed(body));
+            }
+            Statement::Match { value, arms, .. } => {
+                expression(value, &mut names);
+                arms.iter()
+                    .for_each(|arm| names.extend(addressed(&arm.body)));
+            }
+            _ => {}
+        }
+    }
+    names
+}
@@ -1,10 +1,18 @@
+mod checker;
 mod compiler;
 mod error;
+mod flow;
 mod symbol;
+mod types;
+mod warning;
 
+pub use checker::*;
 pub use compiler::*;
 pub use error::*;
+pub use flow::*;
 pub use symbol::*;
+pub use types::*;
+pub use warning::*;
 
 #[cfg(test)]
 mod test;
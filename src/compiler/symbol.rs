@@ -1,10 +1,34 @@
+use super::Type;
+
+#[derive(Clone)]
 pub enum FunctionStorage {
     External,
     Internal,
 }
 
+#[derive(Clone)]
 pub struct FunctionSymbol {
     pub args: Vec<String>,
     pub return_type: String,
     pub storage: FunctionStorage,
+    pub variadic: bool,
+}
+
+// NOTE : `$` can not appear in an identifier but is valid in FASM and JS, so
+// a user spellcard or type never share its name with a generated symbol
+pub fn mangle(name: &str, types: &[Type]) -> String {
+    let types = types.iter().map(symbol).collect::<Vec<_>>();
+    format!("{}${}", name, types.join("$"))
+}
+
+pub fn method_symbol(ty: &Type, method: &str) -> String {
+    format!("{}${}", symbol(ty), method)
+}
+
+fn symbol(ty: &Type) -> String {
+    match ty {
+        Type::Pointer(pointee) => format!("$ptr{}", symbol(pointee)),
+        Type::Array(element, length) => format!("$arr{}_{}", length, symbol(element)),
+        ty => ty.to_string(),
+    }
 }
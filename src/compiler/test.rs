@@ -1,11 +1,12 @@
 use crate::i32;
 use crate::{
+    commons::Loc,
     lexer::Lexer,
     op::{Arg, Op},
     parser::parser::Parser,
 };
 
-use super::Compiler;
+use super::{Compiler, CompilerError, Type};
 
 fn setup(body: &str) -> (Vec<Op>, Compiler) {
     let chars = body.chars().collect::<Vec<_>>();
@@ -19,6 +20,31 @@ fn setup(body: &str) -> (Vec<Op>, Compiler) {
     (ops, compiler)
 }
 
+fn setup_error(body: &str) -> CompilerError {
+    let chars = body.chars().collect::<Vec<_>>();
+    let ast = Parser::new(Lexer::new(&chars))
+        .parse()
+        .expect("Should parse correctly");
+    Compiler::new()
+        .compile(ast)
+        .expect_err("Should fail to compile")
+}
+
+fn assert_missmatch(err: CompilerError, statement: &str, expected: &str, found: &str, loc: Loc) {
+    match err {
+        CompilerError::TypeMissmatch {
+            statement: s,
+            expected: e,
+            found: f,
+            loc: l,
+        } => assert_eq!(
+            (s.as_str(), e.as_str(), f.as_str(), l),
+            (statement, expected, found, loc)
+        ),
+        other => panic!("Expected type missmatch but found {}", other),
+    }
+}
+
 #[test]
 pub fn compile_simple_source_code() {
     let body = "
@@ -52,7 +78,7 @@ spellcard main() i32 {
             arg: Arg::Literal(i32!(69)),
             offset: 0,
         },
-        Op::Ret(Some(Arg::Local(0))),
+        Op::Ret(Some(Arg::Literal(i32!(69)))),
     ];
 
     let (ops, _) = setup(body);
@@ -65,7 +91,7 @@ spellcard main() i32 {
 pub fn compile_source_code_with_until() {
     let body = "
 spellcard main() i32 {
-    eternal a = 69;
+    vow a = 69;
     until a > 0 {
         a = a - 1;
     }
@@ -82,6 +108,7 @@ spellcard main() i32 {
         Op::Label(".L0".to_string()),
         Op::BinOp {
             binop: crate::ast::BinOp::Greater,
+            ty: Type::I32,
             offset: 1,
             lhs: Arg::Local(0),
             rhs: Arg::Literal(i32!(0)),
@@ -92,6 +119,7 @@ spellcard main() i32 {
         },
         Op::BinOp {
             binop: crate::ast::BinOp::Sub,
+            ty: Type::I32,
             offset: 1,
             lhs: Arg::Local(0),
             rhs: Arg::Literal(i32!(1)),
@@ -117,7 +145,7 @@ spellcard main() i32 {
 pub fn compile_source_code_with_foreseen() {
     let body = "
 spellcard main() i32 {
-    eternal a = 69;
+    vow a = 69;
     foreseen a > 0 {
         a = a - 1;
     }
@@ -133,6 +161,7 @@ spellcard main() i32 {
         },
         Op::BinOp {
             binop: crate::ast::BinOp::Greater,
+            ty: Type::I32,
             offset: 1,
             lhs: Arg::Local(0),
             rhs: Arg::Literal(i32!(0)),
@@ -143,6 +172,7 @@ spellcard main() i32 {
         },
         Op::BinOp {
             binop: crate::ast::BinOp::Sub,
+            ty: Type::I32,
             offset: 1,
             lhs: Arg::Local(0),
             rhs: Arg::Literal(i32!(1)),
@@ -181,6 +211,7 @@ spellcard sum(a: i32, b: i32) i32 {
         },
         Op::BinOp {
             binop: crate::ast::BinOp::Add,
+            ty: Type::I32,
             offset: 2,
             lhs: Arg::Local(0),
             rhs: Arg::Local(1),
@@ -193,3 +224,908 @@ spellcard sum(a: i32, b: i32) i32 {
         assert_eq!(expect, ops.get(i).expect("Should have the same op length"));
     }
 }
+
+#[test]
+pub fn check_annotation_and_inference() {
+    let body = "
+spellcard greet(name: cstr, times: i32) i32 {
+    vow count: i32 = times * 2;
+    eternal greeting = name;
+    offer count;
+}
+
+spellcard main() i32 {
+    offer greet(\"Remilia\", 3);
+}";
+
+    let (_, compiler) = setup(body);
+    let greet = compiler
+        .checker
+        .function("greet")
+        .expect("Should be declared");
+    assert_eq!(
+        greet.params,
+        Some(vec![super::Type::CStr, super::Type::I32])
+    );
+    assert_eq!(greet.return_type, super::Type::I32);
+}
+
+#[test]
+pub fn check_assignment_type() {
+    let body = "
+spellcard main() i32 {
+    vow a = 1;
+    a = \"Remilia\";
+    offer a;
+}";
+
+    assert_missmatch(
+        setup_error(body),
+        "assignment of a",
+        "i32",
+        "cstr",
+        Loc::new(5, 4),
+    );
+}
+
+#[test]
+pub fn check_binary_operand_type() {
+    let body = "
+spellcard main() i32 {
+    eternal name: cstr = \"Remilia\";
+    offer name + 1;
+}";
+
+    assert_missmatch(
+        setup_error(body),
+        "left operand of +",
+        "number",
+        "cstr",
+        Loc::new(5, 4),
+    );
+}
+
+#[test]
+pub fn check_call_argument_type() {
+    let body = "
+spellcard twice(a: i32) i32 {
+    offer a * 2;
+}
+
+spellcard main() i32 {
+    offer twice(\"Remilia\");
+}";
+
+    assert_missmatch(
+        setup_error(body),
+        "argument 1 of twice",
+        "i32",
+        "cstr",
+        Loc::new(5, 7),
+    );
+}
+
+#[test]
+pub fn check_invite_signature() {
+    let body = "
+invite printf(fmt: cstr, ...) i32;
+invite InitWindow(w: i32, h: i32, title: cstr) void;
+
+spellcard main() i32 {
+    vow a = printf(\"%d\\n\", 69);
+    InitWindow(900, 506, \"Remilia\");
+    offer a;
+}";
+
+    let (_, compiler) = setup(body);
+    let printf = compiler.checker.function("printf").unwrap();
+    assert_eq!(printf.params, Some(vec![super::Type::CStr]));
+    assert_eq!(printf.return_type, super::Type::I32);
+    let init_window = compiler.checker.function("InitWindow").unwrap();
+    assert_eq!(init_window.return_type, super::Type::Void);
+    assert_eq!(
+        compiler.spellcard["InitWindow"].args,
+        vec!["w", "h", "title"]
+    );
+
+    let body = "
+invite InitWindow(w: i32, h: i32, title: cstr) void;
+
+spellcard main() i32 {
+    InitWindow(900, \"Remilia\", 506);
+    offer 0;
+}";
+
+    assert_missmatch(
+        setup_error(body),
+        "argument 2 of InitWindow",
+        "i32",
+        "cstr",
+        Loc::new(5, 5),
+    );
+
+    let body = "
+invite InitWindow(w: i32, h: i32, title: cstr) void;
+
+spellcard main() i32 {
+    vow a = InitWindow(900, 506, \"Remilia\");
+    offer 0;
+}";
+
+    assert_missmatch(
+        setup_error(body),
+        "assignment of a",
+        "value",
+        "void",
+        Loc::new(5, 5),
+    );
+}
+
+#[test]
+pub fn check_call_arity() {
+    let body = "
+spellcard add_number(a: i32, b: i32) i32 {
+    offer a + b;
+}
+
+spellcard main() i32 {
+    offer add_number(1);
+}";
+
+    match setup_error(body) {
+        CompilerError::ArityMismatch {
+            name,
+            expected,
+            found,
+            variadic,
+            loc,
+        } => assert_eq!(
+            (name.as_str(), expected, found, variadic, loc),
+            ("add_number", 2, 1, false, Loc::new(5, 7))
+        ),
+        other => panic!("Expected arity mismatch but found {}", other),
+    }
+
+    let body = "
+invite printf(fmt: cstr, ...) i32;
+invite puts;
+
+spellcard main() i32 {
+    printf(\"%d %d\\n\", 6, 9);
+    puts(\"Remilia\", 495);
+    printf();
+    offer 0;
+}";
+
+    match setup_error(body) {
+        CompilerError::ArityMismatch {
+            name,
+            expected,
+            found,
+            variadic,
+            ..
+        } => assert_eq!(
+            (name.as_str(), expected, found, variadic),
+            ("printf", 1, 0, true)
+        ),
+        other => panic!("Expected arity mismatch but found {}", other),
+    }
+}
+
+#[test]
+pub fn compile_forward_reference() {
+    let body = "
+spellcard main() i32 {
+    offer twice(square(3));
+}
+
+spellcard twice(a: i32) i32 {
+    offer a * 2;
+}
+
+invite square(a: i32) i32;";
+
+    let (ops, compiler) = setup(body);
+    assert_eq!(
+        ops[0..2],
+        [Op::Function("main".to_owned()), Op::StackAlloc(2),]
+    );
+    assert_eq!(compiler.spellcard["twice"].args, vec!["a"]);
+    assert_eq!(
+        compiler.checker.function("square").unwrap().return_type,
+        super::Type::I32
+    );
+
+    let body = "
+spellcard main() i32 {
+    offer twice(\"Remilia\");
+}
+
+spellcard twice(a: i32) i32 {
+    offer a * 2;
+}";
+
+    assert_missmatch(
+        setup_error(body),
+        "argument 1 of twice",
+        "i32",
+        "cstr",
+        Loc::new(5, 3),
+    );
+}
+
+#[test]
+pub fn check_missing_offer() {
+    let body = "
+spellcard sign(a: i32) i32 {
+    foreseen a < 0 {
+        offer 0 - 1;
+    }
+}";
+
+    match setup_error(body) {
+        CompilerError::MissingOffer {
+            name,
+            return_type,
+            loc,
+        } => assert_eq!(
+            (name.as_str(), return_type.as_str(), loc),
+            ("sign", "i32", Loc::new(1, 2))
+        ),
+        other => panic!("Expected missing offer but found {}", other),
+    }
+
+    let body = "
+spellcard sign(a: i32) i32 {
+    foreseen a < 0 {
+        offer 0 - 1;
+    } otherwise {
+        offer 1;
+    }
+}
+
+spellcard greet() void {
+    vow a = sign(1);
+}";
+
+    let (ops, compiler) = setup(body);
+    assert_eq!(ops.last(), Some(&Op::Ret(None)));
+    assert!(compiler.warnings.is_empty());
+}
+
+#[test]
+pub fn warn_unreachable_statement() {
+    let body = "
+spellcard main() i32 {
+    vow a = 1;
+    foreseen a > 0 {
+        offer 1;
+        a = 2;
+    }
+    offer a;
+    a = 3;
+}";
+
+    let (_, compiler) = setup(body);
+    assert_eq!(
+        compiler.warnings,
+        vec![
+            super::CompilerWarning::UnreachableCode {
+                loc: Loc::new(5, 9)
+            },
+            super::CompilerWarning::UnreachableCode {
+                loc: Loc::new(9, 6)
+            },
+        ]
+    );
+}
+
+#[test]
+pub fn check_bool_type() {
+    let body = "
+spellcard main() i32 {
+    vow a = 1;
+    until a {
+        a = a - 1;
+    }
+    offer a;
+}";
+
+    assert_missmatch(
+        setup_error(body),
+        "until condition",
+        "bool",
+        "i32",
+        Loc::new(5, 4),
+    );
+
+    let body = "
+spellcard main() i32 {
+    vow done = false;
+    foreseen done == 1 {
+        offer 1;
+    }
+    offer 0;
+}";
+
+    assert_missmatch(
+        setup_error(body),
+        "right operand of ==",
+        "bool",
+        "i32",
+        Loc::new(5, 4),
+    );
+
+    let body = "
+spellcard main() i32 {
+    eternal done = true;
+    foreseen !done {
+        offer 1;
+    }
+    offer 0;
+}";
+
+    let (ops, _) = setup(body);
+    assert_eq!(
+        ops[3],
+        Op::UnaryNot {
+            offset: 1,
+            arg: Arg::Literal(crate::bool!(true)),
+        }
+    );
+}
+
+#[test]
+pub fn check_offer_type() {
+    let body = "
+spellcard name() cstr {
+    offer 495;
+}";
+
+    assert_missmatch(
+        setup_error(body),
+        "offer of name",
+        "cstr",
+        "i32",
+        Loc::new(5, 3),
+    );
+
+    let body = "
+spellcard nothing() void {
+    offer 495;
+}";
+
+    assert_missmatch(
+        setup_error(body),
+        "offer of nothing",
+        "void",
+        "i32",
+        Loc::new(5, 3),
+    );
+}
+
+#[test]
+pub fn check_unknown_type() {
+    let body = "
+spellcard main() f128 {
+    offer 0;
+}";
+
+    assert!(matches!(
+        setup_error(body),
+        CompilerError::UnknownType { found, loc } if found == "f128" && loc == Loc::new(1, 2)
+    ));
+}
+
+#[test]
+pub fn reject_eternal_reassign() {
+    let body = "
+spellcard main() i32 {
+    eternal a = 69;
+    a = 70;
+    offer a;
+}";
+
+    assert!(matches!(
+        setup_error(body),
+        CompilerError::EternalReassign { name, declared, loc }
+            if name == "a" && declared == Loc::new(5, 3) && loc == Loc::new(5, 4)
+    ));
+
+    let body = "
+spellcard main() i32 {
+    eternal a: i32;
+    until true {
+        a = 70;
+    }
+    offer a;
+}";
+
+    assert!(matches!(
+        setup_error(body),
+        CompilerError::EternalReassign { declared, loc, .. }
+            if declared == Loc::new(5, 3) && loc == Loc::new(9, 5)
+    ));
+}
+
+#[test]
+pub fn compile_eternal_initialized_later() {
+    let body = "
+spellcard main() i32 {
+    eternal a: i32;
+    vow b = 1;
+    a = b + 68;
+    offer a;
+}";
+
+    let (ops, compiler) = setup(body);
+    assert_eq!(ops.last(), Some(&Op::Ret(Some(Arg::Local(0)))));
+    let scope = &compiler.spellcard_scope["main"];
+    assert!(!scope.is_mutable("a"));
+    assert!(scope.is_mutable("b"));
+}
+
+#[test]
+pub fn check_integer_literal() {
+    let body = "
+spellcard main() i32 {
+    vow a: u8 = 300;
+    offer 0;
+}";
+
+    match setup_error(body) {
+        CompilerError::LiteralOutOfRange { value, ty, loc } => {
+            assert_eq!((value, ty.as_str(), loc), (300, "u8", Loc::new(5, 3)))
+        }
+        other => panic!("Expected literal out of range but found {}", other),
+    }
+
+    let body = "
+spellcard main() i32 {
+    vow a: i64 = 5000000000;
+    offer 0;
+}";
+
+    let (ops, _) = setup(body);
+    assert!(ops.contains(&Op::EternalAssign {
+        offset: 0,
+        arg: Arg::Literal(crate::value::Value::I64(5000000000)),
+    }));
+}
+
+#[test]
+pub fn check_float_type() {
+    let body = "
+spellcard main() i32 {
+    vow a: f32 = 1;
+    offer 0;
+}";
+
+    assert_missmatch(
+        setup_error(body),
+        "assignment of a",
+        "f32",
+        "i32",
+        Loc::new(5, 3),
+    );
+
+    let body = "
+spellcard main() i32 {
+    vow a: f32 = 4.95;
+    offer 0;
+}";
+
+    let (ops, _) = setup(body);
+    assert!(ops.contains(&Op::EternalAssign {
+        offset: 0,
+        arg: Arg::Literal(crate::value::Value::F32(4.95)),
+    }));
+}
+
+#[test]
+pub fn check_pointer_type() {
+    let body = "
+spellcard main() i32 {
+    offer &1;
+}";
+
+    assert!(matches!(
+        setup_error(body),
+        CompilerError::NotAddressable {
+            loc: Loc { column: 5, row: 3 }
+        }
+    ));
+
+    let body = "
+spellcard main() i32 {
+    vow a = 1;
+    offer *a;
+}";
+
+    assert_missmatch(
+        setup_error(body),
+        "operand of *",
+        "pointer",
+        "i32",
+        Loc::new(5, 4),
+    );
+
+    let body = "
+spellcard main() i32 {
+    vow a: i64 = 1;
+    vow p = &a;
+    vow q: *i32 = p;
+    offer 0;
+}";
+
+    assert_missmatch(
+        setup_error(body),
+        "assignment of q",
+        "*i32",
+        "*i64",
+        Loc::new(5, 5),
+    );
+}
+
+#[test]
+pub fn check_array_type() {
+    let body = "
+spellcard main() i32 {
+    vow a: [i32; 3] = [1, 2];
+    offer 0;
+}";
+
+    assert_missmatch(
+        setup_error(body),
+        "array literal",
+        "[i32; 3]",
+        "[i32; 2]",
+        Loc::new(5, 3),
+    );
+
+    let body = "
+spellcard main() i32 {
+    vow a = [1, 2, 3];
+    offer a[3];
+}";
+
+    assert!(matches!(
+        setup_error(body),
+        CompilerError::IndexOutOfBounds {
+            index: 3,
+            length: 3,
+            ..
+        }
+    ));
+
+    let body = "
+spellcard main() i32 {
+    vow a = 1;
+    offer a[0];
+}";
+
+    assert_missmatch(
+        setup_error(body),
+        "operand of []",
+        "array",
+        "i32",
+        Loc::new(5, 4),
+    );
+
+    let body = "
+spellcard main() i32 {
+    vow a: [u8; 10] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+    vow b = a[9];
+    offer 0;
+}";
+
+    let (ops, _) = setup(body);
+    assert!(ops.contains(&Op::AddrOf {
+        offset: 2,
+        local: 1
+    }));
+    assert!(ops.contains(&Op::Element {
+        offset: 3,
+        base: Arg::Local(2),
+        index: Arg::Literal(crate::value::Value::I64(9)),
+        size: 1,
+    }));
+}
+
+#[test]
+pub fn check_struct_type() {
+    let layout = |body: &str, name: &str| {
+        let (_, compiler) = setup(body);
+        compiler
+            .checker
+            .resolve_type(name)
+            .expect("Struct should be declared")
+    };
+    let body = "
+struct Inner { a: u8, b: i64 }
+struct Outer { flag: bool, inner: Inner, tail: [u16; 3] }";
+    let inner = layout(body, "Inner");
+    assert_eq!((inner.size(), inner.align()), (16, 8));
+    assert_eq!(inner.field("b"), Some((8, Type::I64)));
+    let outer = layout(body, "Outer");
+    assert_eq!((outer.size(), outer.slots()), (32, 4));
+    assert_eq!(outer.field("tail").map(|(offset, _)| offset), Some(24));
+
+    let body = "
+struct Point { x: i32, y: i32 }
+spellcard main() i32 {
+    vow p = Point { x: 1, z: 2 };
+    offer 0;
+}";
+    assert!(matches!(
+        setup_error(body),
+        CompilerError::UnknownField { field, .. } if field == "z"
+    ));
+
+    let body = "
+struct Point { x: i32, y: i32 }
+spellcard main() i32 {
+    vow p = Point { x: 1 };
+    offer 0;
+}";
+    assert!(matches!(
+        setup_error(body),
+        CompilerError::MissingField { field, .. } if field == "y"
+    ));
+
+    let body = "
+struct Point { x: i32, y: i32 }
+spellcard main() i32 {
+    vow p = Point { x: 1, y: 2 };
+    offer p.x.y;
+}";
+    assert_missmatch(
+        setup_error(body),
+        "operand of .y",
+        "struct",
+        "i32",
+        Loc::new(5, 5),
+    );
+
+    let body = "
+struct Point { x: i32, y: i32 }
+spellcard origin() Point {
+    offer Point { x: 0, y: 0 };
+}";
+    assert_missmatch(
+        setup_error(body),
+        "offer of origin",
+        "scalar",
+        "Point",
+        Loc::new(1, 3),
+    );
+}
+
+#[test]
+pub fn check_match() {
+    let body = "
+enum Shape { Circle(f32), Rect(u8, i64), Empty }";
+    let (_, compiler) = setup(body);
+    let shape = compiler.checker.resolve_type("Shape").unwrap();
+    assert_eq!((shape.size(), shape.align()), (24, 8));
+    assert_eq!(
+        shape.variant("Rect"),
+        Some((1, vec![(8, Type::U8), (16, Type::I64)]))
+    );
+
+    let body = "
+enum Shape { Circle(f32), Rect(u8, i64), Empty }
+spellcard main() i32 {
+    vow shape = Shape::Empty;
+    match shape {
+        Shape::Rect(w, h) => {}
+    }
+    offer 0;
+}";
+    assert!(matches!(
+        setup_error(body),
+        CompilerError::NonExhaustiveMatch { missing, .. }
+            if missing == ["Shape::Circle", "Shape::Empty"]
+    ));
+
+    let body = "
+spellcard main() i32 {
+    vow value: u8 = 3;
+    match value {
+        0 => {}
+        10..20 => {}
+        20..=200 => {}
+    }
+    offer 0;
+}";
+    assert!(matches!(
+        setup_error(body),
+        CompilerError::NonExhaustiveMatch { missing, .. }
+            if missing == ["1..=9", "201..=255"]
+    ));
+
+    let body = "
+enum Shape { Circle(f32), Rect(u8, i64), Empty }
+spellcard main() i32 {
+    vow shape = Shape::Rect(1);
+    offer 0;
+}";
+    assert!(matches!(
+        setup_error(body),
+        CompilerError::ArityMismatch { name, expected: 2, found: 1, .. } if name == "Shape::Rect"
+    ));
+
+    let body = "
+enum Shape { Circle(f32), Rect(u8, i64), Empty }
+spellcard main() i32 {
+    vow shape = Shape::Square;
+    offer 0;
+}";
+    assert!(matches!(
+        setup_error(body),
+        CompilerError::UnknownVariant { variant, .. } if variant == "Square"
+    ));
+}
+
+#[test]
+pub fn check_generic_spellcard() {
+    let body = "
+spellcard max<T>(a: T, b: T) T {
+    foreseen a > b {
+        offer a;
+    }
+    offer b;
+}
+spellcard main() i32 {
+    vow a: i64 = 40;
+    vow b = max(a, 2);
+    offer max(1, 2);
+}";
+    let (ops, compiler) = setup(body);
+    let functions = ops
+        .iter()
+        .filter_map(|op| match op {
+            Op::Function(name) => Some(name.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(functions, ["main", "max$i32", "max$i64"]);
+    assert_eq!(
+        compiler.checker.function("max$i64").map(|f| &f.return_type),
+        Some(&Type::I64)
+    );
+
+    let body = "
+spellcard zero<T>() T {
+    offer 0;
+}
+spellcard main() i32 {
+    offer zero();
+}";
+    assert!(matches!(
+        setup_error(body),
+        CompilerError::CannotInfer { generic, name, .. } if generic == "T" && name == "zero"
+    ));
+
+    let body = "
+spellcard max<T>(a: T, b: T) T {
+    offer a;
+}
+spellcard main() i32 {
+    offer max(1, true);
+}";
+    assert!(matches!(
+        setup_error(body),
+        CompilerError::TypeMissmatch { statement, .. } if statement == "argument 2 of max"
+    ));
+
+    let body = "
+spellcard first<T>(p: *T) T {
+    offer *p;
+}
+spellcard main() i32 {
+    offer first(1);
+}";
+    assert!(matches!(
+        setup_error(body),
+        CompilerError::TypeMissmatch { expected, .. } if expected == "*T"
+    ));
+}
+
+#[test]
+pub fn check_impl_trait() {
+    let body = "
+struct Square { side: i32 }
+impl Square {
+    spellcard area(self: *Self) i32 {
+        offer self.side * self.side;
+    }
+}
+spellcard main() i32 {
+    vow s = Square { side: 2 };
+    offer s.area();
+}";
+    let (ops, compiler) = setup(body);
+    assert!(ops.contains(&Op::Function("Square$area".to_owned())));
+    assert_eq!(
+        compiler.checker.function("Square$area").map(|f| &f.params),
+        Some(&Some(vec![Type::Pointer(Box::new(
+            compiler.checker.resolve_type("Square").unwrap()
+        ))]))
+    );
+
+    let body = "
+struct Square { side: i32 }
+trait Shape {
+    spellcard area(self: *Self) i32;
+    spellcard sides(self: *Self) i32;
+}
+impl Square: Shape {
+    spellcard area(self: *Self) i32 {
+        offer self.side * self.side;
+    }
+}";
+    assert!(matches!(
+        setup_error(body),
+        CompilerError::MissingMethod { method, .. } if method == "sides"
+    ));
+
+    let body = "
+struct Square { side: i32 }
+trait Shape {
+    spellcard area(self: *Self) i32;
+}
+impl Square: Shape {
+    spellcard area(self: Self) i32 {
+        offer self.side * self.side;
+    }
+}";
+    assert!(matches!(
+        setup_error(body),
+        CompilerError::TypeMissmatch { statement, .. } if statement == "parameter 1 of Shape::area"
+    ));
+
+    let body = "
+struct Square { side: i32 }
+trait Shape {
+    spellcard area(self: *Self) i32;
+}
+spellcard twice<T: Shape>(a: *T) i32 {
+    offer a.area() * 2;
+}
+spellcard main() i32 {
+    vow s = Square { side: 2 };
+    offer twice(&s);
+}";
+    assert!(matches!(
+        setup_error(body),
+        CompilerError::UnsatisfiedBound { ty, trait_name, .. } if ty == "Square" && trait_name == "Shape"
+    ));
+
+    let body = "
+struct Square { side: i32 }
+spellcard main() i32 {
+    vow s = Square { side: 2 };
+    offer s.area();
+}";
+    assert!(matches!(
+        setup_error(body),
+        CompilerError::UnknownMethod { method, .. } if method == "area"
+    ));
+}
+
+#[test]
+pub fn check_logical_operator() {
+    let body = "
+spellcard main() i32 {
+    vow a = 1;
+    foreseen a > 0 && a {
+        offer 1;
+    }
+    offer 0;
+}";
+    assert!(matches!(
+        setup_error(body),
+        CompilerError::TypeMissmatch { statement, found, .. } if statement == "operand of &&" && found == "i32"
+    ));
+}
@@ -0,0 +1,328 @@
+use crate::value::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Void,
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+    Bool,
+    CStr,
+    Pointer(Box<Type>),
+    Array(Box<Type>, usize),
+    Struct {
+        name: String,
+        fields: Vec<(String, Type)>,
+    },
+    // NOTE : The tag is an u32, the payload of every variant start at the same offset after it
+    Enum {
+        name: String,
+        variants: Vec<(String, Vec<Type>)>,
+    },
+}
+
+impl Type {
+    pub fn resolve(name: &str) -> Option<Self> {
+        Type::resolve_with(name, &|_| None)
+    }
+
+    pub fn resolve_with(name: &str, declared: &dyn Fn(&str) -> Option<Type>) -> Option<Self> {
+        if let Some(pointee) = name.strip_prefix('*') {
+            let pointee = Type::resolve_with(pointee, declared)?;
+            return Some(Type::Pointer(Box::new(pointee)));
+        }
+        if let Some(array) = name.strip_prefix('[').and_then(|a| a.strip_suffix(']')) {
+            let (element, length) = array.rsplit_once(';')?;
+            let length = length.trim().parse().ok()?;
+            let element = Type::resolve_with(element.trim(), declared)?;
+            return Some(Type::Array(Box::new(element), length));
+        }
+        Some(match name {
+            "void" => Type::Void,
+            "i8" => Type::I8,
+            "i16" => Type::I16,
+            "i32" => Type::I32,
+            "i64" => Type::I64,
+            "u8" => Type::U8,
+            "u16" => Type::U16,
+            "u32" => Type::U32,
+            "u64" => Type::U64,
+            "f32" => Type::F32,
+            "f64" => Type::F64,
+            "bool" => Type::Bool,
+            "cstr" => Type::CStr,
+            name => return declared(name),
+        })
+    }
+
+    pub fn of(value: &Value) -> Self {
+        match value {
+            Value::I8(_) => Type::I8,
+            Value::I16(_) => Type::I16,
+            Value::I32(_) => Type::I32,
+            Value::I64(_) => Type::I64,
+            Value::U8(_) => Type::U8,
+            Value::U16(_) => Type::U16,
+            Value::U32(_) => Type::U32,
+            Value::U64(_) => Type::U64,
+            Value::F32(_) => Type::F32,
+            Value::F64(_) => Type::F64,
+            Value::Bool(_) => Type::Bool,
+            Value::Pointer(_) => Type::Pointer(Box::new(Type::Void)),
+            Value::String(_) => Type::CStr,
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.bits().is_some()
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, Type::F32 | Type::F64)
+    }
+
+    pub fn is_signed(&self) -> bool {
+        matches!(self, Type::I8 | Type::I16 | Type::I32 | Type::I64)
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            Type::Void | Type::I8 | Type::U8 | Type::Bool => 1,
+            Type::I16 | Type::U16 => 2,
+            Type::I32 | Type::U32 | Type::F32 => 4,
+            Type::I64 | Type::U64 | Type::F64 | Type::CStr | Type::Pointer(_) => 8,
+            Type::Array(element, length) => element.size() * length,
+            Type::Struct { fields, .. } => {
+                let end = fields.iter().fold(0usize, |offset, (_, ty)| {
+                    offset.next_multiple_of(ty.align()) + ty.size()
+                });
+                end.next_multiple_of(self.align())
+            }
+            Type::Enum { variants, .. } => {
+                let payload = variants
+                    .iter()
+                    .map(|(_, fields)| Type::payload(fields).size())
+                    .max()
+                    .unwrap_or(0);
+                (self.payload_offset() + payload).next_multiple_of(self.align())
+            }
+        }
+    }
+
+    pub fn align(&self) -> usize {
+        match self {
+            Type::Array(element, _) => element.align(),
+            Type::Struct { fields, .. } => {
+                fields.iter().map(|(_, ty)| ty.align()).max().unwrap_or(1)
+            }
+            Type::Enum { variants, .. } => variants
+                .iter()
+                .map(|(_, fields)| Type::payload(fields).align())
+                .fold(Type::U32.align(), usize::max),
+            ty => ty.size(),
+        }
+    }
+
+    pub fn is_aggregate(&self) -> bool {
+        matches!(
+            self,
+            Type::Array(..) | Type::Struct { .. } | Type::Enum { .. }
+        )
+    }
+
+    fn payload(fields: &[Type]) -> Type {
+        Type::Struct {
+            name: String::new(),
+            fields: fields
+                .iter()
+                .enumerate()
+                .map(|(i, ty)| (i.to_string(), ty.clone()))
+                .collect(),
+        }
+    }
+
+    fn payload_offset(&self) -> usize {
+        Type::U32.size().next_multiple_of(self.align())
+    }
+
+    pub fn variant(&self, name: &str) -> Option<(u32, Vec<(usize, Type)>)> {
+        let Type::Enum { variants, .. } = self else {
+            return None;
+        };
+        let tag = variants.iter().position(|(variant, _)| variant == name)?;
+        let payload = Type::payload(&variants[tag].1);
+        let fields = variants[tag]
+            .1
+            .iter()
+            .enumerate()
+            .map(|(i, _)| {
+                let (offset, ty) = payload.field(&i.to_string()).unwrap();
+                (self.payload_offset() + offset, ty)
+            })
+            .collect();
+        Some((tag as u32, fields))
+    }
+
+    pub fn slots(&self) -> usize {
+        match self.is_aggregate() {
+            true => self.size().div_ceil(8).max(1),
+            false => 1,
+        }
+    }
+
+    pub fn field(&self, name: &str) -> Option<(usize, Type)> {
+        let Type::Struct { fields, .. } = self else {
+            return None;
+        };
+        let mut offset = 0usize;
+        for (field, ty) in fields {
+            offset = offset.next_multiple_of(ty.align());
+            if field == name {
+                return Some((offset, ty.clone()));
+            }
+            offset += ty.size();
+        }
+        None
+    }
+
+    pub fn scalars(&self) -> Vec<(usize, Type)> {
+        match self {
+            Type::Array(element, length) => (0..*length)
+                .flat_map(|i| {
+                    element
+                        .scalars()
+                        .into_iter()
+                        .map(move |(offset, ty)| (i * element.size() + offset, ty))
+                })
+                .collect(),
+            Type::Struct { fields, .. } => fields
+                .iter()
+                .flat_map(|(name, _)| {
+                    let (start, ty) = self.field(name).unwrap();
+                    ty.scalars()
+                        .into_iter()
+                        .map(move |(offset, ty)| (start + offset, ty))
+                })
+                .collect(),
+            // NOTE : Payload of every variant overlap, so C see each eightbyte as an integer
+            Type::Enum { .. } => (0..self.size().div_ceil(8))
+                .map(|i| (i * 8, Type::U64))
+                .collect(),
+            ty => vec![(0, ty.clone())],
+        }
+    }
+
+    pub fn bits(&self) -> Option<u32> {
+        match self {
+            Type::I8 | Type::U8 => Some(8),
+            Type::I16 | Type::U16 => Some(16),
+            Type::I32 | Type::U32 => Some(32),
+            Type::I64 | Type::U64 => Some(64),
+            _ => None,
+        }
+    }
+
+    pub fn bounds(&self) -> Option<(i128, i128)> {
+        let bits = self.bits()?;
+        Some(match self.is_signed() {
+            true => (-(1 << (bits - 1)), (1 << (bits - 1)) - 1),
+            false => (0, (1 << bits) - 1),
+        })
+    }
+
+    pub fn literal(&self, int: i128) -> Option<Value> {
+        Some(match self {
+            Type::I8 => Value::I8(int.try_into().ok()?),
+            Type::I16 => Value::I16(int.try_into().ok()?),
+            Type::I32 => Value::I32(int.try_into().ok()?),
+            Type::I64 => Value::I64(int.try_into().ok()?),
+            Type::U8 => Value::U8(int.try_into().ok()?),
+            Type::U16 => Value::U16(int.try_into().ok()?),
+            Type::U32 => Value::U32(int.try_into().ok()?),
+            Type::U64 => Value::U64(int.try_into().ok()?),
+            _ => return None,
+        })
+    }
+
+    pub fn float(&self, float: f64) -> Option<Value> {
+        match self {
+            Type::F32 => Some(Value::F32(float as f32)),
+            Type::F64 => Some(Value::F64(float)),
+            _ => None,
+        }
+    }
+
+    pub fn to_float(&self, raw: u64) -> f64 {
+        match self {
+            Type::F32 => f32::from_bits(raw as u32) as f64,
+            _ => f64::from_bits(raw),
+        }
+    }
+
+    pub fn from_float(&self, float: f64) -> u64 {
+        match self {
+            Type::F32 => (float as f32).to_bits() as u64,
+            _ => float.to_bits(),
+        }
+    }
+
+    pub fn normalize(&self, raw: u64) -> u64 {
+        match self {
+            Type::I8 => raw as i8 as i64 as u64,
+            Type::I16 => raw as i16 as i64 as u64,
+            Type::I32 => raw as i32 as i64 as u64,
+            Type::U8 => raw as u8 as u64,
+            Type::U16 => raw as u16 as u64,
+            Type::U32 | Type::F32 => raw as u32 as u64,
+            Type::Bool => (raw != 0) as u64,
+            _ => raw,
+        }
+    }
+
+    pub fn value(&self, raw: u64) -> Option<Value> {
+        match self {
+            Type::Bool => Some(Value::Bool(raw != 0)),
+            Type::F32 => Some(Value::F32(f32::from_bits(raw as u32))),
+            Type::F64 => Some(Value::F64(f64::from_bits(raw))),
+            Type::Pointer(_) => Some(Value::Pointer(raw)),
+            ty if ty.is_integer() => {
+                let int = match ty.is_signed() {
+                    true => ty.normalize(raw) as i64 as i128,
+                    false => ty.normalize(raw) as i128,
+                };
+                ty.literal(int)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Void => f.write_str("void"),
+            Type::I8 => f.write_str("i8"),
+            Type::I16 => f.write_str("i16"),
+            Type::I32 => f.write_str("i32"),
+            Type::I64 => f.write_str("i64"),
+            Type::U8 => f.write_str("u8"),
+            Type::U16 => f.write_str("u16"),
+            Type::U32 => f.write_str("u32"),
+            Type::U64 => f.write_str("u64"),
+            Type::F32 => f.write_str("f32"),
+            Type::F64 => f.write_str("f64"),
+            Type::Bool => f.write_str("bool"),
+            Type::CStr => f.write_str("cstr"),
+            Type::Pointer(pointee) => f.write_fmt(format_args!("*{}", pointee)),
+            Type::Array(element, length) => f.write_fmt(format_args!("[{}; {}]", element, length)),
+            Type::Struct { name, .. } | Type::Enum { name, .. } => f.write_str(name),
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use crate::commons::Loc;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompilerWarning {
+    UnreachableCode { loc: Loc },
+}
+
+impl std::fmt::Display for CompilerWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompilerWarning::UnreachableCode { loc } => f.write_fmt(format_args!(
+                "Unreachable statement at {}, it come after an offer",
+                loc
+            )),
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use std::{collections::HashMap, error::Error};
+
+use crate::{
+    compiler::{Compiler, FunctionStorage, Signature, Type},
+    lexer::Lexer,
+    parser::parser::Parser,
+    value::Value,
+    vm::{Limits, Memory, Program, VM, VMError},
+};
+
+use super::EngineError;
+
+#[derive(Debug, Clone)]
+pub struct Script {
+    program: Program,
+    signatures: HashMap<String, Signature>,
+    params: HashMap<String, Vec<Type>>,
+}
+
+impl Script {
+    pub fn compile(src: &str) -> Result<Self, EngineError> {
+        let chars = src.chars().collect::<Vec<_>>();
+        let ast = Parser::new(Lexer::new(&chars)).parse()?;
+        let mut compiler = Compiler::new();
+        let ops = compiler.compile(ast)?;
+        let program = Program::new(&compiler, ops)?;
+        let signatures = compiler
+            .spellcard
+            .iter()
+            .filter(|(_, f)| matches!(f.storage, FunctionStorage::Internal))
+            .filter_map(|(name, _)| {
+                let signature = compiler.checker.function(name)?;
+                Some((name.clone(), signature.clone()))
+            })
+            .collect();
+        let params = compiler
+            .spellcard
+            .iter()
+            .filter(|(_, f)| matches!(f.storage, FunctionStorage::External))
+            .filter_map(|(name, _)| {
+                let params = compiler.checker.function(name)?.params.clone()?;
+                Some((name.clone(), params))
+            })
+            .collect();
+
+        Ok(Self {
+            program,
+            signatures,
+            params,
+        })
+    }
+
+    // NOTE : Bytecode does not carry the signature, so only `Engine::vm` can call into it
+    pub fn from_bytecode(bytes: &[u8]) -> Result<Self, EngineError> {
+        Ok(Self {
+            program: Program::decode(bytes)?,
+            signatures: HashMap::new(),
+            params: HashMap::new(),
+        })
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+}
+
+pub struct Engine {
+    vm: VM,
+    signatures: HashMap<String, Signature>,
+    params: HashMap<String, Vec<Type>>,
+    initialized: bool,
+}
+
+impl Engine {
+    pub fn new(script: Script) -> Self {
+        Self::with_limits(script, Limits::default()).expect("Unlimited VM should not fail")
+    }
+
+    pub fn with_limits(script: Script, limits: Limits) -> Result<Self, EngineError> {
+        Ok(Self {
+            vm: VM::with_limits(script.program, limits)?,
+            signatures: script.signatures,
+            params: script.params,
+            initialized: false,
+        })
+    }
+
+    pub fn vm(&mut self) -> &mut VM {
+        &mut self.vm
+    }
+
+    // NOTE : Argument of a bare `invite name;` and of the variadic part has no type to convert into
+    pub fn register<F>(&mut self, name: &str, function: F)
+    where
+        F: Fn(&[Value]) -> Result<Value, Box<dyn Error>> + 'static,
+    {
+        let owned = name.to_owned();
+        let params = self.params.get(name).cloned().unwrap_or_default();
+        self.vm.register(
+            name,
+            Box::new(move |memory: &mut Memory, args: &[u64]| {
+                let args = args
+                    .iter()
+                    .enumerate()
+                    .map(|(i, arg)| {
+                        let value = match params.get(i) {
+                            Some(ty) => from_raw(memory, ty, *arg)?,
+                            None => None,
+                        };
+                        value.ok_or(VMError::HostError {
+                            name: owned.clone(),
+                            message: format!("Argument {} has no declared type", i + 1),
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let value = function(&args).map_err(|err| VMError::HostError {
+                    name: owned.clone(),
+                    message: err.to_string(),
+                })?;
+                to_raw(memory, &value)
+            }),
+        );
+    }
+
+    fn init(&mut self) -> Result<(), EngineError> {
+        if !self.initialized {
+            self.vm.init()?;
+            self.initialized = true;
+        }
+        Ok(())
+    }
+
+    pub fn run(&mut self) -> Result<Option<Value>, EngineError> {
+        self.init()?;
+        match self.vm.program().function("main") {
+            Some(_) => self.call("main", &[]),
+            None => Ok(None),
+        }
+    }
+
+    pub fn call(&mut self, name: &str, args: &[Value]) -> Result<Option<Value>, EngineError> {
+        self.init()?;
+        let idx = self
+            .vm
+            .program()
+            .function(name)
+            .ok_or(VMError::UnknownFunction {
+                name: name.to_owned(),
+            })?;
+        let arity = self.vm.program().functions[idx].arity;
+        if arity != args.len() {
+            return Err(EngineError::ArityMismatch {
+                name: name.to_owned(),
+                expected: arity,
+                found: args.len(),
+            });
+        }
+
+        let signature =
+            self.signatures
+                .get(name)
+                .cloned()
+                .ok_or(EngineError::UnknownSignature {
+                    name: name.to_owned(),
+                })?;
+        let params = signature.params.unwrap_or_default();
+
+        // NOTE : String passed in or offered by a host closure only live until the call return
+        let mark = self.vm.memory().heap_mark();
+        let value = self.call_with(name, args, &params, &signature.return_type);
+        self.vm.memory_mut().release(mark);
+        value
+    }
+
+    fn call_with(
+        &mut self,
+        name: &str,
+        args: &[Value],
+        params: &[Type],
+        return_type: &Type,
+    ) -> Result<Option<Value>, EngineError> {
+        let mut raw = vec![];
+        for (i, (arg, param)) in args.iter().zip(params.iter()).enumerate() {
+            let found = Type::of(arg);
+            let matched = match (&found, param) {
+                (Type::Pointer(_), Type::Pointer(_)) => true,
+                (found, param) => found == param,
+            };
+            if !matched {
+                return Err(EngineError::TypeMismatch {
+                    name: name.to_owned(),
+                    index: i,
+                    expected: param.to_string(),
+                    found: found.to_string(),
+                });
+            }
+            raw.push(to_raw(self.vm.memory_mut(), arg)?);
+        }
+        let value = self.vm.call(name, &raw)?;
+        match return_type {
+            Type::Void => Ok(None),
+            ty => match from_raw(self.vm.memory(), ty, value)? {
+                Some(value) => Ok(Some(value)),
+                None => Err(EngineError::UnsupportedType {
+                    name: name.to_owned(),
+                    ty: ty.to_string(),
+                }),
+            },
+        }
+    }
+}
+
+fn from_raw(memory: &Memory, ty: &Type, raw: u64) -> Result<Option<Value>, VMError> {
+    match ty {
+        Type::CStr => Ok(Some(Value::String(
+            String::from_utf8_lossy(memory.read_cstr(raw)?).into_owned(),
+        ))),
+        ty => Ok(ty.value(raw)),
+    }
+}
+
+fn to_raw(memory: &mut Memory, value: &Value) -> Result<u64, VMError> {
+    match value {
+        Value::String(str) => memory.alloc_cstr(str),
+        value => Ok(value.as_raw().unwrap_or_default()),
+    }
+}
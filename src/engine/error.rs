@@ -0,0 +1,97 @@
+use std::error::Error;
+
+use crate::{
+    compiler::CompilerError,
+    parser::error::ParseError,
+    vm::{BytecodeError, VMError},
+};
+
+#[derive(Debug)]
+pub enum EngineError {
+    Parse(ParseError),
+    Compile(CompilerError),
+    Bytecode(BytecodeError),
+    Runtime(VMError),
+    ArityMismatch {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    TypeMismatch {
+        name: String,
+        index: usize,
+        expected: String,
+        found: String,
+    },
+    UnknownSignature {
+        name: String,
+    },
+    UnsupportedType {
+        name: String,
+        ty: String,
+    },
+}
+
+impl Error for EngineError {}
+
+impl std::fmt::Display for EngineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EngineError::Parse(err) => f.write_fmt(format_args!("{}", err)),
+            EngineError::Compile(err) => f.write_fmt(format_args!("{}", err)),
+            EngineError::Bytecode(err) => f.write_fmt(format_args!("{}", err)),
+            EngineError::Runtime(err) => f.write_fmt(format_args!("{}", err)),
+            EngineError::ArityMismatch {
+                name,
+                expected,
+                found,
+            } => f.write_fmt(format_args!(
+                "Spellcard {} expect {} argument, but found {}",
+                name, expected, found
+            )),
+            EngineError::TypeMismatch {
+                name,
+                index,
+                expected,
+                found,
+            } => f.write_fmt(format_args!(
+                "Argument {} of spellcard {} expect {}, but found {}",
+                index + 1,
+                name,
+                expected,
+                found
+            )),
+            EngineError::UnknownSignature { name } => {
+                f.write_fmt(format_args!("Spellcard {} has no known signature", name))
+            }
+            EngineError::UnsupportedType { name, ty } => f.write_fmt(format_args!(
+                "Spellcard {} offer {} which can not be converted into a value",
+                name, ty
+            )),
+        }
+    }
+}
+
+impl From<ParseError> for EngineError {
+    fn from(err: ParseError) -> Self {
+        EngineError::Parse(err)
+    }
+}
+
+impl From<CompilerError> for EngineError {
+    fn from(err: CompilerError) -> Self {
+        EngineError::Compile(err)
+    }
+}
+
+impl From<BytecodeError> for EngineError {
+    fn from(err: BytecodeError) -> Self {
+        EngineError::Bytecode(err)
+    }
+}
+
+impl From<VMError> for EngineError {
+    fn from(err: VMError) -> Self {
+        EngineError::Runtime(err)
+    }
+}
@@ -0,0 +1,8 @@
+mod embed;
+mod error;
+
+pub use embed::*;
+pub use error::*;
+
+#[cfg(test)]
+mod test;
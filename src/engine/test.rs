@@ -0,0 +1,258 @@
+use crate::{value::Value, vm::VMError};
+
+use super::{Engine, EngineError, Script};
+
+fn setup(body: &str) -> Engine {
+    Engine::new(Script::compile(body).expect("Should compile correctly"))
+}
+
+#[test]
+fn call_spellcard_with_value() {
+    let body = "
+spellcard add_number(a: i32, b: i32) i32 {
+    offer a + b;
+}";
+
+    let mut engine = setup(body);
+    assert_eq!(
+        engine
+            .call("add_number", &[Value::I32(35), Value::I32(34)])
+            .expect("Should call correctly"),
+        Some(Value::I32(69))
+    );
+}
+
+#[test]
+fn call_host_closure() {
+    let body = "
+invite square(x: i32) i32;
+
+spellcard main() i32 {
+    offer square(8) + 5;
+}";
+
+    let mut engine = setup(body);
+    engine.register("square", |args| match args {
+        [Value::I32(int)] => Ok(Value::I32(int * int)),
+        _ => Err("square expect a single i32".into()),
+    });
+    assert_eq!(
+        engine.run().expect("Should run correctly"),
+        Some(Value::I32(69))
+    );
+}
+
+#[test]
+fn call_host_closure_with_signature() {
+    let body = "
+invite scale(name: cstr, factor: f64, offset: u8) f64;
+
+spellcard main() f64 {
+    offer scale(\"Remilia\", 2.5, 200);
+}";
+
+    let mut engine = setup(body);
+    engine.register("scale", |args| match args {
+        [Value::String(name), Value::F64(factor), Value::U8(offset)] => {
+            Ok(Value::F64(name.len() as f64 * factor + *offset as f64))
+        }
+        _ => Err("scale expect a cstr, an f64 and an u8".into()),
+    });
+    assert_eq!(
+        engine.run().expect("Should run correctly"),
+        Some(Value::F64(217.5))
+    );
+}
+
+#[test]
+fn host_closure_error() {
+    let body = "
+invite fail;
+
+spellcard main() i32 {
+    fail();
+    offer 0;
+}";
+
+    let mut engine = setup(body);
+    engine.register("fail", |_| Err("Remilia is not amused".into()));
+    assert!(matches!(
+        engine.run(),
+        Err(EngineError::Runtime(VMError::HostError { name, message }))
+            if name == "fail" && message == "Remilia is not amused"
+    ));
+}
+
+#[cfg(unix)]
+#[test]
+fn call_with_string_argument() {
+    let body = "
+invite strlen;
+
+spellcard length(str: cstr) i32 {
+    offer strlen(str);
+}";
+
+    let mut engine = setup(body);
+    assert_eq!(
+        engine
+            .call("length", &[Value::String("Remilia Scarlet".to_owned())])
+            .expect("Should call correctly"),
+        Some(Value::I32(15))
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn call_many_times_with_string() {
+    let body = "
+invite greet(name: cstr) cstr;
+invite strlen;
+
+spellcard length(str: cstr) i32 {
+    offer strlen(greet(str));
+}";
+
+    let mut engine = setup(body);
+    engine.register("greet", |args| match args {
+        [Value::String(name)] => Ok(Value::String(format!("Hello {}", name))),
+        _ => Err("greet expect a cstr".into()),
+    });
+    let name = "x".repeat(100);
+    for _ in 0..5000 {
+        assert_eq!(
+            engine
+                .call("length", &[Value::String(name.clone())])
+                .expect("Should call correctly"),
+            Some(Value::I32(106))
+        );
+    }
+}
+
+#[test]
+fn reject_invalid_call() {
+    assert!(matches!(
+        Script::compile("spellcard main() i32 { offer 69 }"),
+        Err(EngineError::Parse(_))
+    ));
+
+    let mut engine = setup("spellcard main() i32 { offer 69; }");
+    assert!(matches!(
+        engine.call("main", &[Value::I32(1)]),
+        Err(EngineError::ArityMismatch {
+            expected: 0,
+            found: 1,
+            ..
+        })
+    ));
+    assert!(matches!(
+        engine.call("marisa", &[]),
+        Err(EngineError::Runtime(VMError::UnknownFunction { .. }))
+    ));
+}
+
+#[test]
+fn reject_mismatched_argument() {
+    let body = "
+spellcard twice(x: i32) i32 {
+    offer x * 2;
+}";
+
+    let mut engine = setup(body);
+    assert!(matches!(
+        engine.call("twice", &[Value::F64(1.5)]),
+        Err(EngineError::TypeMismatch { index: 0, ref expected, ref found, .. })
+            if expected == "i32" && found == "f64"
+    ));
+    assert!(matches!(
+        engine.call("twice", &[Value::I64(-3)]),
+        Err(EngineError::TypeMismatch { ref expected, ref found, .. })
+            if expected == "i32" && found == "i64"
+    ));
+    assert_eq!(
+        engine
+            .call("twice", &[Value::I32(-3)])
+            .expect("Should call correctly"),
+        Some(Value::I32(-6))
+    );
+
+    let mut engine = setup("spellcard main() i32 { offer 69; }");
+    engine.call("main", &[]).expect("Should call correctly");
+    let bytes = engine.vm().program().encode();
+    let mut engine = Engine::new(Script::from_bytecode(&bytes).expect("Should decode correctly"));
+    assert!(matches!(
+        engine.run(),
+        Err(EngineError::UnknownSignature { .. })
+    ));
+}
+
+#[test]
+fn reject_untyped_host_argument() {
+    let body = "
+invite square;
+
+spellcard main() i32 {
+    offer square(8);
+}";
+
+    let mut engine = setup(body);
+    engine.register("square", |_| Ok(Value::I32(0)));
+    assert!(matches!(
+        engine.run(),
+        Err(EngineError::Runtime(VMError::HostError { .. }))
+    ));
+}
+
+#[test]
+fn call_with_string_result() {
+    let body = "
+spellcard name() cstr {
+    offer \"Remilia Scarlet\";
+}
+
+spellcard nothing() void {
+    offer;
+}";
+
+    let mut engine = setup(body);
+    assert_eq!(
+        engine.call("name", &[]).expect("Should call correctly"),
+        Some(Value::String("Remilia Scarlet".to_owned()))
+    );
+    assert_eq!(
+        engine.call("nothing", &[]).expect("Should call correctly"),
+        None
+    );
+}
+
+#[test]
+fn call_with_bool() {
+    let body = "
+spellcard choose(flag: bool) bool {
+    offer !flag;
+}";
+
+    let mut engine = setup(body);
+    assert_eq!(
+        engine
+            .call("choose", &[Value::Bool(true)])
+            .expect("Should call correctly"),
+        Some(Value::Bool(false))
+    );
+}
+
+#[test]
+fn call_with_float() {
+    let body = "
+spellcard average(a: f64, b: f64) f64 {
+    offer (a + b) / 2.0;
+}";
+
+    let mut engine = setup(body);
+    assert_eq!(
+        engine
+            .call("average", &[Value::F64(1.5), Value::F64(2.0)])
+            .expect("Should call correctly"),
+        Some(Value::F64(1.75))
+    );
+}
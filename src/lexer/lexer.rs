@@ -2,22 +2,31 @@ use crate::commons::Loc;
 
 use super::token::{Token, TokenKind};
 
+#[derive(Clone)]
 pub struct Lexer<'a> {
     content: &'a [char],
     loc: Loc,
 }
 
-const KEYWORDS: [(&str, TokenKind); 10] = [
+const KEYWORDS: [(&str, TokenKind); 18] = [
     ("spellcard", TokenKind::SpellCard),
     ("offer", TokenKind::Offer),
     ("eternal", TokenKind::Eternal),
     ("vow", TokenKind::Vow),
-    ("and", TokenKind::Eternal),
-    ("or", TokenKind::Vow),
+    ("and", TokenKind::And),
+    ("or", TokenKind::Or),
     ("invite", TokenKind::Invite),
     ("foreseen", TokenKind::Foreseen),
     ("otherwise", TokenKind::Otherwise),
     ("until", TokenKind::Until),
+    ("true", TokenKind::True),
+    ("false", TokenKind::False),
+    ("null", TokenKind::Null),
+    ("struct", TokenKind::Struct),
+    ("enum", TokenKind::Enum),
+    ("match", TokenKind::Match),
+    ("impl", TokenKind::Impl),
+    ("trait", TokenKind::Trait),
 ];
 
 impl<'a> Lexer<'a> {
@@ -69,16 +78,64 @@ impl<'a> Lexer<'a> {
         (chop, loc)
     }
 
+    fn number(&mut self, negative: bool) -> Token {
+        let loc = self.loc;
+        if negative {
+            self.skip(1);
+        }
+        let (digits, _) = self.chop_while(|a| a.is_ascii_digit());
+        let mut chop = if negative {
+            format!("-{}", digits)
+        } else {
+            digits
+        };
+
+        if self.content.len() > 1 && self.content[0] == '.' && self.content[1].is_ascii_digit() {
+            self.skip(1);
+            let (fraction, _) = self.chop_while(|a| a.is_ascii_digit());
+            chop.push('.');
+            chop.push_str(&fraction);
+            return Token {
+                kind: TokenKind::FloatLiteral(chop.parse().unwrap()),
+                loc,
+            };
+        }
+
+        let kind = match chop.parse() {
+            Ok(int) => TokenKind::IntLiteral(int),
+            Err(_) => TokenKind::IntOverflow(chop),
+        };
+        Token { kind, loc }
+    }
+
     pub fn next_token(&mut self) -> Option<Token> {
         if self.content.is_empty() {
             return None;
         }
 
         return match self.content[0] {
-            '.' => Some(self.skip_n_return(1, TokenKind::Dot)),
+            '.' => {
+                if self.content.starts_with(&['.', '.', '.']) {
+                    return Some(self.skip_n_return(3, TokenKind::Ellipsis));
+                }
+                if self.content.starts_with(&['.', '.', '=']) {
+                    return Some(self.skip_n_return(3, TokenKind::DotDotEqual));
+                }
+                if self.content.starts_with(&['.', '.']) {
+                    return Some(self.skip_n_return(2, TokenKind::DotDot));
+                }
+
+                Some(self.skip_n_return(1, TokenKind::Dot))
+            }
             ',' => Some(self.skip_n_return(1, TokenKind::Comma)),
             ';' => Some(self.skip_n_return(1, TokenKind::SemiColon)),
-            ':' => Some(self.skip_n_return(1, TokenKind::Colon)),
+            ':' => {
+                if self.content.starts_with(&[':', ':']) {
+                    return Some(self.skip_n_return(2, TokenKind::ColonColon));
+                }
+
+                Some(self.skip_n_return(1, TokenKind::Colon))
+            }
             '{' => Some(self.skip_n_return(1, TokenKind::OCurly)),
             '}' => Some(self.skip_n_return(1, TokenKind::CCurly)),
             '(' => Some(self.skip_n_return(1, TokenKind::OParen)),
@@ -89,14 +146,7 @@ impl<'a> Lexer<'a> {
             '*' => Some(self.skip_n_return(1, TokenKind::Star)),
             '-' => {
                 if self.content[1].is_numeric() {
-                    self.content = &self.content[1..];
-                    let (mut chop, loc) = self.chop_while(|a| a.is_digit(10));
-
-                    chop.insert(0, '-');
-                    return Some(Token {
-                        kind: TokenKind::IntLiteral(chop.parse().unwrap()),
-                        loc,
-                    });
+                    return Some(self.number(true));
                 } else {
                     return Some(self.skip_n_return(1, TokenKind::Minus));
                 }
@@ -121,6 +171,9 @@ impl<'a> Lexer<'a> {
                 if self.content[1] == '=' {
                     return Some(self.skip_n_return(2, TokenKind::EqualEqual));
                 }
+                if self.content[1] == '>' {
+                    return Some(self.skip_n_return(2, TokenKind::FatArrow));
+                }
 
                 Some(self.skip_n_return(1, TokenKind::Equal))
             }
@@ -145,19 +198,12 @@ impl<'a> Lexer<'a> {
                 Some(self.skip_n_return(1, TokenKind::BitOr))
             }
             '&' => {
-                if self.content[1] == '|' {
+                if self.content[1] == '&' {
                     return Some(self.skip_n_return(2, TokenKind::And));
                 }
                 Some(self.skip_n_return(1, TokenKind::BitAnd))
             }
-            '0'..='9' => {
-                let (chop, loc) = self.chop_while(|a| a.is_digit(10));
-
-                Some(Token {
-                    kind: TokenKind::IntLiteral(chop.parse().unwrap()),
-                    loc,
-                })
-            }
+            '0'..='9' => Some(self.number(false)),
             'a'..='z' | 'A'..='Z' | '_' => {
                 let (chop, loc) = self.chop_while(|a| a.is_alphanumeric() || *a == '_');
 
@@ -216,7 +262,7 @@ mod test {
 
     #[test]
     fn parse_puncts() {
-        let body = "[] () {} | & = == != > >= < <= + - * / . ; :";
+        let body = "[] () {} | & || && = == != > >= < <= + - * / . ... .. ..= ; : :: =>";
         let chars = body.chars().collect::<Vec<_>>();
 
         let expected = [
@@ -228,6 +274,8 @@ mod test {
             TokenKind::CCurly,
             TokenKind::BitOr,
             TokenKind::BitAnd,
+            TokenKind::Or,
+            TokenKind::And,
             TokenKind::Equal,
             TokenKind::EqualEqual,
             TokenKind::NotEqual,
@@ -240,8 +288,13 @@ mod test {
             TokenKind::Star,
             TokenKind::Slash,
             TokenKind::Dot,
+            TokenKind::Ellipsis,
+            TokenKind::DotDot,
+            TokenKind::DotDotEqual,
             TokenKind::SemiColon,
             TokenKind::Colon,
+            TokenKind::ColonColon,
+            TokenKind::FatArrow,
         ];
 
         let mut lexer = Lexer::new(&chars);
@@ -254,7 +307,7 @@ mod test {
 
     #[test]
     fn parse_keyword() {
-        let body = "spellcard offer eternal vow and or invite foreseen otherwise until";
+        let body = "spellcard offer eternal vow and or invite foreseen otherwise until true false null struct enum match impl trait";
         let chars = body.chars().collect::<Vec<_>>();
         let mut lexer = Lexer::new(&chars);
 
@@ -406,4 +459,26 @@ main
             assert_eq!(token.loc.row, loc.row);
         }
     }
+
+    #[test]
+    fn parse_float_literal() {
+        let body = "4.95 -0.5 1.";
+        let chars = body.chars().collect::<Vec<_>>();
+
+        let expected = [
+            (Loc::new(1, 1), TokenKind::FloatLiteral(4.95)),
+            (Loc::new(6, 1), TokenKind::FloatLiteral(-0.5)),
+            (Loc::new(11, 1), TokenKind::IntLiteral(1)),
+            (Loc::new(12, 1), TokenKind::Dot),
+        ];
+
+        let mut lexer = Lexer::new(&chars);
+
+        for (loc, kind) in expected {
+            let token = lexer.next().expect("it should have token");
+            assert_eq!(token.kind, kind);
+            assert_eq!(token.loc.column, loc.column);
+            assert_eq!(token.loc.row, loc.row);
+        }
+    }
 }
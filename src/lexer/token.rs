@@ -19,16 +19,24 @@ pub enum TokenKind {
 
     Ident(String),
     StringLiteral(String),
-    IntLiteral(i64),
+    IntLiteral(i128),
+    // Integer literal that does not fit in i128, kept as text so the parser can report it
+    IntOverflow(String),
+    FloatLiteral(f64),
 
     // Puncts
     Plus,
     Minus,
     Star,
     Dot,
+    Ellipsis,
+    DotDot,
+    DotDotEqual,
     Comma,
     SemiColon,
     Colon,
+    ColonColon,
+    FatArrow,
     Slash,
 
     Bang,
@@ -59,6 +67,14 @@ pub enum TokenKind {
     Foreseen,  // If statement
     Otherwise, // else statement
     Until,     // while statement
+    True,
+    False,
+    Null,
+    Struct,
+    Enum,
+    Match,
+    Impl,
+    Trait,
 }
 
 impl std::fmt::Display for TokenKind {
@@ -69,13 +85,22 @@ impl std::fmt::Display for TokenKind {
             TokenKind::Ident(str) => f.write_fmt(format_args!("IDENT: {}", str)),
             TokenKind::StringLiteral(str) => f.write_fmt(format_args!("CHAR LITERAL: {}", str)),
             TokenKind::IntLiteral(int) => f.write_fmt(format_args!("INT LITERAL: {}", int)),
+            TokenKind::IntOverflow(int) => f.write_fmt(format_args!("INT LITERAL: {}", int)),
+            TokenKind::FloatLiteral(float) => {
+                f.write_fmt(format_args!("FLOAT LITERAL: {:?}", float))
+            }
             TokenKind::Plus => f.write_str("PLUS"),
             TokenKind::Minus => f.write_str("MINUS"),
             TokenKind::Star => f.write_str("STAR"),
             TokenKind::Dot => f.write_str("DOT"),
+            TokenKind::Ellipsis => f.write_str("ELLIPSIS"),
+            TokenKind::DotDot => f.write_str("DOT DOT"),
+            TokenKind::DotDotEqual => f.write_str("DOT DOT EQUAL"),
             TokenKind::Comma => f.write_str("COMMA"),
             TokenKind::SemiColon => f.write_str("SEMICOLON"),
             TokenKind::Colon => f.write_str("COLON"),
+            TokenKind::ColonColon => f.write_str("COLON COLON"),
+            TokenKind::FatArrow => f.write_str("FAT ARROW"),
             TokenKind::Slash => f.write_str("SLASH"),
             TokenKind::Bang => f.write_str("BANG"),
             TokenKind::Equal => f.write_str("EQUAL"),
@@ -104,6 +129,14 @@ impl std::fmt::Display for TokenKind {
             TokenKind::Foreseen => f.write_str("FORESEEN"),
             TokenKind::Otherwise => f.write_str("OTHERWISE"),
             TokenKind::Until => f.write_str("UNTIL"),
+            TokenKind::True => f.write_str("TRUE"),
+            TokenKind::False => f.write_str("FALSE"),
+            TokenKind::Null => f.write_str("NULL"),
+            TokenKind::Struct => f.write_str("STRUCT"),
+            TokenKind::Enum => f.write_str("ENUM"),
+            TokenKind::Match => f.write_str("MATCH"),
+            TokenKind::Impl => f.write_str("IMPL"),
+            TokenKind::Trait => f.write_str("TRAIT"),
         }
     }
 }
@@ -3,6 +3,7 @@ pub mod cli;
 pub mod codegen;
 pub mod commons;
 pub mod compiler;
+pub mod engine;
 pub mod lexer;
 pub mod op;
 pub mod parser;
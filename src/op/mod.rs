@@ -1,4 +1,4 @@
-use crate::{ast::BinOp, value::Value};
+use crate::{ast::BinOp, commons::Loc, compiler::Type, value::Value};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Arg {
@@ -23,6 +23,7 @@ pub enum Op {
     },
     BinOp {
         binop: BinOp,
+        ty: Type,
         offset: usize,
         lhs: Arg,
         rhs: Arg,
@@ -40,6 +41,7 @@ pub enum Op {
         result: usize,
         name: String,
         args: Vec<Arg>,
+        types: Vec<Type>,
     },
     Ret(Option<Arg>),
     Jmp {
@@ -49,6 +51,32 @@ pub enum Op {
         name: String,
         arg: Arg,
     },
+    AddrOf {
+        offset: usize,
+        local: usize,
+    },
+    Load {
+        offset: usize,
+        ty: Type,
+        ptr: Arg,
+    },
+    Store {
+        ty: Type,
+        ptr: Arg,
+        arg: Arg,
+    },
+    Element {
+        offset: usize,
+        base: Arg,
+        index: Arg,
+        size: usize,
+    },
+    BoundsCheck {
+        index: Arg,
+        length: usize,
+    },
+    // Source location of the statement, only emitted when compiling with debug info
+    Loc(Loc),
 }
 
 impl std::fmt::Display for Arg {
@@ -73,12 +101,13 @@ impl std::fmt::Display for Op {
             }
             crate::op::Op::BinOp {
                 binop,
+                ty,
                 offset,
                 lhs,
                 rhs,
             } => f.write_fmt(format_args!(
-                "    BinOp {} {} {} {}",
-                offset, binop, lhs, rhs
+                "    BinOp<{}> {} {} {} {}",
+                ty, offset, binop, lhs, rhs
             )),
             crate::op::Op::ParamAssign { offset, arg } => {
                 f.write_fmt(format_args!("ParamAssign({}, {})", offset, arg))
@@ -88,9 +117,9 @@ impl std::fmt::Display for Op {
             crate::op::Op::UnaryNot { offset, arg } => {
                 f.write_fmt(format_args!("UnaryNot({}, {:?})", offset, arg))
             }
-            crate::op::Op::Call { result, name, args } => {
-                f.write_fmt(format_args!("Call({}, {}, {:?})", result, name, args))
-            }
+            crate::op::Op::Call {
+                result, name, args, ..
+            } => f.write_fmt(format_args!("Call({}, {}, {:?})", result, name, args)),
             crate::op::Op::Ret(arg) => match arg {
                 Some(arg) => f.write_fmt(format_args!("Ret({})", arg)),
                 None => f.write_fmt(format_args!("Ret(void)")),
@@ -99,6 +128,28 @@ impl std::fmt::Display for Op {
             crate::op::Op::JmpIfNot { name, arg } => {
                 f.write_fmt(format_args!("jnz {} {}", name, arg))
             }
+            crate::op::Op::AddrOf { offset, local } => {
+                f.write_fmt(format_args!("AddrOf({}, {})", offset, local))
+            }
+            crate::op::Op::Load { offset, ty, ptr } => {
+                f.write_fmt(format_args!("Load<{}>({}, {})", ty, offset, ptr))
+            }
+            crate::op::Op::Store { ty, ptr, arg } => {
+                f.write_fmt(format_args!("Store<{}>({}, {})", ty, ptr, arg))
+            }
+            crate::op::Op::Element {
+                offset,
+                base,
+                index,
+                size,
+            } => f.write_fmt(format_args!(
+                "Element({}, {} + {} * {})",
+                offset, base, index, size
+            )),
+            crate::op::Op::BoundsCheck { index, length } => {
+                f.write_fmt(format_args!("BoundsCheck({} < {})", index, length))
+            }
+            crate::op::Op::Loc(loc) => f.write_fmt(format_args!("; {}", loc)),
         }
     }
 }
@@ -8,6 +8,10 @@ pub enum ParseError {
         expected: Vec<TokenKind>,
         loc: Loc,
     },
+    IntegerOverflow {
+        found: String,
+        loc: Loc,
+    },
 }
 
 impl Error for ParseError {}
@@ -30,6 +34,10 @@ impl std::fmt::Display for ParseError {
                 f.write_fmt(format_args!(" at {}", loc))?;
                 Ok(())
             }
+            ParseError::IntegerOverflow { found, loc } => f.write_fmt(format_args!(
+                "Integer literal {} does not fit in 64 bit at {}",
+                found, loc
+            )),
         }
     }
 }
@@ -52,6 +60,10 @@ impl std::fmt::Debug for ParseError {
                 f.write_fmt(format_args!(" at {}", loc))?;
                 Ok(())
             }
+            ParseError::IntegerOverflow { found, loc } => f.write_fmt(format_args!(
+                "Integer literal {} does not fit in 64 bit at {}",
+                found, loc
+            )),
         }
     }
 }
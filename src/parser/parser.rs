@@ -1,19 +1,25 @@
 use super::error::ParseError;
 
 use crate::{
-    ast::{Expression, FunctionArgs, Statement, UnaryOp},
+    ast::{
+        EnumVariant, Expression, FunctionArgs, GenericParam, MatchArm, MethodSignature, Pattern,
+        Statement, UnaryOp,
+    },
+    bool,
     commons::Loc,
-    i32,
     lexer::{Lexer, Token, TokenKind},
     string,
+    value::Value,
 };
 
 fn get_precedence(token: &TokenKind) -> Option<u8> {
     Some(match token {
-        TokenKind::EqualEqual | TokenKind::NotEqual => 1,
-        TokenKind::Less | TokenKind::LessEqual | TokenKind::Greater | TokenKind::GreaterEqual => 2,
-        TokenKind::Plus | TokenKind::Minus => 3,
-        TokenKind::Star | TokenKind::Slash => 4,
+        TokenKind::Or => 1,
+        TokenKind::And => 2,
+        TokenKind::EqualEqual | TokenKind::NotEqual => 3,
+        TokenKind::Less | TokenKind::LessEqual | TokenKind::Greater | TokenKind::GreaterEqual => 4,
+        TokenKind::Plus | TokenKind::Minus => 5,
+        TokenKind::Star | TokenKind::Slash => 6,
         _ => return None,
     })
 }
@@ -39,14 +45,21 @@ impl<'a> Parser<'a> {
     }
 
     fn next_token(&mut self, loc: Loc) -> Result<Token, ParseError> {
-        if let Some(tok) = self.peeked.take() {
-            return Ok(tok);
+        let token = match self.peeked.take() {
+            Some(tok) => tok,
+            None => self.lexer.next().ok_or(ParseError::UnexpectedToken {
+                found: TokenKind::EOF,
+                expected: vec![],
+                loc,
+            })?,
+        };
+        match token.kind {
+            TokenKind::IntOverflow(found) => Err(ParseError::IntegerOverflow {
+                found,
+                loc: token.loc,
+            }),
+            _ => Ok(token),
         }
-        self.lexer.next().ok_or(ParseError::UnexpectedToken {
-            found: TokenKind::EOF,
-            expected: vec![],
-            loc,
-        })
     }
 
     fn expect_many_kind_but_no_consume(
@@ -84,6 +97,20 @@ impl<'a> Parser<'a> {
         }
     }
 
+    fn expect_int(&mut self, loc: Loc) -> Result<i128, ParseError> {
+        match self.next_token(loc)? {
+            Token {
+                kind: TokenKind::IntLiteral(int),
+                ..
+            } => Ok(int),
+            token => Err(ParseError::UnexpectedToken {
+                found: token.kind,
+                expected: vec![TokenKind::IntLiteral(0)],
+                loc: token.loc,
+            }),
+        }
+    }
+
     fn get_indent(&mut self, loc: Loc) -> Result<(String, Loc), ParseError> {
         let name_token = self.next_token(loc)?;
         let new_loc = name_token.loc;
@@ -113,6 +140,25 @@ impl<'a> Parser<'a> {
         Ok(stmt)
     }
 
+    pub fn parse_expression(&mut self) -> Result<Expression, ParseError> {
+        let loc = Loc::new(1, 1);
+        let expr = self.expression(loc)?;
+        if self
+            .peek_token()
+            .is_some_and(|token| token.kind == TokenKind::SemiColon)
+        {
+            self.next_token(loc)?;
+        }
+        match self.peek_token() {
+            Some(token) => Err(ParseError::UnexpectedToken {
+                found: token.kind.clone(),
+                expected: vec![TokenKind::SemiColon],
+                loc: token.loc,
+            }),
+            None => Ok(expr),
+        }
+    }
+
     fn parse_statement(&mut self, token: Token) -> Result<Option<Vec<Statement>>, ParseError> {
         match token.kind {
             TokenKind::Ident(name) => self.parse_ident(token.loc, name).map(Some),
@@ -123,6 +169,12 @@ impl<'a> Parser<'a> {
             TokenKind::Foreseen => self.parse_foreseen(token.loc).map(Some),
             TokenKind::Until => self.parse_until(token.loc).map(Some),
             TokenKind::Vow => self.parse_vow(token.loc).map(Some),
+            TokenKind::Star => self.parse_store(token.loc).map(Some),
+            TokenKind::Struct => self.parse_struct(token.loc).map(Some),
+            TokenKind::Enum => self.parse_enum(token.loc).map(Some),
+            TokenKind::Match => self.parse_match(token.loc).map(Some),
+            TokenKind::Trait => self.parse_trait(token.loc).map(Some),
+            TokenKind::Impl => self.parse_impl(token.loc).map(Some),
             TokenKind::EOF => Ok(None),
             _ => Err(ParseError::UnexpectedToken {
                 found: token.kind,
@@ -139,37 +191,99 @@ impl<'a> Parser<'a> {
                 let args = self.parse_call(token.loc)?;
                 self.expect_kind(token.loc, TokenKind::CParen)?;
                 self.expect_kind(token.loc, TokenKind::SemiColon)?;
-                Ok(vec![Statement::Expression(Expression::Call {
-                    function: name,
-                    args,
-                })])
+                Ok(vec![Statement::Expression(
+                    Expression::Call {
+                        function: name,
+                        args,
+                    },
+                    loc,
+                )])
             }
             TokenKind::Equal => {
                 let value = self.expression(loc)?;
                 self.expect_kind(token.loc, TokenKind::SemiColon)?;
-                Ok(vec![Statement::Assignment { name, value }])
+                Ok(vec![Statement::Assignment { name, value, loc }])
+            }
+            TokenKind::OBracket | TokenKind::Dot => {
+                let at = token.loc;
+                self.peeked = Some(token);
+                let target = self.parse_postfix(Expression::Variable(name), at)?;
+                if let Expression::MethodCall { .. } = target
+                    && self.expect_many_kind_but_no_consume(at, vec![TokenKind::SemiColon])?
+                {
+                    self.expect_kind(at, TokenKind::SemiColon)?;
+                    return Ok(vec![Statement::Expression(target, loc)]);
+                }
+                let token = self.expect_kind(loc, TokenKind::Equal)?;
+                let value = self.expression(token.loc)?;
+                self.expect_kind(token.loc, TokenKind::SemiColon)?;
+                Ok(vec![Statement::Store { target, value, loc }])
             }
             _ => Err(ParseError::UnexpectedToken {
                 found: token.kind,
-                expected: vec![TokenKind::OParen, TokenKind::Equal],
+                expected: vec![
+                    TokenKind::OParen,
+                    TokenKind::Equal,
+                    TokenKind::OBracket,
+                    TokenKind::Dot,
+                ],
                 loc: token.loc,
             }),
         }
     }
-    fn parse_spellcard(&mut self, loc: Loc) -> Result<Vec<Statement>, ParseError> {
-        let (name, _) = self.get_indent(loc)?;
+    fn parse_store(&mut self, loc: Loc) -> Result<Vec<Statement>, ParseError> {
+        let target = Expression::Deref(Box::new(self.parse_primary(loc)?));
+        let token = self.expect_kind(loc, TokenKind::Equal)?;
+        let value = self.expression(token.loc)?;
+        self.expect_kind(token.loc, TokenKind::SemiColon)?;
+        Ok(vec![Statement::Store { target, value, loc }])
+    }
 
+    fn parse_type(&mut self, loc: Loc) -> Result<(String, Loc), ParseError> {
+        let mut pointer = String::new();
+        while self.expect_many_kind_but_no_consume(loc, vec![TokenKind::Star])? {
+            self.expect_kind(loc, TokenKind::Star)?;
+            pointer.push('*');
+        }
+        if !self.expect_many_kind_but_no_consume(loc, vec![TokenKind::OBracket])? {
+            let (name, loc) = self.get_indent(loc)?;
+            return Ok((pointer + &name, loc));
+        }
+        let token = self.expect_kind(loc, TokenKind::OBracket)?;
+        let (element, _) = self.parse_type(token.loc)?;
+        self.expect_kind(token.loc, TokenKind::SemiColon)?;
+        let length = self.expect_int(token.loc)?;
+        let end = self.expect_kind(token.loc, TokenKind::CBracket)?;
+        Ok((format!("{}[{}; {}]", pointer, element, length), end.loc))
+    }
+
+    fn parse_params(
+        &mut self,
+        loc: Loc,
+        variadic: bool,
+    ) -> Result<(Vec<FunctionArgs>, bool), ParseError> {
         self.expect_kind(loc, TokenKind::OParen)?;
+        let mut expected = vec![TokenKind::Ident("".to_string()), TokenKind::CParen];
+        if variadic {
+            expected.push(TokenKind::Ellipsis);
+        }
+
         let mut args = vec![];
+        let mut is_variadic = false;
         loop {
             let (name_param, loc) = match self.peek_token() {
                 Some(token) => match token.kind.clone() {
                     TokenKind::Ident(name) => (name, token.loc.clone()),
                     TokenKind::CParen => break,
+                    TokenKind::Ellipsis if variadic => {
+                        self.next_token(loc)?;
+                        is_variadic = true;
+                        break;
+                    }
                     _ => {
                         return Err(ParseError::UnexpectedToken {
                             found: token.kind.clone(),
-                            expected: vec![TokenKind::Ident("".to_string()), TokenKind::CParen],
+                            expected,
                             loc: token.loc,
                         });
                     }
@@ -177,14 +291,14 @@ impl<'a> Parser<'a> {
                 None => {
                     return Err(ParseError::UnexpectedToken {
                         found: TokenKind::EOF,
-                        expected: vec![TokenKind::Ident("".to_string()), TokenKind::CParen],
+                        expected,
                         loc,
                     });
                 }
             };
             let tok = self.next_token(loc)?;
             self.expect_kind(tok.loc, TokenKind::Colon)?;
-            let (annotation, _) = self.get_indent(tok.loc)?;
+            let (annotation, _) = self.parse_type(tok.loc)?;
             args.push(FunctionArgs {
                 name: name_param,
                 annotation,
@@ -195,99 +309,150 @@ impl<'a> Parser<'a> {
             }
         }
         self.expect_kind(loc, TokenKind::CParen)?;
-        let (type_annotation, _) = self.get_indent(loc)?;
+        Ok((args, is_variadic))
+    }
+
+    fn parse_spellcard(&mut self, loc: Loc) -> Result<Vec<Statement>, ParseError> {
+        let (name, name_loc) = self.get_indent(loc)?;
+        let mut generics = vec![];
+        if self.expect_many_kind_but_no_consume(name_loc, vec![TokenKind::Less])? {
+            self.expect_kind(name_loc, TokenKind::Less)?;
+            while !self.expect_many_kind_but_no_consume(name_loc, vec![TokenKind::Greater])? {
+                let (generic, generic_loc) = self.get_indent(name_loc)?;
+                let mut bounds = vec![];
+                if self.expect_many_kind_but_no_consume(generic_loc, vec![TokenKind::Colon])? {
+                    self.expect_kind(generic_loc, TokenKind::Colon)?;
+                    loop {
+                        let (bound, _) = self.get_indent(generic_loc)?;
+                        bounds.push(bound);
+                        if !self
+                            .expect_many_kind_but_no_consume(generic_loc, vec![TokenKind::Plus])?
+                        {
+                            break;
+                        }
+                        self.expect_kind(generic_loc, TokenKind::Plus)?;
+                    }
+                }
+                generics.push(GenericParam {
+                    name: generic,
+                    bounds,
+                });
+                if self.expect_many_kind_but_no_consume(generic_loc, vec![TokenKind::Comma])? {
+                    self.expect_kind(generic_loc, TokenKind::Comma)?;
+                }
+            }
+            self.expect_kind(name_loc, TokenKind::Greater)?;
+        }
+
+        let (args, _) = self.parse_params(loc, false)?;
+        let (type_annotation, _) = self.parse_type(loc)?;
         self.expect_kind(loc, TokenKind::OCurly)?;
 
         let body = self.parse_body(loc)?;
         // TODO : Parse args
         Ok(vec![Statement::SpellCard {
             name,
+            generics,
             args,
             return_type: Some(type_annotation),
             body,
+            loc,
         }])
     }
 
-    fn parse_vow(&mut self, loc: Loc) -> Result<Vec<Statement>, ParseError> {
-        let (name, next_loc) = self.get_indent(loc)?;
-        let token = self.next_token(next_loc)?;
-        let mut annotation = None;
-        if token.kind == TokenKind::Equal {
-            let primary = self.expression(token.loc)?;
-            self.expect_kind(token.loc, TokenKind::SemiColon)?;
-            return Ok(vec![
-                Statement::Vow {
-                    name: name.to_owned(),
-                    annotation: None,
-                },
-                Statement::Assignment {
-                    name: name,
-                    value: primary,
-                },
-            ]);
-        } else if token.kind == TokenKind::Colon {
-            let (annon, next_loc) = self.get_indent(loc)?;
-            annotation = Some(annon);
-
-            if token.kind == TokenKind::Equal {
-                let primary = self.expression(next_loc)?;
-                self.expect_kind(token.loc, TokenKind::SemiColon)?;
-                return Ok(vec![
-                    Statement::Vow {
-                        name: name.to_owned(),
-                        annotation,
-                    },
-                    Statement::Assignment {
-                        name: name,
-                        value: primary,
-                    },
-                ]);
-            }
+    fn parse_trait(&mut self, loc: Loc) -> Result<Vec<Statement>, ParseError> {
+        let (name, name_loc) = self.get_indent(loc)?;
+        self.expect_kind(name_loc, TokenKind::OCurly)?;
+        let mut methods = vec![];
+        while !self.expect_many_kind_but_no_consume(name_loc, vec![TokenKind::CCurly])? {
+            let token = self.expect_kind(name_loc, TokenKind::SpellCard)?;
+            let (method, method_loc) = self.get_indent(token.loc)?;
+            let (args, _) = self.parse_params(method_loc, false)?;
+            let (return_type, _) = self.parse_type(method_loc)?;
+            self.expect_kind(method_loc, TokenKind::SemiColon)?;
+            methods.push(MethodSignature {
+                name: method,
+                args,
+                return_type: Some(return_type),
+            });
         }
+        self.expect_kind(name_loc, TokenKind::CCurly)?;
+        Ok(vec![Statement::Trait { name, methods, loc }])
+    }
 
-        self.expect_kind(token.loc, TokenKind::SemiColon)?;
-        Ok(vec![Statement::Vow { name, annotation }])
+    fn parse_impl(&mut self, loc: Loc) -> Result<Vec<Statement>, ParseError> {
+        let (ty, ty_loc) = self.parse_type(loc)?;
+        let mut trait_name = None;
+        if self.expect_many_kind_but_no_consume(ty_loc, vec![TokenKind::Colon])? {
+            self.expect_kind(ty_loc, TokenKind::Colon)?;
+            trait_name = Some(self.get_indent(ty_loc)?.0);
+        }
+        self.expect_kind(ty_loc, TokenKind::OCurly)?;
+        let mut methods = vec![];
+        while !self.expect_many_kind_but_no_consume(ty_loc, vec![TokenKind::CCurly])? {
+            let token = self.expect_kind(ty_loc, TokenKind::SpellCard)?;
+            methods.append(&mut self.parse_spellcard(token.loc)?);
+        }
+        self.expect_kind(ty_loc, TokenKind::CCurly)?;
+        Ok(vec![Statement::Impl {
+            ty,
+            trait_name,
+            methods,
+            loc,
+        }])
     }
 
-    fn parse_eternal(&mut self, loc: Loc) -> Result<Vec<Statement>, ParseError> {
+    fn parse_declaration(
+        &mut self,
+        loc: Loc,
+    ) -> Result<(String, Option<String>, Option<Expression>), ParseError> {
         let (name, next_loc) = self.get_indent(loc)?;
-        let token = self.next_token(next_loc)?;
+        let mut token = self.next_token(next_loc)?;
         let mut annotation = None;
+        if token.kind == TokenKind::Colon {
+            let (annon, next_loc) = self.parse_type(token.loc)?;
+            annotation = Some(annon);
+            token = self.next_token(next_loc)?;
+        }
+
+        let mut value = None;
         if token.kind == TokenKind::Equal {
-            let primary = self.expression(token.loc)?;
+            value = Some(self.expression(token.loc)?);
             self.expect_kind(token.loc, TokenKind::SemiColon)?;
-            return Ok(vec![
-                Statement::Eternal {
-                    name: name.to_owned(),
-                    annotation: None,
-                },
-                Statement::Assignment {
-                    name: name,
-                    value: primary,
-                },
-            ]);
-        } else if token.kind == TokenKind::Colon {
-            let (annon, next_loc) = self.get_indent(loc)?;
-            annotation = Some(annon);
+        } else if token.kind != TokenKind::SemiColon {
+            return Err(ParseError::UnexpectedToken {
+                found: token.kind,
+                expected: vec![TokenKind::Colon, TokenKind::Equal, TokenKind::SemiColon],
+                loc: token.loc,
+            });
+        }
+        Ok((name, annotation, value))
+    }
 
-            if token.kind == TokenKind::Equal {
-                let primary = self.expression(next_loc)?;
-                self.expect_kind(token.loc, TokenKind::SemiColon)?;
-                return Ok(vec![
-                    Statement::Eternal {
-                        name: name.to_owned(),
-                        annotation,
-                    },
-                    Statement::Assignment {
-                        name: name,
-                        value: primary,
-                    },
-                ]);
-            }
+    fn parse_vow(&mut self, loc: Loc) -> Result<Vec<Statement>, ParseError> {
+        let (name, annotation, value) = self.parse_declaration(loc)?;
+        let mut stmt = vec![Statement::Vow {
+            name: name.to_owned(),
+            annotation,
+            loc,
+        }];
+        if let Some(value) = value {
+            stmt.push(Statement::Assignment { name, value, loc });
         }
+        Ok(stmt)
+    }
 
-        self.expect_kind(token.loc, TokenKind::SemiColon)?;
-        Ok(vec![Statement::Eternal { name, annotation }])
+    fn parse_eternal(&mut self, loc: Loc) -> Result<Vec<Statement>, ParseError> {
+        let (name, annotation, value) = self.parse_declaration(loc)?;
+        let mut stmt = vec![Statement::Eternal {
+            name: name.to_owned(),
+            annotation,
+            loc,
+        }];
+        if let Some(value) = value {
+            stmt.push(Statement::Assignment { name, value, loc });
+        }
+        Ok(stmt)
     }
 
     fn parse_offer(&mut self, loc: Loc) -> Result<Vec<Statement>, ParseError> {
@@ -297,20 +462,36 @@ impl<'a> Parser<'a> {
                 _ => {
                     let primary = self.expression(loc)?;
                     self.expect_kind(loc, TokenKind::SemiColon)?;
-                    return Ok(vec![Statement::Offer(Some(primary))]);
+                    return Ok(vec![Statement::Offer(Some(primary), loc)]);
                 }
             },
             None => todo!(),
         };
 
         self.next_token(loc)?;
-        Ok(vec![Statement::Offer(None)])
+        Ok(vec![Statement::Offer(None, loc)])
     }
 
     fn parse_invite(&mut self, loc: Loc) -> Result<Vec<Statement>, ParseError> {
         let (name, new_loc) = self.get_indent(loc)?;
+        let mut args = None;
+        let mut variadic = false;
+        let mut return_type = None;
+        if self.expect_many_kind_but_no_consume(new_loc, vec![TokenKind::OParen])? {
+            let (params, is_variadic) = self.parse_params(new_loc, true)?;
+            let (annotation, _) = self.parse_type(new_loc)?;
+            args = Some(params);
+            variadic = is_variadic;
+            return_type = Some(annotation);
+        }
         self.expect_kind(new_loc, TokenKind::SemiColon)?;
-        Ok(vec![Statement::Invite { name }])
+        Ok(vec![Statement::Invite {
+            name,
+            args,
+            variadic,
+            return_type,
+            loc,
+        }])
     }
 
     fn parse_foreseen(&mut self, loc: Loc) -> Result<Vec<Statement>, ParseError> {
@@ -334,6 +515,7 @@ impl<'a> Parser<'a> {
             condition,
             then_branch,
             else_branch,
+            loc,
         }])
     }
 
@@ -345,6 +527,7 @@ impl<'a> Parser<'a> {
         Ok(vec![Statement::Until {
             condition,
             body: then_branch,
+            loc,
         }])
     }
 
@@ -385,12 +568,260 @@ impl<'a> Parser<'a> {
         Ok(left)
     }
 
+    fn parse_postfix(&mut self, mut expr: Expression, loc: Loc) -> Result<Expression, ParseError> {
+        loop {
+            expr = match self.peek_token().map(|token| token.kind.clone()) {
+                Some(TokenKind::OBracket) => {
+                    let token = self.expect_kind(loc, TokenKind::OBracket)?;
+                    let index = self.expression(token.loc)?;
+                    self.expect_kind(token.loc, TokenKind::CBracket)?;
+                    Expression::Index {
+                        array: Box::new(expr),
+                        index: Box::new(index),
+                    }
+                }
+                Some(TokenKind::Dot) => {
+                    let token = self.expect_kind(loc, TokenKind::Dot)?;
+                    let (field, field_loc) = self.get_indent(token.loc)?;
+                    if self.expect_many_kind_but_no_consume(field_loc, vec![TokenKind::OParen])? {
+                        self.expect_kind(field_loc, TokenKind::OParen)?;
+                        let args = self.parse_call(field_loc)?;
+                        self.expect_kind(field_loc, TokenKind::CParen)?;
+                        Expression::MethodCall {
+                            receiver: Box::new(expr),
+                            method: field,
+                            args,
+                        }
+                    } else {
+                        Expression::Field {
+                            base: Box::new(expr),
+                            field,
+                        }
+                    }
+                }
+                _ => return Ok(expr),
+            };
+        }
+    }
+
     fn parse_primary(&mut self, loc: Loc) -> Result<Expression, ParseError> {
+        let expr = self.parse_operand(loc)?;
+        self.parse_postfix(expr, loc)
+    }
+
+    fn is_struct_literal(&mut self) -> bool {
+        if !self
+            .peek_token()
+            .is_some_and(|token| token.kind == TokenKind::OCurly)
+        {
+            return false;
+        }
+        let mut lexer = self.lexer.clone();
+        matches!(
+            (lexer.next(), lexer.next()),
+            (
+                Some(Token {
+                    kind: TokenKind::Ident(_),
+                    ..
+                }),
+                Some(Token {
+                    kind: TokenKind::Colon,
+                    ..
+                })
+            )
+        )
+    }
+
+    fn parse_struct(&mut self, loc: Loc) -> Result<Vec<Statement>, ParseError> {
+        let (name, name_loc) = self.get_indent(loc)?;
+        self.expect_kind(name_loc, TokenKind::OCurly)?;
+        let mut fields = vec![];
+        while !self.expect_many_kind_but_no_consume(name_loc, vec![TokenKind::CCurly])? {
+            let (field, field_loc) = self.get_indent(name_loc)?;
+            self.expect_kind(field_loc, TokenKind::Colon)?;
+            let (annotation, _) = self.parse_type(field_loc)?;
+            fields.push(FunctionArgs {
+                name: field,
+                annotation,
+            });
+            if self.expect_many_kind_but_no_consume(field_loc, vec![TokenKind::Comma])? {
+                self.expect_kind(field_loc, TokenKind::Comma)?;
+            }
+        }
+        self.expect_kind(name_loc, TokenKind::CCurly)?;
+        Ok(vec![Statement::Struct { name, fields, loc }])
+    }
+
+    fn parse_struct_literal(&mut self, name: String, loc: Loc) -> Result<Expression, ParseError> {
+        self.expect_kind(loc, TokenKind::OCurly)?;
+        let mut fields = vec![];
+        while !self.expect_many_kind_but_no_consume(loc, vec![TokenKind::CCurly])? {
+            let (field, field_loc) = self.get_indent(loc)?;
+            self.expect_kind(field_loc, TokenKind::Colon)?;
+            fields.push((field, self.expression(field_loc)?));
+            if self.expect_many_kind_but_no_consume(field_loc, vec![TokenKind::Comma])? {
+                self.expect_kind(field_loc, TokenKind::Comma)?;
+            }
+        }
+        self.expect_kind(loc, TokenKind::CCurly)?;
+        Ok(Expression::Struct { name, fields })
+    }
+
+    fn parse_enum(&mut self, loc: Loc) -> Result<Vec<Statement>, ParseError> {
+        let (name, name_loc) = self.get_indent(loc)?;
+        self.expect_kind(name_loc, TokenKind::OCurly)?;
+        let mut variants = vec![];
+        while !self.expect_many_kind_but_no_consume(name_loc, vec![TokenKind::CCurly])? {
+            let (variant, variant_loc) = self.get_indent(name_loc)?;
+            let mut fields = vec![];
+            if self.expect_many_kind_but_no_consume(variant_loc, vec![TokenKind::OParen])? {
+                self.expect_kind(variant_loc, TokenKind::OParen)?;
+                while !self.expect_many_kind_but_no_consume(variant_loc, vec![TokenKind::CParen])? {
+                    let (annotation, _) = self.parse_type(variant_loc)?;
+                    fields.push(annotation);
+                    if self.expect_many_kind_but_no_consume(variant_loc, vec![TokenKind::Comma])? {
+                        self.expect_kind(variant_loc, TokenKind::Comma)?;
+                    }
+                }
+                self.expect_kind(variant_loc, TokenKind::CParen)?;
+            }
+            variants.push(EnumVariant {
+                name: variant,
+                fields,
+            });
+            if self.expect_many_kind_but_no_consume(variant_loc, vec![TokenKind::Comma])? {
+                self.expect_kind(variant_loc, TokenKind::Comma)?;
+            }
+        }
+        self.expect_kind(name_loc, TokenKind::CCurly)?;
+        Ok(vec![Statement::Enum {
+            name,
+            variants,
+            loc,
+        }])
+    }
+
+    fn parse_variant(&mut self, name: String, loc: Loc) -> Result<Expression, ParseError> {
+        self.expect_kind(loc, TokenKind::ColonColon)?;
+        let (variant, variant_loc) = self.get_indent(loc)?;
+        let mut args = vec![];
+        if self.expect_many_kind_but_no_consume(variant_loc, vec![TokenKind::OParen])? {
+            self.expect_kind(variant_loc, TokenKind::OParen)?;
+            args = self.parse_call(variant_loc)?;
+            self.expect_kind(variant_loc, TokenKind::CParen)?;
+        }
+        Ok(Expression::Variant {
+            name,
+            variant,
+            args,
+        })
+    }
+
+    fn parse_match(&mut self, loc: Loc) -> Result<Vec<Statement>, ParseError> {
+        let value = self.expression(loc)?;
+        self.expect_kind(loc, TokenKind::OCurly)?;
+        let mut arms = vec![];
+        while !self.expect_many_kind_but_no_consume(loc, vec![TokenKind::CCurly])? {
+            let pattern = self.parse_pattern(loc)?;
+            let arrow = self.expect_kind(loc, TokenKind::FatArrow)?;
+            self.expect_kind(arrow.loc, TokenKind::OCurly)?;
+            let body = self.parse_body(arrow.loc)?;
+            arms.push(MatchArm { pattern, body });
+            if self.expect_many_kind_but_no_consume(arrow.loc, vec![TokenKind::Comma])? {
+                self.expect_kind(arrow.loc, TokenKind::Comma)?;
+            }
+        }
+        self.expect_kind(loc, TokenKind::CCurly)?;
+        Ok(vec![Statement::Match { value, arms, loc }])
+    }
+
+    fn parse_pattern(&mut self, loc: Loc) -> Result<Pattern, ParseError> {
         let token = self.next_token(loc)?;
         match token.kind {
-            TokenKind::IntLiteral(int) => Ok(Expression::Literal(i32!(int as i32))),
+            TokenKind::Ident(name) if name == "_" => Ok(Pattern::Wildcard),
+            TokenKind::Ident(name) => {
+                self.expect_kind(token.loc, TokenKind::ColonColon)?;
+                let (variant, variant_loc) = self.get_indent(token.loc)?;
+                let mut bindings = vec![];
+                if self.expect_many_kind_but_no_consume(variant_loc, vec![TokenKind::OParen])? {
+                    self.expect_kind(variant_loc, TokenKind::OParen)?;
+                    while !self
+                        .expect_many_kind_but_no_consume(variant_loc, vec![TokenKind::CParen])?
+                    {
+                        bindings.push(self.get_indent(variant_loc)?.0);
+                        if self
+                            .expect_many_kind_but_no_consume(variant_loc, vec![TokenKind::Comma])?
+                        {
+                            self.expect_kind(variant_loc, TokenKind::Comma)?;
+                        }
+                    }
+                    self.expect_kind(variant_loc, TokenKind::CParen)?;
+                }
+                Ok(Pattern::Variant {
+                    name,
+                    variant,
+                    bindings,
+                })
+            }
+            TokenKind::IntLiteral(start) => {
+                let inclusive = match self.peek_token().map(|token| token.kind.clone()) {
+                    Some(TokenKind::DotDot) => false,
+                    Some(TokenKind::DotDotEqual) => true,
+                    _ => {
+                        return Ok(Pattern::Range {
+                            start,
+                            end: start,
+                            inclusive: true,
+                        });
+                    }
+                };
+                self.next_token(token.loc)?;
+                let end = self.expect_int(token.loc)?;
+                Ok(Pattern::Range {
+                    start,
+                    end,
+                    inclusive,
+                })
+            }
+            kind => Err(ParseError::UnexpectedToken {
+                found: kind,
+                expected: vec![TokenKind::Ident("".into()), TokenKind::IntLiteral(0)],
+                loc: token.loc,
+            }),
+        }
+    }
+
+    fn parse_operand(&mut self, loc: Loc) -> Result<Expression, ParseError> {
+        let token = self.next_token(loc)?;
+        match token.kind {
+            TokenKind::IntLiteral(int) => {
+                Value::int(int)
+                    .map(Expression::Literal)
+                    .ok_or(ParseError::IntegerOverflow {
+                        found: int.to_string(),
+                        loc: token.loc,
+                    })
+            }
+            TokenKind::FloatLiteral(float) => Ok(Expression::Literal(Value::F64(float))),
             TokenKind::StringLiteral(str) => Ok(Expression::Literal(string!(str))),
+            TokenKind::True => Ok(Expression::Literal(bool!(true))),
+            TokenKind::False => Ok(Expression::Literal(bool!(false))),
+            TokenKind::Null => Ok(Expression::Literal(Value::Pointer(0))),
             TokenKind::Bang => Ok(self.parse_bang(token.loc)?),
+            TokenKind::Star => Ok(Expression::Deref(Box::new(self.parse_primary(token.loc)?))),
+            TokenKind::BitAnd => Ok(Expression::AddressOf(Box::new(
+                self.parse_primary(token.loc)?,
+            ))),
+            TokenKind::Ident(name) if self.is_struct_literal() => {
+                self.parse_struct_literal(name, token.loc)
+            }
+            TokenKind::Ident(name)
+                if self
+                    .peek_token()
+                    .is_some_and(|token| token.kind == TokenKind::ColonColon) =>
+            {
+                self.parse_variant(name, token.loc)
+            }
             TokenKind::Ident(name) => {
                 let args = match self.peek_token() {
                     Some(tok) if tok.kind == TokenKind::OParen => {
@@ -411,6 +842,17 @@ impl<'a> Parser<'a> {
                 self.expect_kind(token.loc, TokenKind::CParen)?;
                 Ok(expr)
             }
+            TokenKind::OBracket => {
+                let mut elements = vec![];
+                while !self.expect_many_kind_but_no_consume(token.loc, vec![TokenKind::CBracket])? {
+                    elements.push(self.expression(token.loc)?);
+                    if self.expect_many_kind_but_no_consume(token.loc, vec![TokenKind::Comma])? {
+                        self.expect_kind(token.loc, TokenKind::Comma)?;
+                    }
+                }
+                self.expect_kind(token.loc, TokenKind::CBracket)?;
+                Ok(Expression::Array(elements))
+            }
             other => Err(ParseError::UnexpectedToken {
                 found: other,
                 expected: vec![
@@ -1,11 +1,15 @@
 use crate::{
-    ast::{BinOp, Expression, FunctionArgs, Statement},
+    ast::{
+        BinOp, EnumVariant, Expression, FunctionArgs, GenericParam, MatchArm, MethodSignature,
+        Pattern, Statement,
+    },
+    commons::Loc,
     i32,
     lexer::Lexer,
     string,
 };
 
-use super::parser::*;
+use super::{error::ParseError, parser::*};
 
 fn setup(body: &str) -> Vec<Statement> {
     let chars = body.chars().collect::<Vec<_>>();
@@ -25,9 +29,14 @@ spellcard main() i32 {
 
     let expected = vec![Statement::SpellCard {
         name: "main".to_owned(),
+        generics: vec![],
         args: vec![],
         return_type: Some("i32".to_string()),
-        body: vec![Statement::Offer(Some(Expression::Literal(i32!(69))))],
+        body: vec![Statement::Offer(
+            Some(Expression::Literal(i32!(69))),
+            Loc::new(5, 3),
+        )],
+        loc: Loc::new(1, 2),
     }];
 
     let ops = setup(body);
@@ -46,19 +55,23 @@ spellcard main() i32 {
         ";
     let expected = vec![Statement::SpellCard {
         name: "main".to_owned(),
+        generics: vec![],
         args: vec![],
         return_type: Some("i32".to_string()),
         body: vec![
             Statement::Eternal {
                 name: "foo".to_owned(),
                 annotation: None,
+                loc: Loc::new(5, 3),
             },
             Statement::Assignment {
                 name: "foo".to_owned(),
                 value: Expression::Literal(i32!(69)),
+                loc: Loc::new(5, 3),
             },
-            Statement::Offer(Some(Expression::Variable("foo".to_owned()))),
+            Statement::Offer(Some(Expression::Variable("foo".to_owned())), Loc::new(5, 4)),
         ],
+        loc: Loc::new(1, 2),
     }];
 
     let ops = setup(body);
@@ -77,12 +90,14 @@ spellcard main() i32 {
         ";
     let expected = vec![Statement::SpellCard {
         name: "main".to_owned(),
+        generics: vec![],
         args: vec![],
         return_type: Some("i32".to_string()),
         body: vec![
             Statement::Eternal {
                 name: "foo".to_owned(),
                 annotation: None,
+                loc: Loc::new(5, 3),
             },
             Statement::Assignment {
                 name: "foo".to_owned(),
@@ -91,9 +106,11 @@ spellcard main() i32 {
                     left: Box::new(Expression::Literal(i32!(35))),
                     right: Box::new(Expression::Literal(i32!(34))),
                 },
+                loc: Loc::new(5, 3),
             },
-            Statement::Offer(Some(Expression::Variable("foo".to_owned()))),
+            Statement::Offer(Some(Expression::Variable("foo".to_owned())), Loc::new(5, 4)),
         ],
+        loc: Loc::new(1, 2),
     }];
 
     let ops = setup(body);
@@ -112,12 +129,14 @@ spellcard main() i32 {
         ";
     let expected = vec![Statement::SpellCard {
         name: "main".to_owned(),
+        generics: vec![],
         args: vec![],
         return_type: Some("i32".to_string()),
         body: vec![
             Statement::Eternal {
                 name: "foo".to_owned(),
                 annotation: None,
+                loc: Loc::new(5, 3),
             },
             Statement::Assignment {
                 name: "foo".to_owned(),
@@ -130,9 +149,11 @@ spellcard main() i32 {
                         right: Box::new(Expression::Literal(i32!(4))),
                     }),
                 },
+                loc: Loc::new(5, 3),
             },
-            Statement::Offer(Some(Expression::Variable("foo".to_owned()))),
+            Statement::Offer(Some(Expression::Variable("foo".to_owned())), Loc::new(5, 4)),
         ],
+        loc: Loc::new(1, 2),
     }];
 
     let ops = setup(body);
@@ -146,10 +167,13 @@ fn parse_call_function_without_param() {
     let body = "
 foo();
         ";
-    let expected = vec![Statement::Expression(Expression::Call {
-        function: "foo".to_owned(),
-        args: vec![],
-    })];
+    let expected = vec![Statement::Expression(
+        Expression::Call {
+            function: "foo".to_owned(),
+            args: vec![],
+        },
+        Loc::new(1, 2),
+    )];
 
     let ops = setup(body);
     for (i, expect) in expected.iter().enumerate() {
@@ -162,10 +186,13 @@ fn parse_call_function_with_1_param() {
     let body = "
 foo(1);
         ";
-    let expected = vec![Statement::Expression(Expression::Call {
-        function: "foo".to_owned(),
-        args: vec![Expression::Literal(i32!(1))],
-    })];
+    let expected = vec![Statement::Expression(
+        Expression::Call {
+            function: "foo".to_owned(),
+            args: vec![Expression::Literal(i32!(1))],
+        },
+        Loc::new(1, 2),
+    )];
 
     let ops = setup(body);
     for (i, expect) in expected.iter().enumerate() {
@@ -178,10 +205,13 @@ fn parse_call_function_with_2_param() {
     let body = "
 foo(1, 2);
         ";
-    let expected = vec![Statement::Expression(Expression::Call {
-        function: "foo".to_owned(),
-        args: vec![Expression::Literal(i32!(1)), Expression::Literal(i32!(2))],
-    })];
+    let expected = vec![Statement::Expression(
+        Expression::Call {
+            function: "foo".to_owned(),
+            args: vec![Expression::Literal(i32!(1)), Expression::Literal(i32!(2))],
+        },
+        Loc::new(1, 2),
+    )];
 
     let ops = setup(body);
     for (i, expect) in expected.iter().enumerate() {
@@ -200,6 +230,7 @@ foo = foo(1, 2);
             function: "foo".to_owned(),
             args: vec![Expression::Literal(i32!(1)), Expression::Literal(i32!(2))],
         },
+        loc: Loc::new(1, 2),
     }];
 
     let ops = setup(body);
@@ -217,11 +248,15 @@ foreseen foo {
         ";
     let expected = vec![Statement::Foreseen {
         condition: Expression::Variable("foo".to_string()),
-        then_branch: vec![Statement::Expression(Expression::Call {
-            function: "say".to_string(),
-            args: vec![Expression::Literal(i32!(1))],
-        })],
+        then_branch: vec![Statement::Expression(
+            Expression::Call {
+                function: "say".to_string(),
+                args: vec![Expression::Literal(i32!(1))],
+            },
+            Loc::new(5, 3),
+        )],
         else_branch: None,
+        loc: Loc::new(1, 2),
     }];
 
     let ops = setup(body);
@@ -241,16 +276,22 @@ spellcard main() i32 {
         ";
     let expected = vec![Statement::SpellCard {
         name: "main".to_owned(),
+        generics: vec![],
         args: vec![],
         return_type: Some("i32".to_string()),
         body: vec![Statement::Foreseen {
             condition: Expression::Variable("foo".to_string()),
-            then_branch: vec![Statement::Expression(Expression::Call {
-                function: "say".to_string(),
-                args: vec![Expression::Literal(i32!(1))],
-            })],
+            then_branch: vec![Statement::Expression(
+                Expression::Call {
+                    function: "say".to_string(),
+                    args: vec![Expression::Literal(i32!(1))],
+                },
+                Loc::new(9, 4),
+            )],
             else_branch: None,
+            loc: Loc::new(5, 3),
         }],
+        loc: Loc::new(1, 2),
     }];
 
     let ops = setup(body);
@@ -270,14 +311,21 @@ foreseen foo {
         ";
     let expected = vec![Statement::Foreseen {
         condition: Expression::Variable("foo".to_string()),
-        then_branch: vec![Statement::Expression(Expression::Call {
-            function: "say".to_string(),
-            args: vec![Expression::Literal(i32!(1))],
-        })],
-        else_branch: Some(vec![Statement::Expression(Expression::Call {
-            function: "say".to_string(),
-            args: vec![Expression::Literal(i32!(2))],
-        })]),
+        then_branch: vec![Statement::Expression(
+            Expression::Call {
+                function: "say".to_string(),
+                args: vec![Expression::Literal(i32!(1))],
+            },
+            Loc::new(5, 3),
+        )],
+        else_branch: Some(vec![Statement::Expression(
+            Expression::Call {
+                function: "say".to_string(),
+                args: vec![Expression::Literal(i32!(2))],
+            },
+            Loc::new(5, 5),
+        )]),
+        loc: Loc::new(1, 2),
     }];
 
     let ops = setup(body);
@@ -299,19 +347,28 @@ spellcard main() i32 {
         ";
     let expected = vec![Statement::SpellCard {
         name: "main".to_owned(),
+        generics: vec![],
         args: vec![],
         return_type: Some("i32".to_string()),
         body: vec![Statement::Foreseen {
             condition: Expression::Variable("foo".to_string()),
-            then_branch: vec![Statement::Expression(Expression::Call {
-                function: "say".to_string(),
-                args: vec![Expression::Literal(i32!(1))],
-            })],
-            else_branch: Some(vec![Statement::Expression(Expression::Call {
-                function: "say".to_string(),
-                args: vec![Expression::Literal(i32!(2))],
-            })]),
+            then_branch: vec![Statement::Expression(
+                Expression::Call {
+                    function: "say".to_string(),
+                    args: vec![Expression::Literal(i32!(1))],
+                },
+                Loc::new(9, 4),
+            )],
+            else_branch: Some(vec![Statement::Expression(
+                Expression::Call {
+                    function: "say".to_string(),
+                    args: vec![Expression::Literal(i32!(2))],
+                },
+                Loc::new(9, 6),
+            )]),
+            loc: Loc::new(5, 3),
         }],
+        loc: Loc::new(1, 2),
     }];
 
     let ops = setup(body);
@@ -329,10 +386,14 @@ until foo {
         ";
     let expected = vec![Statement::Until {
         condition: Expression::Variable("foo".to_string()),
-        body: vec![Statement::Expression(Expression::Call {
-            function: "say".to_string(),
-            args: vec![Expression::Literal(i32!(1))],
-        })],
+        body: vec![Statement::Expression(
+            Expression::Call {
+                function: "say".to_string(),
+                args: vec![Expression::Literal(i32!(1))],
+            },
+            Loc::new(5, 3),
+        )],
+        loc: Loc::new(1, 2),
     }];
 
     let ops = setup(body);
@@ -352,15 +413,21 @@ spellcard main() i32 {
         ";
     let expected = vec![Statement::SpellCard {
         name: "main".to_owned(),
+        generics: vec![],
         args: vec![],
         return_type: Some("i32".to_string()),
         body: vec![Statement::Until {
             condition: Expression::Variable("foo".to_string()),
-            body: vec![Statement::Expression(Expression::Call {
-                function: "say".to_string(),
-                args: vec![Expression::Literal(i32!(1))],
-            })],
+            body: vec![Statement::Expression(
+                Expression::Call {
+                    function: "say".to_string(),
+                    args: vec![Expression::Literal(i32!(1))],
+                },
+                Loc::new(9, 4),
+            )],
+            loc: Loc::new(5, 3),
         }],
+        loc: Loc::new(1, 2),
     }];
 
     let ops = setup(body);
@@ -378,14 +445,17 @@ spellcard main(foo: i32) i32 {
         ";
     let expected = vec![Statement::SpellCard {
         name: "main".to_owned(),
+        generics: vec![],
         args: vec![FunctionArgs {
             name: "foo".to_string(),
             annotation: "i32".to_string(),
         }],
         return_type: Some("i32".to_string()),
-        body: vec![Statement::Offer(Some(Expression::Variable(
-            "foo".to_owned(),
-        )))],
+        body: vec![Statement::Offer(
+            Some(Expression::Variable("foo".to_owned())),
+            Loc::new(5, 3),
+        )],
+        loc: Loc::new(1, 2),
     }];
 
     let ops = setup(body);
@@ -394,6 +464,47 @@ spellcard main(foo: i32) i32 {
     }
 }
 
+#[test]
+fn parse_generic_spellcard() {
+    let body = "
+spellcard first<T, U>(a: T, b: *U) T {
+    offer a;
+}
+        ";
+    let expected = vec![Statement::SpellCard {
+        name: "first".to_owned(),
+        generics: vec![
+            GenericParam {
+                name: "T".to_string(),
+                bounds: vec![],
+            },
+            GenericParam {
+                name: "U".to_string(),
+                bounds: vec![],
+            },
+        ],
+        args: vec![
+            FunctionArgs {
+                name: "a".to_string(),
+                annotation: "T".to_string(),
+            },
+            FunctionArgs {
+                name: "b".to_string(),
+                annotation: "*U".to_string(),
+            },
+        ],
+        return_type: Some("T".to_string()),
+        body: vec![Statement::Offer(
+            Some(Expression::Variable("a".to_owned())),
+            Loc::new(5, 3),
+        )],
+        loc: Loc::new(1, 2),
+    }];
+
+    let ops = setup(body);
+    assert_eq!(expected, ops);
+}
+
 #[test]
 fn parse_spellcard_param_2() {
     let body = "
@@ -403,6 +514,7 @@ spellcard main(foo: i32, bar: i32) i32 {
         ";
     let expected = vec![Statement::SpellCard {
         name: "main".to_owned(),
+        generics: vec![],
         args: vec![
             FunctionArgs {
                 name: "foo".to_string(),
@@ -414,9 +526,11 @@ spellcard main(foo: i32, bar: i32) i32 {
             },
         ],
         return_type: Some("i32".to_string()),
-        body: vec![Statement::Offer(Some(Expression::Variable(
-            "foo".to_owned(),
-        )))],
+        body: vec![Statement::Offer(
+            Some(Expression::Variable("foo".to_owned())),
+            Loc::new(5, 3),
+        )],
+        loc: Loc::new(1, 2),
     }];
 
     let ops = setup(body);
@@ -434,10 +548,12 @@ vow testing = \"Hi\";
         Statement::Vow {
             name: "testing".to_string(),
             annotation: None,
+            loc: Loc::new(1, 2),
         },
         Statement::Assignment {
             name: "testing".to_string(),
             value: Expression::Literal(string!("Hi".to_string())),
+            loc: Loc::new(1, 2),
         },
     ];
 
@@ -456,10 +572,12 @@ vow testing = 0;
         Statement::Vow {
             name: "testing".to_string(),
             annotation: None,
+            loc: Loc::new(1, 2),
         },
         Statement::Assignment {
             name: "testing".to_string(),
             value: Expression::Literal(i32!(0)),
+            loc: Loc::new(1, 2),
         },
     ];
 
@@ -468,3 +586,429 @@ vow testing = 0;
         assert_eq!(expect, ops.get(i).expect("Should have the same op length"));
     }
 }
+
+#[test]
+fn parse_vow_with_annotation() {
+    let body = "
+vow testing: i32 = 0;
+eternal name: cstr;
+        ";
+    let expected = vec![
+        Statement::Vow {
+            name: "testing".to_string(),
+            annotation: Some("i32".to_string()),
+            loc: Loc::new(1, 2),
+        },
+        Statement::Assignment {
+            name: "testing".to_string(),
+            value: Expression::Literal(i32!(0)),
+            loc: Loc::new(1, 2),
+        },
+        Statement::Eternal {
+            name: "name".to_string(),
+            annotation: Some("cstr".to_string()),
+            loc: Loc::new(1, 3),
+        },
+    ];
+
+    let ops = setup(body);
+    assert_eq!(expected, ops);
+}
+
+#[test]
+fn parse_invite_with_signature() {
+    let body = "
+invite puts;
+invite printf(fmt: cstr, ...) i32;
+invite InitWindow(w: i32, h: i32, title: cstr) void;
+        ";
+    let expected = vec![
+        Statement::Invite {
+            name: "puts".to_string(),
+            args: None,
+            variadic: false,
+            return_type: None,
+            loc: Loc::new(1, 2),
+        },
+        Statement::Invite {
+            name: "printf".to_string(),
+            args: Some(vec![FunctionArgs {
+                name: "fmt".to_string(),
+                annotation: "cstr".to_string(),
+            }]),
+            variadic: true,
+            return_type: Some("i32".to_string()),
+            loc: Loc::new(1, 3),
+        },
+        Statement::Invite {
+            name: "InitWindow".to_string(),
+            args: Some(vec![
+                FunctionArgs {
+                    name: "w".to_string(),
+                    annotation: "i32".to_string(),
+                },
+                FunctionArgs {
+                    name: "h".to_string(),
+                    annotation: "i32".to_string(),
+                },
+                FunctionArgs {
+                    name: "title".to_string(),
+                    annotation: "cstr".to_string(),
+                },
+            ]),
+            variadic: false,
+            return_type: Some("void".to_string()),
+            loc: Loc::new(1, 4),
+        },
+    ];
+
+    let ops = setup(body);
+    assert_eq!(ops.len(), expected.len());
+    for (i, expect) in expected.iter().enumerate() {
+        assert_eq!(expect, ops.get(i).expect("Should have the same op length"));
+    }
+}
+
+#[test]
+fn parse_pointer() {
+    let body = "
+vow p: *i32 = &a;
+*p = *p + 1;
+        ";
+    let expected = vec![
+        Statement::Vow {
+            name: "p".to_string(),
+            annotation: Some("*i32".to_string()),
+            loc: Loc::new(1, 2),
+        },
+        Statement::Assignment {
+            name: "p".to_string(),
+            value: Expression::AddressOf(Box::new(Expression::Variable("a".to_string()))),
+            loc: Loc::new(1, 2),
+        },
+        Statement::Store {
+            target: Expression::Deref(Box::new(Expression::Variable("p".to_string()))),
+            value: Expression::Binary {
+                op: BinOp::Add,
+                left: Box::new(Expression::Deref(Box::new(Expression::Variable(
+                    "p".to_string(),
+                )))),
+                right: Box::new(Expression::Literal(i32!(1))),
+            },
+            loc: Loc::new(1, 3),
+        },
+    ];
+
+    let ops = setup(body);
+    assert_eq!(expected, ops);
+}
+
+#[test]
+fn parse_array() {
+    let body = "
+vow a: [[i32; 2]; 3] = [1, 2];
+a[0][1] = a[1][0];
+        ";
+    let index = |array: Expression, index: i32| Expression::Index {
+        array: Box::new(array),
+        index: Box::new(Expression::Literal(i32!(index))),
+    };
+    let a = || Expression::Variable("a".to_string());
+    let expected = vec![
+        Statement::Vow {
+            name: "a".to_string(),
+            annotation: Some("[[i32; 2]; 3]".to_string()),
+            loc: Loc::new(1, 2),
+        },
+        Statement::Assignment {
+            name: "a".to_string(),
+            value: Expression::Array(vec![
+                Expression::Literal(i32!(1)),
+                Expression::Literal(i32!(2)),
+            ]),
+            loc: Loc::new(1, 2),
+        },
+        Statement::Store {
+            target: index(index(a(), 0), 1),
+            value: index(index(a(), 1), 0),
+            loc: Loc::new(1, 3),
+        },
+    ];
+
+    let ops = setup(body);
+    assert_eq!(expected, ops);
+}
+
+#[test]
+fn parse_struct() {
+    let body = "
+struct Point { x: i32, y: *Point }
+vow p = Point { x: 1, y: null };
+p.y.x = p.x;
+        ";
+    let field = |base: Expression, field: &str| Expression::Field {
+        base: Box::new(base),
+        field: field.to_string(),
+    };
+    let p = || Expression::Variable("p".to_string());
+    let expected = vec![
+        Statement::Struct {
+            name: "Point".to_string(),
+            fields: vec![
+                FunctionArgs {
+                    name: "x".to_string(),
+                    annotation: "i32".to_string(),
+                },
+                FunctionArgs {
+                    name: "y".to_string(),
+                    annotation: "*Point".to_string(),
+                },
+            ],
+            loc: Loc::new(1, 2),
+        },
+        Statement::Vow {
+            name: "p".to_string(),
+            annotation: None,
+            loc: Loc::new(1, 3),
+        },
+        Statement::Assignment {
+            name: "p".to_string(),
+            value: Expression::Struct {
+                name: "Point".to_string(),
+                fields: vec![
+                    ("x".to_string(), Expression::Literal(i32!(1))),
+                    (
+                        "y".to_string(),
+                        Expression::Literal(crate::value::Value::Pointer(0)),
+                    ),
+                ],
+            },
+            loc: Loc::new(1, 3),
+        },
+        Statement::Store {
+            target: field(field(p(), "y"), "x"),
+            value: field(p(), "x"),
+            loc: Loc::new(1, 4),
+        },
+    ];
+
+    let ops = setup(body);
+    assert_eq!(expected, ops);
+}
+
+#[test]
+fn parse_enum_match() {
+    let body = "
+enum Shape { Circle(f32), Rect(i32, i32), Empty }
+match Shape::Rect(1, 2) {
+    Shape::Rect(w, _) => {}
+    _ => {}
+}
+match 5 {
+    0..5 => {},
+    5..=9 => {}
+    10 => {}
+}
+        ";
+    let range = |start: i128, end: i128, inclusive: bool| MatchArm {
+        pattern: Pattern::Range {
+            start,
+            end,
+            inclusive,
+        },
+        body: vec![],
+    };
+    let expected = vec![
+        Statement::Enum {
+            name: "Shape".to_string(),
+            variants: vec![
+                EnumVariant {
+                    name: "Circle".to_string(),
+                    fields: vec!["f32".to_string()],
+                },
+                EnumVariant {
+                    name: "Rect".to_string(),
+                    fields: vec!["i32".to_string(), "i32".to_string()],
+                },
+                EnumVariant {
+                    name: "Empty".to_string(),
+                    fields: vec![],
+                },
+            ],
+            loc: Loc::new(1, 2),
+        },
+        Statement::Match {
+            value: Expression::Variant {
+                name: "Shape".to_string(),
+                variant: "Rect".to_string(),
+                args: vec![Expression::Literal(i32!(1)), Expression::Literal(i32!(2))],
+            },
+            arms: vec![
+                MatchArm {
+                    pattern: Pattern::Variant {
+                        name: "Shape".to_string(),
+                        variant: "Rect".to_string(),
+                        bindings: vec!["w".to_string(), "_".to_string()],
+                    },
+                    body: vec![],
+                },
+                MatchArm {
+                    pattern: Pattern::Wildcard,
+                    body: vec![],
+                },
+            ],
+            loc: Loc::new(1, 3),
+        },
+        Statement::Match {
+            value: Expression::Literal(i32!(5)),
+            arms: vec![range(0, 5, false), range(5, 9, true), range(10, 10, true)],
+            loc: Loc::new(1, 7),
+        },
+    ];
+
+    let ops = setup(body);
+    assert_eq!(expected, ops);
+}
+
+#[test]
+fn parse_impl_trait() {
+    let body = "
+trait Shape {
+    spellcard area(self: *Self) i32;
+}
+impl Square: Shape {
+    spellcard area(self: *Square) i32 {
+        s.side.area(2);
+    }
+}
+spellcard total<T: Shape + Debug>(a: *T) i32 {
+    offer a.area();
+}
+        ";
+    let this = FunctionArgs {
+        name: "self".to_string(),
+        annotation: "*Self".to_string(),
+    };
+    let expected = vec![
+        Statement::Trait {
+            name: "Shape".to_string(),
+            methods: vec![MethodSignature {
+                name: "area".to_string(),
+                args: vec![this.clone()],
+                return_type: Some("i32".to_string()),
+            }],
+            loc: Loc::new(1, 2),
+        },
+        Statement::Impl {
+            ty: "Square".to_string(),
+            trait_name: Some("Shape".to_string()),
+            methods: vec![Statement::SpellCard {
+                name: "area".to_string(),
+                generics: vec![],
+                args: vec![FunctionArgs {
+                    annotation: "*Square".to_string(),
+                    ..this.clone()
+                }],
+                return_type: Some("i32".to_string()),
+                body: vec![Statement::Expression(
+                    Expression::MethodCall {
+                        receiver: Box::new(Expression::Field {
+                            base: Box::new(Expression::Variable("s".to_string())),
+                            field: "side".to_string(),
+                        }),
+                        method: "area".to_string(),
+                        args: vec![Expression::Literal(i32!(2))],
+                    },
+                    Loc::new(9, 7),
+                )],
+                loc: Loc::new(5, 6),
+            }],
+            loc: Loc::new(1, 5),
+        },
+        Statement::SpellCard {
+            name: "total".to_string(),
+            generics: vec![GenericParam {
+                name: "T".to_string(),
+                bounds: vec!["Shape".to_string(), "Debug".to_string()],
+            }],
+            args: vec![FunctionArgs {
+                name: "a".to_string(),
+                annotation: "*T".to_string(),
+            }],
+            return_type: Some("i32".to_string()),
+            body: vec![Statement::Offer(
+                Some(Expression::MethodCall {
+                    receiver: Box::new(Expression::Variable("a".to_string())),
+                    method: "area".to_string(),
+                    args: vec![],
+                }),
+                Loc::new(5, 11),
+            )],
+            loc: Loc::new(1, 10),
+        },
+    ];
+
+    let ops = setup(body);
+    assert_eq!(expected, ops);
+}
+
+#[test]
+fn parse_logical_precedence() {
+    let body = "
+vow ok = a > 1 && b or c and d;
+        ";
+    let variable = |name: &str| Box::new(Expression::Variable(name.to_string()));
+    let expected = vec![
+        Statement::Vow {
+            name: "ok".to_owned(),
+            annotation: None,
+            loc: Loc::new(1, 2),
+        },
+        Statement::Assignment {
+            name: "ok".to_owned(),
+            value: Expression::Binary {
+                op: BinOp::Or,
+                left: Box::new(Expression::Binary {
+                    op: BinOp::And,
+                    left: Box::new(Expression::Binary {
+                        op: BinOp::Greater,
+                        left: variable("a"),
+                        right: Box::new(Expression::Literal(i32!(1))),
+                    }),
+                    right: variable("b"),
+                }),
+                right: Box::new(Expression::Binary {
+                    op: BinOp::And,
+                    left: variable("c"),
+                    right: variable("d"),
+                }),
+            },
+            loc: Loc::new(1, 2),
+        },
+    ];
+
+    let ops = setup(body);
+    assert_eq!(expected, ops);
+}
+
+#[test]
+fn parse_integer_overflow() {
+    let cases = [
+        (
+            "vow a = 1234567890123456789012345678901234567890123;",
+            "1234567890123456789012345678901234567890123",
+        ),
+        ("vow a = 18446744073709551616;", "18446744073709551616"),
+    ];
+    for (body, literal) in cases {
+        let chars = body.chars().collect::<Vec<_>>();
+        let mut parser = Parser::new(Lexer::new(&chars));
+        match parser.parse() {
+            Err(ParseError::IntegerOverflow { found, loc }) => {
+                assert_eq!(found, literal);
+                assert_eq!(loc, Loc::new(9, 1));
+            }
+            result => panic!("Should fail with integer overflow, found {:?}", result),
+        }
+    }
+}
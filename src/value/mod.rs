@@ -1,14 +1,81 @@
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Value {
+    I8(i8),
+    I16(i16),
     I32(i32),
+    I64(i64),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    Bool(bool),
+    Pointer(u64),
     String(String),
 }
 
 impl Value {
+    pub fn int(value: i128) -> Option<Self> {
+        if let Ok(int) = i32::try_from(value) {
+            return Some(Value::I32(int));
+        }
+        if let Ok(int) = i64::try_from(value) {
+            return Some(Value::I64(int));
+        }
+        u64::try_from(value).ok().map(Value::U64)
+    }
+
+    pub fn as_int(&self) -> Option<i128> {
+        Some(match self {
+            Value::I8(int) => *int as i128,
+            Value::I16(int) => *int as i128,
+            Value::I32(int) => *int as i128,
+            Value::I64(int) => *int as i128,
+            Value::U8(int) => *int as i128,
+            Value::U16(int) => *int as i128,
+            Value::U32(int) => *int as i128,
+            Value::U64(int) => *int as i128,
+            _ => return None,
+        })
+    }
+
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::F32(float) => Some(*float as f64),
+            Value::F64(float) => Some(*float),
+            _ => None,
+        }
+    }
+
+    pub fn as_raw(&self) -> Option<u64> {
+        match self {
+            Value::Bool(bool) => Some(*bool as u64),
+            Value::F32(float) => Some(float.to_bits() as u64),
+            Value::F64(float) => Some(float.to_bits()),
+            Value::Pointer(address) => Some(*address),
+            value => value.as_int().map(|int| int as u64),
+        }
+    }
+
     pub fn str(&self) -> String {
         match self {
-            Value::I32(int) => format!("{}", int),
+            Value::Bool(bool) => format!("{}", *bool as i32),
+            Value::F32(float) => format!("{:?}", float),
+            Value::F64(float) => format!("{:?}", float),
+            Value::Pointer(address) => format!("{}", address),
             Value::String(str) => format!("{}", str),
+            value => format!("{}", value.as_int().unwrap()),
+        }
+    }
+
+    // Value as written in the source, used by the repl and debugger
+    pub fn pretty(&self) -> String {
+        match self {
+            Value::Bool(bool) => bool.to_string(),
+            Value::Pointer(address) => format!("{:#x}", address),
+            Value::String(str) => format!("{:?}", str),
+            value => value.str(),
         }
     }
 }
@@ -16,7 +83,18 @@ impl Value {
 impl std::fmt::Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            Value::I8(int) => f.write_fmt(format_args!("I8 {}", int)),
+            Value::I16(int) => f.write_fmt(format_args!("I16 {}", int)),
             Value::I32(int) => f.write_fmt(format_args!("INT {}", int)),
+            Value::I64(int) => f.write_fmt(format_args!("I64 {}", int)),
+            Value::U8(int) => f.write_fmt(format_args!("U8 {}", int)),
+            Value::U16(int) => f.write_fmt(format_args!("U16 {}", int)),
+            Value::U32(int) => f.write_fmt(format_args!("U32 {}", int)),
+            Value::U64(int) => f.write_fmt(format_args!("U64 {}", int)),
+            Value::F32(float) => f.write_fmt(format_args!("F32 {:?}", float)),
+            Value::F64(float) => f.write_fmt(format_args!("F64 {:?}", float)),
+            Value::Bool(bool) => f.write_fmt(format_args!("BOOL {}", bool)),
+            Value::Pointer(address) => f.write_fmt(format_args!("POINTER {:#x}", address)),
             Value::String(str) => f.write_fmt(format_args!("STRING {}", str)),
         }
     }
@@ -29,6 +107,13 @@ macro_rules! i32 {
     };
 }
 
+#[macro_export]
+macro_rules! bool {
+    ($x:expr) => {
+        $crate::value::Value::Bool($x)
+    };
+}
+
 #[macro_export]
 macro_rules! string {
     ($x:expr) => {
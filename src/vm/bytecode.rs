@@ -0,0 +1,639 @@
+use crate::{ast::BinOp, commons::Loc, compiler::Type, op::Arg, value::Value};
+
+use super::{BytecodeError, CallTarget, Function, Import, Instruction, Program};
+
+pub const MAGIC: &[u8; 4] = b"REMI";
+pub const VERSION: u16 = 9;
+pub const EXTENSION: &str = "remib";
+
+// Layout of the file, every integer is little endian
+//
+//   magic      "REMI"
+//   version    u16
+//   data       u32 length, followed by the raw bytes of the data segment
+//   imports    u32 count, followed by (name, return type, arity, variadic)
+//   functions  u32 count, followed by (name, entry, frame size, arity)
+//   global     u32 frame size of the global statement
+//   code       u32 count, followed by the instruction
+//   lines      u32 count, followed by (pc, row, column) of each statement
+//
+// string is encoded as u32 length followed by utf-8 bytes and jump target is
+// the index of instruction inside code, literal is encoded as its type
+// followed by the string for cstr or the u64 content of the slot otherwise,
+// pointer type is encoded as its tag followed by the type it point to, array
+// type as its tag followed by the u32 length and the element type, struct
+// type as its tag followed by its name and the u32 count of (name, type) field
+// and enum type as its tag followed by its name and the u32 count of variant,
+// each being its name and the u32 count of the type of its payload
+
+const OP_ASSIGN: u8 = 0x01;
+const OP_UNARY_NOT: u8 = 0x02;
+const OP_BINOP: u8 = 0x03;
+const OP_PARAM_ASSIGN: u8 = 0x04;
+const OP_CALL: u8 = 0x05;
+const OP_RET: u8 = 0x06;
+const OP_RET_VOID: u8 = 0x07;
+const OP_JMP: u8 = 0x08;
+const OP_JMP_IF_NOT: u8 = 0x09;
+const OP_ADDR_OF: u8 = 0x0a;
+const OP_LOAD: u8 = 0x0b;
+const OP_STORE: u8 = 0x0c;
+const OP_ELEMENT: u8 = 0x0d;
+const OP_BOUNDS_CHECK: u8 = 0x0e;
+
+const ARG_LOCAL: u8 = 0x01;
+const ARG_LITERAL: u8 = 0x02;
+const ARG_DATA_OFFSET: u8 = 0x03;
+
+const TYPE_POINTER: u8 = 0xff;
+const TYPE_ARRAY: u8 = 0xfe;
+const TYPE_STRUCT: u8 = 0xfd;
+const TYPE_ENUM: u8 = 0xfc;
+
+const TARGET_FUNCTION: u8 = 0x01;
+const TARGET_IMPORT: u8 = 0x02;
+
+const BINOPS: [BinOp; 7] = [
+    BinOp::Add,
+    BinOp::Sub,
+    BinOp::Mul,
+    BinOp::Div,
+    BinOp::Equal,
+    BinOp::Greater,
+    BinOp::Less,
+];
+
+const TYPES: [Type; 13] = [
+    Type::Void,
+    Type::I8,
+    Type::I16,
+    Type::I32,
+    Type::I64,
+    Type::U8,
+    Type::U16,
+    Type::U32,
+    Type::U64,
+    Type::Bool,
+    Type::CStr,
+    Type::F32,
+    Type::F64,
+];
+
+struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: usize) {
+        self.buf.extend_from_slice(&(value as u32).to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn ty(&mut self, ty: &Type) {
+        match ty {
+            Type::Pointer(pointee) => {
+                self.u8(TYPE_POINTER);
+                self.ty(pointee);
+            }
+            Type::Array(element, length) => {
+                self.u8(TYPE_ARRAY);
+                self.u32(*length);
+                self.ty(element);
+            }
+            Type::Struct { name, fields } => {
+                self.u8(TYPE_STRUCT);
+                self.str(name);
+                self.u32(fields.len());
+                for (name, ty) in fields {
+                    self.str(name);
+                    self.ty(ty);
+                }
+            }
+            Type::Enum { name, variants } => {
+                self.u8(TYPE_ENUM);
+                self.str(name);
+                self.u32(variants.len());
+                for (name, fields) in variants {
+                    self.str(name);
+                    self.u32(fields.len());
+                    fields.iter().for_each(|ty| self.ty(ty));
+                }
+            }
+            ty => self.u8(TYPES.iter().position(|t| t == ty).unwrap() as u8),
+        }
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.u32(bytes.len());
+        self.buf.extend_from_slice(bytes);
+    }
+
+    fn str(&mut self, str: &str) {
+        self.bytes(str.as_bytes());
+    }
+
+    fn arg(&mut self, arg: &Arg) {
+        match arg {
+            Arg::Local(offset) => {
+                self.u8(ARG_LOCAL);
+                self.u32(*offset);
+            }
+            Arg::Literal(value) => {
+                self.u8(ARG_LITERAL);
+                self.ty(&Type::of(value));
+                match value {
+                    Value::String(str) => self.str(str),
+                    value => self.u64(value.as_raw().unwrap()),
+                }
+            }
+            Arg::DataOffset(offset) => {
+                self.u8(ARG_DATA_OFFSET);
+                self.u32(*offset);
+            }
+        }
+    }
+
+    fn instruction(&mut self, instruction: &Instruction) {
+        match instruction {
+            Instruction::Assign { offset, arg } => {
+                self.u8(OP_ASSIGN);
+                self.u32(*offset);
+                self.arg(arg);
+            }
+            Instruction::UnaryNot { offset, arg } => {
+                self.u8(OP_UNARY_NOT);
+                self.u32(*offset);
+                self.arg(arg);
+            }
+            Instruction::BinOp {
+                binop,
+                ty,
+                offset,
+                lhs,
+                rhs,
+            } => {
+                self.u8(OP_BINOP);
+                self.u8(BINOPS.iter().position(|b| b == binop).unwrap() as u8);
+                self.ty(ty);
+                self.u32(*offset);
+                self.arg(lhs);
+                self.arg(rhs);
+            }
+            Instruction::ParamAssign { offset, arg } => {
+                self.u8(OP_PARAM_ASSIGN);
+                self.u32(*offset);
+                self.arg(arg);
+            }
+            Instruction::Call {
+                result,
+                target,
+                args,
+                types,
+            } => {
+                self.u8(OP_CALL);
+                self.u32(*result);
+                match target {
+                    CallTarget::Function(idx) => {
+                        self.u8(TARGET_FUNCTION);
+                        self.u32(*idx);
+                    }
+                    CallTarget::Import(idx) => {
+                        self.u8(TARGET_IMPORT);
+                        self.u32(*idx);
+                    }
+                }
+                self.u32(args.len());
+                for arg in args {
+                    self.arg(arg);
+                }
+                self.u32(types.len());
+                for ty in types {
+                    self.ty(ty);
+                }
+            }
+            Instruction::AddrOf { offset, local } => {
+                self.u8(OP_ADDR_OF);
+                self.u32(*offset);
+                self.u32(*local);
+            }
+            Instruction::Load { offset, ty, ptr } => {
+                self.u8(OP_LOAD);
+                self.u32(*offset);
+                self.ty(ty);
+                self.arg(ptr);
+            }
+            Instruction::Store { ty, ptr, arg } => {
+                self.u8(OP_STORE);
+                self.ty(ty);
+                self.arg(ptr);
+                self.arg(arg);
+            }
+            Instruction::Element {
+                offset,
+                base,
+                index,
+                size,
+            } => {
+                self.u8(OP_ELEMENT);
+                self.u32(*offset);
+                self.arg(base);
+                self.arg(index);
+                self.u32(*size);
+            }
+            Instruction::BoundsCheck { index, length } => {
+                self.u8(OP_BOUNDS_CHECK);
+                self.arg(index);
+                self.u32(*length);
+            }
+            Instruction::Ret(Some(arg)) => {
+                self.u8(OP_RET);
+                self.arg(arg);
+            }
+            Instruction::Ret(None) => self.u8(OP_RET_VOID),
+            Instruction::Jmp(target) => {
+                self.u8(OP_JMP);
+                self.u32(*target);
+            }
+            Instruction::JmpIfNot { target, arg } => {
+                self.u8(OP_JMP_IF_NOT);
+                self.u32(*target);
+                self.arg(arg);
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], BytecodeError> {
+        if self.buf.len() < n {
+            return Err(BytecodeError::UnexpectedEof);
+        }
+        let (chop, rest) = self.buf.split_at(n);
+        self.buf = rest;
+        Ok(chop)
+    }
+
+    fn u8(&mut self) -> Result<u8, BytecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, BytecodeError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<usize, BytecodeError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()) as usize)
+    }
+
+    fn u64(&mut self) -> Result<u64, BytecodeError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn ty(&mut self) -> Result<Type, BytecodeError> {
+        let tag = self.u8()?;
+        match tag {
+            TYPE_POINTER => return Ok(Type::Pointer(Box::new(self.ty()?))),
+            TYPE_ARRAY => {
+                let length = self.u32()?;
+                return Ok(Type::Array(Box::new(self.ty()?), length));
+            }
+            TYPE_STRUCT => {
+                let name = self.str()?;
+                let fields = (0..self.u32()?)
+                    .map(|_| Ok((self.str()?, self.ty()?)))
+                    .collect::<Result<_, _>>()?;
+                return Ok(Type::Struct { name, fields });
+            }
+            TYPE_ENUM => {
+                let name = self.str()?;
+                let variants = (0..self.u32()?)
+                    .map(|_| {
+                        let name = self.str()?;
+                        let fields = (0..self.u32()?)
+                            .map(|_| self.ty())
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok((name, fields))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                return Ok(Type::Enum { name, variants });
+            }
+            _ => {}
+        }
+        TYPES
+            .get(tag as usize)
+            .cloned()
+            .ok_or(BytecodeError::InvalidTag { kind: "type", tag })
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], BytecodeError> {
+        let len = self.u32()?;
+        self.take(len)
+    }
+
+    fn str(&mut self) -> Result<String, BytecodeError> {
+        String::from_utf8(self.bytes()?.to_vec()).map_err(|_| BytecodeError::InvalidString)
+    }
+
+    fn arg(&mut self) -> Result<Arg, BytecodeError> {
+        Ok(match self.u8()? {
+            ARG_LOCAL => Arg::Local(self.u32()?),
+            ARG_LITERAL => Arg::Literal(match self.ty()? {
+                Type::CStr => Value::String(self.str()?),
+                ty => ty.value(self.u64()?).ok_or(BytecodeError::InvalidTag {
+                    kind: "value",
+                    tag: 0,
+                })?,
+            }),
+            ARG_DATA_OFFSET => Arg::DataOffset(self.u32()?),
+            tag => return Err(BytecodeError::InvalidTag { kind: "arg", tag }),
+        })
+    }
+
+    fn instruction(&mut self) -> Result<Instruction, BytecodeError> {
+        Ok(match self.u8()? {
+            OP_ASSIGN => Instruction::Assign {
+                offset: self.u32()?,
+                arg: self.arg()?,
+            },
+            OP_UNARY_NOT => Instruction::UnaryNot {
+                offset: self.u32()?,
+                arg: self.arg()?,
+            },
+            OP_BINOP => {
+                let tag = self.u8()?;
+                Instruction::BinOp {
+                    binop: BINOPS
+                        .get(tag as usize)
+                        .cloned()
+                        .ok_or(BytecodeError::InvalidTag { kind: "binop", tag })?,
+                    ty: self.ty()?,
+                    offset: self.u32()?,
+                    lhs: self.arg()?,
+                    rhs: self.arg()?,
+                }
+            }
+            OP_PARAM_ASSIGN => Instruction::ParamAssign {
+                offset: self.u32()?,
+                arg: self.arg()?,
+            },
+            OP_CALL => {
+                let result = self.u32()?;
+                let target = match self.u8()? {
+                    TARGET_FUNCTION => CallTarget::Function(self.u32()?),
+                    TARGET_IMPORT => CallTarget::Import(self.u32()?),
+                    tag => return Err(BytecodeError::InvalidTag { kind: "call", tag }),
+                };
+                let count = self.u32()?;
+                let mut args = vec![];
+                for _ in 0..count {
+                    args.push(self.arg()?);
+                }
+                let count = self.u32()?;
+                let mut types = vec![];
+                for _ in 0..count {
+                    types.push(self.ty()?);
+                }
+                Instruction::Call {
+                    result,
+                    target,
+                    args,
+                    types,
+                }
+            }
+            OP_ADDR_OF => Instruction::AddrOf {
+                offset: self.u32()?,
+                local: self.u32()?,
+            },
+            OP_LOAD => Instruction::Load {
+                offset: self.u32()?,
+                ty: self.ty()?,
+                ptr: self.arg()?,
+            },
+            OP_STORE => Instruction::Store {
+                ty: self.ty()?,
+                ptr: self.arg()?,
+                arg: self.arg()?,
+            },
+            OP_ELEMENT => Instruction::Element {
+                offset: self.u32()?,
+                base: self.arg()?,
+                index: self.arg()?,
+                size: self.u32()?,
+            },
+            OP_BOUNDS_CHECK => Instruction::BoundsCheck {
+                index: self.arg()?,
+                length: self.u32()?,
+            },
+            OP_RET => Instruction::Ret(Some(self.arg()?)),
+            OP_RET_VOID => Instruction::Ret(None),
+            OP_JMP => Instruction::Jmp(self.u32()?),
+            OP_JMP_IF_NOT => Instruction::JmpIfNot {
+                target: self.u32()?,
+                arg: self.arg()?,
+            },
+            tag => {
+                return Err(BytecodeError::InvalidTag {
+                    kind: "instruction",
+                    tag,
+                });
+            }
+        })
+    }
+}
+
+impl Program {
+    pub fn is_bytecode(bytes: &[u8]) -> bool {
+        bytes.starts_with(MAGIC)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut w = Writer { buf: vec![] };
+        w.buf.extend_from_slice(MAGIC);
+        w.u16(VERSION);
+        w.bytes(&self.data);
+
+        w.u32(self.imports.len());
+        for import in self.imports.iter() {
+            w.str(&import.name);
+            w.ty(&import.return_type);
+            w.u32(import.arity);
+            w.u8(import.variadic as u8);
+        }
+
+        w.u32(self.functions.len());
+        for function in self.functions.iter() {
+            w.str(&function.name);
+            w.u32(function.entry);
+            w.u32(function.frame_size);
+            w.u32(function.arity);
+        }
+
+        w.u32(self.global_frame_size);
+        w.u32(self.code.len());
+        for instruction in self.code.iter() {
+            w.instruction(instruction);
+        }
+
+        w.u32(self.lines.len());
+        for (pc, loc) in self.lines.iter() {
+            w.u32(*pc);
+            w.u32(loc.row);
+            w.u32(loc.column);
+        }
+        w.buf
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, BytecodeError> {
+        let mut r = Reader { buf: bytes };
+        if r.take(MAGIC.len())
+            .map_err(|_| BytecodeError::InvalidMagic)?
+            != MAGIC
+        {
+            return Err(BytecodeError::InvalidMagic);
+        }
+        let version = r.u16()?;
+        if version != VERSION {
+            return Err(BytecodeError::UnsupportedVersion { found: version });
+        }
+
+        let data = r.bytes()?.to_vec();
+
+        let mut imports = vec![];
+        for _ in 0..r.u32()? {
+            imports.push(Import {
+                name: r.str()?,
+                return_type: r.ty()?,
+                arity: r.u32()?,
+                variadic: r.u8()? != 0,
+            });
+        }
+
+        let mut functions = vec![];
+        for _ in 0..r.u32()? {
+            functions.push(Function {
+                name: r.str()?,
+                entry: r.u32()?,
+                frame_size: r.u32()?,
+                arity: r.u32()?,
+            });
+        }
+
+        let global_frame_size = r.u32()?;
+        let mut code = vec![];
+        for _ in 0..r.u32()? {
+            code.push(r.instruction()?);
+        }
+
+        let mut lines = vec![];
+        for _ in 0..r.u32()? {
+            let pc = r.u32()?;
+            let row = r.u32()?;
+            let column = r.u32()?;
+            lines.push((pc, Loc::new(column, row)));
+        }
+
+        let program = Program {
+            data,
+            imports,
+            functions,
+            global_frame_size,
+            code,
+            lines,
+        };
+        program.verify()?;
+        Ok(program)
+    }
+
+    fn verify(&self) -> Result<(), BytecodeError> {
+        let check = |kind, index: usize, len: usize| {
+            if index < len {
+                Ok(())
+            } else {
+                Err(BytecodeError::OutOfBound { kind, index })
+            }
+        };
+        for function in self.functions.iter() {
+            check("entry", function.entry, self.code.len())?;
+        }
+        let mut frames = self
+            .functions
+            .iter()
+            .map(|function| (function.entry, function.frame_size))
+            .collect::<Vec<_>>();
+        frames.sort();
+        for (pc, instruction) in self.code.iter().enumerate() {
+            let frame_size = match frames.partition_point(|(entry, _)| *entry <= pc) {
+                0 => self.global_frame_size,
+                i => frames[i - 1].1,
+            };
+            for local in locals(instruction) {
+                check("local", local, frame_size)?;
+            }
+            match instruction {
+                Instruction::Call {
+                    target: CallTarget::Function(idx),
+                    ..
+                } => check("function", *idx, self.functions.len())?,
+                Instruction::Call {
+                    target: CallTarget::Import(idx),
+                    ..
+                } => check("import", *idx, self.imports.len())?,
+                Instruction::Jmp(target) | Instruction::JmpIfNot { target, .. } => {
+                    check("jump", *target, self.code.len())?
+                }
+                _ => {}
+            }
+        }
+        for (pc, _) in self.lines.iter() {
+            check("line", *pc, self.code.len())?;
+        }
+        Ok(())
+    }
+}
+
+fn locals(instruction: &Instruction) -> Vec<usize> {
+    let (offsets, args) = match instruction {
+        Instruction::Assign { offset, arg } | Instruction::UnaryNot { offset, arg } => {
+            (vec![*offset], vec![arg])
+        }
+        Instruction::BinOp {
+            offset, lhs, rhs, ..
+        } => (vec![*offset], vec![lhs, rhs]),
+        Instruction::ParamAssign { arg, .. } => (vec![], vec![arg]),
+        Instruction::Call { result, args, .. } => (vec![*result], args.iter().collect()),
+        Instruction::AddrOf { offset, local } => (vec![*offset, *local], vec![]),
+        Instruction::Load { offset, ptr, .. } => (vec![*offset], vec![ptr]),
+        Instruction::Store { ptr, arg, .. } => (vec![], vec![ptr, arg]),
+        Instruction::Element {
+            offset,
+            base,
+            index,
+            ..
+        } => (vec![*offset], vec![base, index]),
+        Instruction::BoundsCheck { index, .. } => (vec![], vec![index]),
+        Instruction::Ret(Some(arg)) | Instruction::JmpIfNot { arg, .. } => (vec![], vec![arg]),
+        Instruction::Ret(None) | Instruction::Jmp(_) => (vec![], vec![]),
+    };
+    args.into_iter()
+        .filter_map(|arg| match arg {
+            Arg::Local(offset) => Some(*offset),
+            _ => None,
+        })
+        .chain(offsets)
+        .collect()
+}
@@ -0,0 +1,133 @@
+use std::collections::BTreeSet;
+
+use crate::codegen::dump_args;
+
+use super::{CallTarget, Instruction, Program, VERSION};
+
+impl Program {
+    pub fn disassemble(&self) -> String {
+        let mut body: Vec<String> = vec![];
+        body.push(format!("Remi Bytecode v{}\n", VERSION));
+        body.push(format!("Imports: {}", self.imports.len()));
+        body.push(format!("Functions: {}", self.functions.len()));
+        body.push(format!("Global frame: {:#04x}", self.global_frame_size));
+        body.push(format!("Code: {}", self.code.len()));
+
+        body.push("\nData:".to_owned());
+        for (i, data) in self.data.chunks(8).enumerate() {
+            let mut buf = format!("    {:#06x}: ", i * 8);
+            for byte in data {
+                buf.push_str(&format!("{:#04x} ", byte));
+            }
+            body.push(buf);
+        }
+
+        body.push("\nImports:".to_owned());
+        for (i, import) in self.imports.iter().enumerate() {
+            body.push(format!("    {:#04x}: {}", i, import.name));
+        }
+
+        let targets = self
+            .code
+            .iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::Jmp(target) | Instruction::JmpIfNot { target, .. } => Some(*target),
+                _ => None,
+            })
+            .collect::<BTreeSet<_>>();
+
+        body.push("\nText:".to_owned());
+        let mut functions = self.functions.iter().collect::<Vec<_>>();
+        functions.sort_by_key(|f| f.entry);
+        if functions.first().is_none_or(|f| f.entry > 0) {
+            body.push("    __global():".to_owned());
+        }
+        for (pc, instruction) in self.code.iter().enumerate() {
+            for function in functions.iter().filter(|f| f.entry == pc) {
+                body.push(format!(
+                    "    {}(): arity {}, frame {:#04x}",
+                    function.name, function.arity, function.frame_size
+                ));
+            }
+            if targets.contains(&pc) {
+                body.push(format!("    .L{:#06x}:", pc));
+            }
+            if let Some(loc) = self.line(pc) {
+                body.push(format!("        ; line {}", loc.row));
+            }
+            body.push(format!("        {:#06x}: {}", pc, self.dump(instruction)));
+        }
+        body.join("\n")
+    }
+
+    pub(super) fn dump(&self, instruction: &Instruction) -> String {
+        match instruction {
+            Instruction::Assign { offset, arg } => {
+                format!("EternalAssign({:#04x}, {})", offset, dump_args(arg))
+            }
+            Instruction::UnaryNot { offset, arg } => {
+                format!("UnaryNot({:#04x}, {})", offset, dump_args(arg))
+            }
+            Instruction::BinOp {
+                binop,
+                ty,
+                offset,
+                lhs,
+                rhs,
+            } => format!(
+                "BinOp<{}>({:#04x} {} {} {})",
+                ty,
+                offset,
+                dump_args(lhs),
+                binop,
+                dump_args(rhs)
+            ),
+            Instruction::ParamAssign { offset, arg } => {
+                format!("ParamAssign({:#04x}, {})", offset, dump_args(arg))
+            }
+            Instruction::Call {
+                result,
+                target,
+                args,
+                ..
+            } => {
+                let name = match target {
+                    CallTarget::Function(idx) => self.functions[*idx].name.clone(),
+                    CallTarget::Import(idx) => format!("{} (import)", self.imports[*idx].name),
+                };
+                let args = args.iter().map(dump_args).collect::<Vec<_>>().join(", ");
+                format!("Call({:#04x}, {}, [{}])", result, name, args)
+            }
+            Instruction::AddrOf { offset, local } => {
+                format!("AddrOf({:#04x}, Local({:#04x}))", offset, local)
+            }
+            Instruction::Load { offset, ty, ptr } => {
+                format!("Load<{}>({:#04x}, {})", ty, offset, dump_args(ptr))
+            }
+            Instruction::Store { ty, ptr, arg } => {
+                format!("Store<{}>({}, {})", ty, dump_args(ptr), dump_args(arg))
+            }
+            Instruction::Element {
+                offset,
+                base,
+                index,
+                size,
+            } => format!(
+                "Element({:#04x}, {} + {} * {})",
+                offset,
+                dump_args(base),
+                dump_args(index),
+                size
+            ),
+            Instruction::BoundsCheck { index, length } => {
+                format!("BoundsCheck({} < {})", dump_args(index), length)
+            }
+            Instruction::Ret(Some(arg)) => format!("Ret({})", dump_args(arg)),
+            Instruction::Ret(None) => "Ret(void)".to_owned(),
+            Instruction::Jmp(target) => format!("Jmp(.L{:#06x})", target),
+            Instruction::JmpIfNot { target, arg } => {
+                format!("Jne(.L{:#06x}, {})", target, dump_args(arg))
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::error::Error;
+
+#[derive(Debug)]
+pub enum VMError {
+    UnknownFunction { name: String },
+    UnknownLabel { name: String },
+    InvalidAddress { address: u64 },
+    InvalidOperation { message: String },
+    DivisionByZero,
+    IndexOutOfBounds { index: i64, length: usize },
+    StackOverflow,
+    OutOfFuel { budget: u64 },
+    CallDepthExceeded { limit: usize },
+    MemoryLimitExceeded { requested: usize, limit: usize },
+    ImportNotAllowed { name: String },
+    HostError { name: String, message: String },
+}
+
+impl Error for VMError {}
+
+impl std::fmt::Display for VMError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VMError::UnknownFunction { name } => {
+                f.write_fmt(format_args!("Undefined function symbol of {}", name))
+            }
+            VMError::UnknownLabel { name } => {
+                f.write_fmt(format_args!("Undefined label of {}", name))
+            }
+            VMError::InvalidAddress { address } => {
+                f.write_fmt(format_args!("Invalid memory access at {:#x}", address))
+            }
+            VMError::InvalidOperation { message } => f.write_str(message),
+            VMError::DivisionByZero => f.write_str("Division by zero"),
+            VMError::IndexOutOfBounds { index, length } => f.write_fmt(format_args!(
+                "Index {} is out of bounds for array of length {}",
+                index, length
+            )),
+            VMError::StackOverflow => f.write_str("Stack overflow"),
+            VMError::OutOfFuel { budget } => {
+                f.write_fmt(format_args!("Instruction budget of {} exhausted", budget))
+            }
+            VMError::CallDepthExceeded { limit } => {
+                f.write_fmt(format_args!("Call depth exceeded the limit of {}", limit))
+            }
+            VMError::MemoryLimitExceeded { requested, limit } => f.write_fmt(format_args!(
+                "Memory of {} bytes exceeded the limit of {} bytes",
+                requested, limit
+            )),
+            VMError::ImportNotAllowed { name } => {
+                f.write_fmt(format_args!("Calling {} is not allowed", name))
+            }
+            VMError::HostError { name, message } => {
+                f.write_fmt(format_args!("Host function {} failed: {}", name, message))
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum BytecodeError {
+    InvalidMagic,
+    UnsupportedVersion { found: u16 },
+    UnexpectedEof,
+    InvalidTag { kind: &'static str, tag: u8 },
+    InvalidString,
+    OutOfBound { kind: &'static str, index: usize },
+}
+
+impl Error for BytecodeError {}
+
+impl std::fmt::Display for BytecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BytecodeError::InvalidMagic => f.write_str("Not a remi bytecode file"),
+            BytecodeError::UnsupportedVersion { found } => f.write_fmt(format_args!(
+                "Unsupported bytecode version {}, expected {}",
+                found,
+                super::VERSION
+            )),
+            BytecodeError::UnexpectedEof => f.write_str("Unexpected end of bytecode file"),
+            BytecodeError::InvalidTag { kind, tag } => {
+                f.write_fmt(format_args!("Invalid {} tag {:#04x}", kind, tag))
+            }
+            BytecodeError::InvalidString => f.write_str("Invalid utf-8 string in bytecode"),
+            BytecodeError::OutOfBound { kind, index } => {
+                f.write_fmt(format_args!("The {} index {} is out of bound", kind, index))
+            }
+        }
+    }
+}
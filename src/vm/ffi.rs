@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+
+use libloading::Library;
+
+use crate::compiler::Type;
+
+use super::VMError;
+
+pub const MAX_FFI_ARGS: usize = 12;
+pub const MAX_FFI_FLOAT_ARGS: usize = 8;
+
+pub struct Ffi {
+    libraries: Vec<Library>,
+    symbols: HashMap<String, usize>,
+}
+
+impl Ffi {
+    pub fn new() -> Self {
+        #[cfg(unix)]
+        let this: Library = libloading::os::unix::Library::this().into();
+        #[cfg(windows)]
+        let this: Library = libloading::os::windows::Library::this()
+            .expect("Failed to open the current process")
+            .into();
+
+        Self {
+            libraries: vec![this],
+            symbols: HashMap::new(),
+        }
+    }
+
+    pub fn load(&mut self, path: &str) -> Result<(), VMError> {
+        // SAFETY: Loading a library run its initializer, the user explicitly
+        // ask for this library so we trust it
+        let library = unsafe { Library::new(path) }.map_err(|err| VMError::InvalidOperation {
+            message: format!("Failed to load shared library {}: {}", path, err),
+        })?;
+        // NOTE : User library take priority over the process symbol
+        self.libraries.insert(self.libraries.len() - 1, library);
+        Ok(())
+    }
+
+    pub fn resolve(&mut self, name: &str) -> Option<usize> {
+        if let Some(symbol) = self.symbols.get(name) {
+            return Some(*symbol);
+        }
+
+        let symbol = self.libraries.iter().find_map(|library| {
+            // SAFETY: The symbol is only used as an address until it is called
+            unsafe { library.get::<*const ()>(name.as_bytes()) }
+                .ok()
+                .map(|symbol| *symbol as usize)
+        })?;
+        self.symbols.insert(name.to_owned(), symbol);
+        Some(symbol)
+    }
+
+    /// # Safety
+    /// `symbol` must point into a function matching `types` and `return_type`
+    pub unsafe fn call(
+        &self,
+        symbol: usize,
+        args: &[u64],
+        types: &[Type],
+        return_type: &Type,
+        variadic: bool,
+    ) -> Result<u64, VMError> {
+        let is_float = |i: usize| types.get(i).is_some_and(Type::is_float);
+        // NOTE : SysV variadic callee read the count of vector register from al
+        if (variadic && cfg!(not(windows)))
+            || return_type.is_float()
+            || (0..args.len()).any(is_float)
+        {
+            // SAFETY: Guaranteed by the caller
+            return unsafe { self.call_float(symbol, args, types, return_type, variadic) };
+        }
+        // SAFETY: Guaranteed by the caller
+        unsafe { self.call_integer(symbol, args) }
+    }
+
+    unsafe fn call_integer(&self, symbol: usize, args: &[u64]) -> Result<u64, VMError> {
+        type F0 = extern "C" fn() -> u64;
+        type F1 = extern "C" fn(u64) -> u64;
+        type F2 = extern "C" fn(u64, u64) -> u64;
+        type F3 = extern "C" fn(u64, u64, u64) -> u64;
+        type F4 = extern "C" fn(u64, u64, u64, u64) -> u64;
+        type F5 = extern "C" fn(u64, u64, u64, u64, u64) -> u64;
+        type F6 = extern "C" fn(u64, u64, u64, u64, u64, u64) -> u64;
+        type F12 = extern "C" fn(u64, u64, u64, u64, u64, u64, u64, u64, u64, u64, u64, u64) -> u64;
+
+        let a = |i: usize| args.get(i).copied().unwrap_or(0);
+        // SAFETY: Guaranteed by the caller, the extra argument for the 12
+        // argument version are ignored by the callee since the caller clean
+        // up the stack on both SysV and Win64
+        let value = unsafe {
+            let ptr = symbol as *const ();
+            match args.len() {
+                0 => std::mem::transmute::<*const (), F0>(ptr)(),
+                1 => std::mem::transmute::<*const (), F1>(ptr)(a(0)),
+                2 => std::mem::transmute::<*const (), F2>(ptr)(a(0), a(1)),
+                3 => std::mem::transmute::<*const (), F3>(ptr)(a(0), a(1), a(2)),
+                4 => std::mem::transmute::<*const (), F4>(ptr)(a(0), a(1), a(2), a(3)),
+                5 => std::mem::transmute::<*const (), F5>(ptr)(a(0), a(1), a(2), a(3), a(4)),
+                6 => std::mem::transmute::<*const (), F6>(ptr)(a(0), a(1), a(2), a(3), a(4), a(5)),
+                n if n <= MAX_FFI_ARGS => std::mem::transmute::<*const (), F12>(ptr)(
+                    a(0),
+                    a(1),
+                    a(2),
+                    a(3),
+                    a(4),
+                    a(5),
+                    a(6),
+                    a(7),
+                    a(8),
+                    a(9),
+                    a(10),
+                    a(11),
+                ),
+                n => {
+                    return Err(VMError::InvalidOperation {
+                        message: format!(
+                            "Foreign call only support up to {} argument, found {}",
+                            MAX_FFI_ARGS, n
+                        ),
+                    });
+                }
+            }
+        };
+        Ok(value)
+    }
+
+    #[cfg(windows)]
+    unsafe fn call_float(
+        &self,
+        _symbol: usize,
+        _args: &[u64],
+        _types: &[Type],
+        _return_type: &Type,
+        _variadic: bool,
+    ) -> Result<u64, VMError> {
+        // NOTE : Win64 assign register by position, so float can not be split out like SysV
+        Err(VMError::InvalidOperation {
+            message: "Foreign call with float is not supported on Windows".to_owned(),
+        })
+    }
+
+    #[cfg(not(windows))]
+    unsafe fn call_float(
+        &self,
+        symbol: usize,
+        args: &[u64],
+        types: &[Type],
+        return_type: &Type,
+        variadic: bool,
+    ) -> Result<u64, VMError> {
+        let (floats, ints): (Vec<_>, Vec<_>) = args
+            .iter()
+            .enumerate()
+            .partition(|(i, _)| types.get(*i).is_some_and(Type::is_float));
+        if ints.len() > MAX_FFI_ARGS || floats.len() > MAX_FFI_FLOAT_ARGS {
+            return Err(VMError::InvalidOperation {
+                message: format!(
+                    "Foreign call only support up to {} integer and {} float argument, found {} and {}",
+                    MAX_FFI_ARGS,
+                    MAX_FFI_FLOAT_ARGS,
+                    ints.len(),
+                    floats.len()
+                ),
+            });
+        }
+
+        let mut a = [0; MAX_FFI_ARGS];
+        for (slot, (_, value)) in a.iter_mut().zip(ints) {
+            *slot = *value;
+        }
+        // NOTE : The callee read f32 from the low bits, where the slot hold it
+        let mut f = [0.0; MAX_FFI_FLOAT_ARGS];
+        for (slot, (_, value)) in f.iter_mut().zip(floats) {
+            *slot = f64::from_bits(*value);
+        }
+
+        let ptr = symbol as *const ();
+        // SAFETY: Guaranteed by the caller
+        let value = unsafe {
+            match (variadic, return_type.is_float()) {
+                (false, false) => invoke::<u64>(ptr, a, f),
+                (false, true) => invoke::<f64>(ptr, a, f).to_bits(),
+                (true, false) => invoke_variadic::<u64>(ptr, a, f),
+                (true, true) => invoke_variadic::<f64>(ptr, a, f).to_bits(),
+            }
+        };
+        Ok(value)
+    }
+}
+
+// NOTE : SysV and AArch64 assign each register class on its own, so the extra are ignored
+#[cfg(not(windows))]
+unsafe fn invoke<R>(ptr: *const (), a: [u64; MAX_FFI_ARGS], f: [f64; MAX_FFI_FLOAT_ARGS]) -> R {
+    type F<R> = extern "C" fn(
+        u64,
+        u64,
+        u64,
+        u64,
+        u64,
+        u64,
+        u64,
+        u64,
+        u64,
+        u64,
+        u64,
+        u64,
+        f64,
+        f64,
+        f64,
+        f64,
+        f64,
+        f64,
+        f64,
+        f64,
+    ) -> R;
+    // SAFETY: Guaranteed by the caller
+    unsafe {
+        std::mem::transmute::<*const (), F<R>>(ptr)(
+            a[0], a[1], a[2], a[3], a[4], a[5], a[6], a[7], a[8], a[9], a[10], a[11], f[0], f[1],
+            f[2], f[3], f[4], f[5], f[6], f[7],
+        )
+    }
+}
+
+// NOTE : Calling through a C variadic type make the compiler set al, the
+// declared parameter land in the same register since SysV and AArch64 Linux
+// pass variadic argument like the other
+#[cfg(not(windows))]
+unsafe fn invoke_variadic<R>(
+    ptr: *const (),
+    a: [u64; MAX_FFI_ARGS],
+    f: [f64; MAX_FFI_FLOAT_ARGS],
+) -> R {
+    type F<R> = unsafe extern "C" fn(u64, ...) -> R;
+    // SAFETY: Guaranteed by the caller
+    unsafe {
+        std::mem::transmute::<*const (), F<R>>(ptr)(
+            a[0], a[1], a[2], a[3], a[4], a[5], a[6], a[7], a[8], a[9], a[10], a[11], f[0], f[1],
+            f[2], f[3], f[4], f[5], f[6], f[7],
+        )
+    }
+}
+
+impl Default for Ffi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
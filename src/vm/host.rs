@@ -0,0 +1,252 @@
+use std::{collections::HashMap, io::Write};
+
+use super::{Memory, VMError};
+
+const MAX_FORMAT_WIDTH: usize = 4096;
+
+pub type HostFunction = Box<dyn Fn(&mut Memory, &[u64]) -> Result<u64, VMError>>;
+
+pub fn builtins() -> HashMap<String, HostFunction> {
+    let mut host: HashMap<String, HostFunction> = HashMap::new();
+    host.insert("printf".to_owned(), Box::new(host_printf));
+    host.insert("puts".to_owned(), Box::new(host_puts));
+    host.insert("putchar".to_owned(), Box::new(host_putchar));
+    host
+}
+
+fn write_stdout(bytes: &[u8]) -> Result<(), VMError> {
+    let mut stdout = std::io::stdout();
+    stdout
+        .write_all(bytes)
+        .and_then(|_| stdout.flush())
+        .map_err(|err| VMError::InvalidOperation {
+            message: format!("Failed to write into stdout: {}", err),
+        })
+}
+
+fn host_printf(memory: &mut Memory, args: &[u64]) -> Result<u64, VMError> {
+    let fmt = args.first().ok_or(VMError::InvalidOperation {
+        message: "printf expect a format string".to_owned(),
+    })?;
+    let out = format(memory, *fmt, &args[1..])?;
+    write_stdout(&out)?;
+    Ok(out.len() as u64)
+}
+
+fn host_puts(memory: &mut Memory, args: &[u64]) -> Result<u64, VMError> {
+    let str = args.first().ok_or(VMError::InvalidOperation {
+        message: "puts expect a string".to_owned(),
+    })?;
+    let mut out = memory.read_cstr(*str)?.to_vec();
+    out.push(b'\n');
+    write_stdout(&out)?;
+    Ok(out.len() as u64)
+}
+
+fn host_putchar(_memory: &mut Memory, args: &[u64]) -> Result<u64, VMError> {
+    let char = args.first().ok_or(VMError::InvalidOperation {
+        message: "putchar expect a character".to_owned(),
+    })?;
+    write_stdout(&[*char as u8])?;
+    Ok(*char as u8 as u64)
+}
+
+fn format_number(fmt: &[u8], i: &mut usize) -> Result<usize, VMError> {
+    let mut value: usize = 0;
+    while *i < fmt.len() && fmt[*i].is_ascii_digit() {
+        value = value
+            .checked_mul(10)
+            .and_then(|value| value.checked_add((fmt[*i] - b'0') as usize))
+            .filter(|value| *value <= MAX_FORMAT_WIDTH)
+            .ok_or(VMError::InvalidOperation {
+                message: format!(
+                    "printf width and precision can not exceed {}",
+                    MAX_FORMAT_WIDTH
+                ),
+            })?;
+        *i += 1;
+    }
+    Ok(value)
+}
+
+// NOTE : Rust print the exponent as `e5`, C always sign it and use at least two digit
+fn format_exponent(value: f64, precision: usize) -> (String, i32) {
+    let str = format!("{:.*e}", precision, value);
+    let (mantissa, exponent) = str.split_once('e').expect("Exponent should be present");
+    let exponent = exponent
+        .parse::<i32>()
+        .expect("Exponent should be a number");
+    let sign = if exponent < 0 { '-' } else { '+' };
+    (
+        format!("{}e{}{:02}", mantissa, sign, exponent.abs()),
+        exponent,
+    )
+}
+
+fn format_float(conv: u8, value: f64, precision: Option<usize>) -> String {
+    let upper = conv.is_ascii_uppercase();
+    let str = if value.is_nan() {
+        "nan".to_owned()
+    } else if value.is_infinite() {
+        if value < 0.0 { "-inf" } else { "inf" }.to_owned()
+    } else {
+        let precision = precision.unwrap_or(6);
+        match conv.to_ascii_lowercase() {
+            b'f' => format!("{:.*}", precision, value),
+            b'e' => format_exponent(value, precision).0,
+            _ => {
+                let precision = precision.max(1);
+                let (scientific, exponent) = format_exponent(value, precision - 1);
+                let str = if exponent < -4 || exponent >= precision as i32 {
+                    scientific
+                } else {
+                    format!("{:.*}", (precision as i32 - 1 - exponent) as usize, value)
+                };
+                // NOTE : %g drop the trailing zero of the fraction
+                match str.split_once('e') {
+                    Some((mantissa, exponent)) => {
+                        format!("{}e{}", trim_fraction(mantissa), exponent)
+                    }
+                    None => trim_fraction(&str).to_owned(),
+                }
+            }
+        }
+    };
+    match upper {
+        true => str.to_ascii_uppercase(),
+        false => str,
+    }
+}
+
+fn trim_fraction(str: &str) -> &str {
+    match str.contains('.') {
+        true => str.trim_end_matches('0').trim_end_matches('.'),
+        false => str,
+    }
+}
+
+// NOTE : Only the common conversion are supported (`d i u x X o c s p f e g %`)
+pub fn format(memory: &Memory, fmt: u64, args: &[u64]) -> Result<Vec<u8>, VMError> {
+    let fmt = memory.read_cstr(fmt)?;
+    let mut out = vec![];
+    let mut args = args.iter();
+    let mut i = 0;
+    while i < fmt.len() {
+        if fmt[i] != b'%' {
+            out.push(fmt[i]);
+            i += 1;
+            continue;
+        }
+        i += 1;
+
+        let mut left = false;
+        let mut zero = false;
+        let mut plus = false;
+        while i < fmt.len() && b"-0+ #".contains(&fmt[i]) {
+            match fmt[i] {
+                b'-' => left = true,
+                b'0' => zero = true,
+                b'+' => plus = true,
+                _ => {}
+            }
+            i += 1;
+        }
+
+        let width = format_number(fmt, &mut i)?;
+        let mut precision = None;
+        if i < fmt.len() && fmt[i] == b'.' {
+            i += 1;
+            precision = Some(format_number(fmt, &mut i)?);
+        }
+
+        let mut long = false;
+        while i < fmt.len() && b"hlzjt".contains(&fmt[i]) {
+            long |= fmt[i] != b'h';
+            i += 1;
+        }
+
+        let Some(conv) = fmt.get(i) else {
+            out.push(b'%');
+            break;
+        };
+        i += 1;
+
+        let mut next = || {
+            args.next().copied().ok_or(VMError::InvalidOperation {
+                message: "printf doesn't have enough argument".to_owned(),
+            })
+        };
+        let body = match conv {
+            b'%' => {
+                out.push(b'%');
+                continue;
+            }
+            b'd' | b'i' => {
+                let value = next()?;
+                let value = if long {
+                    value as i64
+                } else {
+                    value as i32 as i64
+                };
+                let mut str = value.to_string();
+                if plus && value >= 0 {
+                    str.insert(0, '+');
+                }
+                str.into_bytes()
+            }
+            b'u' | b'x' | b'X' | b'o' => {
+                let value = next()?;
+                let value = if long { value } else { value as u32 as u64 };
+                match conv {
+                    b'x' => format!("{:x}", value),
+                    b'X' => format!("{:X}", value),
+                    b'o' => format!("{:o}", value),
+                    _ => value.to_string(),
+                }
+                .into_bytes()
+            }
+            // NOTE : Variadic float are promoted into f64, so the slot always hold a double
+            b'f' | b'F' | b'e' | b'E' | b'g' | b'G' => {
+                let value = f64::from_bits(next()?);
+                let mut str = format_float(*conv, value, precision);
+                if plus && value.is_sign_positive() {
+                    str.insert(0, '+');
+                }
+                str.into_bytes()
+            }
+            b'p' => format!("{:#x}", next()?).into_bytes(),
+            b'c' => vec![next()? as u8],
+            b's' => {
+                let str = memory.read_cstr(next()?)?;
+                match precision {
+                    Some(precision) => str[..precision.min(str.len())].to_vec(),
+                    None => str.to_vec(),
+                }
+            }
+            other => {
+                return Err(VMError::InvalidOperation {
+                    message: format!("Unsupported printf conversion %{}", *other as char),
+                });
+            }
+        };
+
+        let pad = width.saturating_sub(body.len());
+        if left {
+            out.extend_from_slice(&body);
+            out.extend(std::iter::repeat_n(b' ', pad));
+        } else if zero && *conv != b's' && *conv != b'c' {
+            let sign = body.first().filter(|c| **c == b'-' || **c == b'+');
+            let digits = if sign.is_some() { &body[1..] } else { &body };
+            if let Some(sign) = sign {
+                out.push(*sign);
+            }
+            out.extend(std::iter::repeat_n(b'0', pad));
+            out.extend_from_slice(digits);
+        } else {
+            out.extend(std::iter::repeat_n(b' ', pad));
+            out.extend_from_slice(&body);
+        }
+    }
+
+    Ok(out)
+}
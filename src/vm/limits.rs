@@ -0,0 +1,15 @@
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Limits {
+    pub fuel: Option<u64>,
+    pub max_call_depth: Option<usize>,
+    pub max_memory: Option<usize>,
+    pub allowed_imports: Option<Vec<String>>,
+}
+
+impl Limits {
+    pub fn is_allowed(&self, name: &str) -> bool {
+        self.allowed_imports
+            .as_ref()
+            .is_none_or(|allowed| allowed.iter().any(|allow| allow == name))
+    }
+}
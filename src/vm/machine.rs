@@ -0,0 +1,741 @@
+use std::collections::HashMap;
+
+use crate::{ast::BinOp, codegen::dump_args, compiler::Type, op::Arg};
+
+use super::{
+    CallTarget, Ffi, HostFunction, Import, Instruction, Limits, Memory, Program, Tracer, VMError,
+    builtins,
+    memory::{HEAP_SIZE, STACK_SIZE},
+};
+
+struct Frame {
+    function: Option<usize>,
+    return_pc: usize,
+    fp: usize,
+    sp: usize,
+    result: Option<usize>,
+    params: Vec<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackFrame {
+    pub function: Option<usize>,
+    pub pc: usize,
+}
+
+pub struct VM {
+    program: Program,
+    memory: Memory,
+    host: HashMap<String, HostFunction>,
+    ffi: Ffi,
+    frames: Vec<Frame>,
+    tracer: Option<Tracer>,
+    limits: Limits,
+    fuel: Option<u64>,
+    sp: usize,
+    pc: usize,
+}
+
+impl VM {
+    pub fn new(program: Program) -> Self {
+        Self::with_limits(program, Limits::default()).expect("Unlimited VM should not fail")
+    }
+
+    pub fn with_limits(program: Program, limits: Limits) -> Result<Self, VMError> {
+        let heap_size = match limits.max_memory {
+            Some(limit) => {
+                limit
+                    .checked_sub(program.data.len())
+                    .ok_or(VMError::MemoryLimitExceeded {
+                        requested: program.data.len(),
+                        limit,
+                    })?
+            }
+            None => HEAP_SIZE,
+        };
+        let memory = Memory::new(&program.data, STACK_SIZE, heap_size);
+        Ok(Self {
+            sp: memory.stack_start(),
+            program,
+            memory,
+            host: builtins(),
+            ffi: Ffi::new(),
+            frames: vec![],
+            tracer: None,
+            fuel: limits.fuel,
+            limits,
+            pc: 0,
+        })
+    }
+
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    pub fn fuel(&self) -> Option<u64> {
+        self.fuel
+    }
+
+    pub fn register(&mut self, name: &str, function: HostFunction) {
+        self.host.insert(name.to_owned(), function);
+    }
+
+    pub fn load_library(&mut self, path: &str) -> Result<(), VMError> {
+        self.ffi.load(path)
+    }
+
+    pub fn set_tracer(&mut self, tracer: Tracer) {
+        self.tracer = Some(tracer);
+    }
+
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    pub fn memory_mut(&mut self) -> &mut Memory {
+        &mut self.memory
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn backtrace(&self) -> Vec<StackFrame> {
+        let mut pc = self.pc;
+        let mut trace = vec![];
+        for frame in self.frames.iter().rev() {
+            trace.push(StackFrame {
+                function: frame.function,
+                pc,
+            });
+            pc = frame.return_pc.saturating_sub(1);
+        }
+        trace
+    }
+
+    pub fn local(&self, depth: usize, offset: usize) -> Result<u64, VMError> {
+        let frame = self.frames.get(depth).ok_or(VMError::InvalidOperation {
+            message: format!("No frame at depth {}", depth),
+        })?;
+        self.memory.load(self.slot_at(frame.fp, offset)?)
+    }
+
+    pub fn run(&mut self) -> Result<Option<u64>, VMError> {
+        self.init()?;
+        match self.program.function("main") {
+            Some(_) => self.call("main", &[]).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn init(&mut self) -> Result<(), VMError> {
+        self.frames.clear();
+        self.sp = self.memory.stack_start();
+        self.eval()?;
+        Ok(())
+    }
+
+    pub fn eval(&mut self) -> Result<u64, VMError> {
+        if self.frames.is_empty() {
+            self.push_frame(None, self.program.global_frame_size, vec![], None, 0)?;
+        }
+        self.pc = 0;
+        let value = self.execute(0);
+        if value.is_err() {
+            self.frames.truncate(1);
+            self.sp = self.frames[0].fp;
+        }
+        value
+    }
+
+    pub fn load(&mut self, program: Program) -> Result<(), VMError> {
+        if self.frames.len() > 1 {
+            return Err(VMError::InvalidOperation {
+                message: "Cannot load program while a spellcard is running".to_owned(),
+            });
+        }
+
+        if let Some(limit) = self.limits.max_memory
+            && program.data.len() > limit
+        {
+            return Err(VMError::MemoryLimitExceeded {
+                requested: program.data.len(),
+                limit,
+            });
+        }
+
+        let loaded = self.memory.data_len();
+        if program.data.len() < loaded || program.data[..loaded] != self.program.data[..loaded] {
+            return Err(VMError::InvalidOperation {
+                message: "Data segment of the new program does not match".to_owned(),
+            });
+        }
+        let size = program
+            .global_frame_size
+            .max(self.program.global_frame_size);
+        if let Some(global) = self.frames.first()
+            && global.sp + size * 8 > self.memory.stack_end()
+        {
+            return Err(VMError::StackOverflow);
+        }
+        self.memory.append_data(&program.data[loaded..])?;
+
+        if let Some(global) = self.frames.first_mut() {
+            let fp = global.sp + size * 8;
+            // NOTE : Local live below the frame pointer so the old one need to
+            // move up to keep their offset
+            self.memory
+                .move_block(global.sp, global.fp, global.sp + fp - global.fp);
+            global.fp = fp;
+            self.sp = fp;
+        }
+        self.program = program;
+        Ok(())
+    }
+
+    pub fn call(&mut self, name: &str, args: &[u64]) -> Result<u64, VMError> {
+        let (base, pc, sp) = (self.frames.len(), self.pc, self.sp);
+        self.start_call(name, args)?;
+        let value = self.execute(base);
+        if value.is_err() {
+            self.frames.truncate(base);
+            self.sp = sp;
+        }
+        self.pc = pc;
+        value
+    }
+
+    pub fn start(&mut self) -> Result<(), VMError> {
+        self.frames.clear();
+        self.sp = self.memory.stack_start();
+        self.push_frame(None, self.program.global_frame_size, vec![], None, 0)?;
+        self.pc = 0;
+        Ok(())
+    }
+
+    pub fn start_call(&mut self, name: &str, args: &[u64]) -> Result<(), VMError> {
+        let idx = self
+            .program
+            .function(name)
+            .ok_or(VMError::UnknownFunction {
+                name: name.to_owned(),
+            })?;
+        self.enter(idx, args.to_vec(), None, self.pc)
+    }
+
+    pub fn step(&mut self) -> Result<Option<u64>, VMError> {
+        self.execute_one(1)
+    }
+
+    fn push_frame(
+        &mut self,
+        function: Option<usize>,
+        frame_size: usize,
+        params: Vec<u64>,
+        result: Option<usize>,
+        return_pc: usize,
+    ) -> Result<(), VMError> {
+        if let Some(limit) = self.limits.max_call_depth
+            && function.is_some()
+            && self.frames.iter().filter(|f| f.function.is_some()).count() >= limit
+        {
+            return Err(VMError::CallDepthExceeded { limit });
+        }
+        let fp = self.sp + frame_size * 8;
+        if fp > self.memory.stack_end() {
+            return Err(VMError::StackOverflow);
+        }
+        for slot in (self.sp..fp).step_by(8) {
+            self.memory.store(slot, 0)?;
+        }
+        self.frames.push(Frame {
+            function,
+            return_pc,
+            fp,
+            sp: self.sp,
+            result,
+            params,
+        });
+        self.sp = fp;
+        Ok(())
+    }
+
+    fn enter(
+        &mut self,
+        idx: usize,
+        params: Vec<u64>,
+        result: Option<usize>,
+        return_pc: usize,
+    ) -> Result<(), VMError> {
+        let function = &self.program.functions[idx];
+        let (entry, frame_size) = (function.entry, function.frame_size);
+        if let Some(tracer) = self.tracer.as_mut() {
+            let depth = self.frames.len().saturating_sub(1);
+            tracer.enter(depth, &function.name, &params);
+        }
+        self.push_frame(Some(idx), frame_size, params, result, return_pc)?;
+        self.pc = entry;
+        Ok(())
+    }
+
+    fn frame(&self) -> &Frame {
+        self.frames.last().expect("VM should always have a frame")
+    }
+
+    fn slot(&self, offset: usize) -> Result<usize, VMError> {
+        self.slot_at(self.frame().fp, offset)
+    }
+
+    fn slot_at(&self, fp: usize, offset: usize) -> Result<usize, VMError> {
+        offset
+            .checked_add(1)
+            .and_then(|slot| slot.checked_mul(8))
+            .and_then(|size| fp.checked_sub(size))
+            .ok_or(VMError::InvalidAddress {
+                address: self
+                    .memory
+                    .address(fp)
+                    .wrapping_sub((offset as u64).wrapping_add(1).wrapping_mul(8)),
+            })
+    }
+
+    fn read(&self, arg: &Arg) -> Result<u64, VMError> {
+        match arg {
+            Arg::Local(offset) => self.memory.load(self.slot(*offset)?),
+            Arg::Literal(value) => value.as_raw().ok_or(VMError::InvalidOperation {
+                message: "String literal should live in data segment".to_owned(),
+            }),
+            Arg::DataOffset(offset) => Ok(self.memory.data(*offset)),
+        }
+    }
+
+    fn write(&mut self, offset: usize, value: u64) -> Result<(), VMError> {
+        let slot = self.slot(offset)?;
+        self.memory.store(slot, value)
+    }
+
+    fn call_import(&mut self, idx: usize, args: &[u64], types: &[Type]) -> Result<u64, VMError> {
+        let Import {
+            name,
+            return_type,
+            variadic,
+            ..
+        } = &self.program.imports[idx];
+        if !self.limits.is_allowed(name) {
+            return Err(VMError::ImportNotAllowed { name: name.clone() });
+        }
+        let depth = self.frames.len().saturating_sub(1);
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.enter(depth, name, args);
+        }
+
+        let value = if let Some(function) = self.host.get(name) {
+            function(&mut self.memory, args)?
+        } else {
+            let symbol = self
+                .ffi
+                .resolve(name)
+                .ok_or(VMError::UnknownFunction { name: name.clone() })?;
+            // SAFETY: The symbol is only known by its name, so we have to trust
+            // the `invite` to match its signature
+            unsafe { self.ffi.call(symbol, args, types, return_type, *variadic)? }
+        };
+        let value = return_type.normalize(value);
+
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.exit(depth, name, value);
+        }
+        Ok(value)
+    }
+
+    // NOTE : C promote the f32 passed through `...` into f64
+    fn promote_variadic(
+        &self,
+        idx: usize,
+        mut args: Vec<u64>,
+        mut types: Vec<Type>,
+    ) -> (Vec<u64>, Vec<Type>) {
+        let arity = self.program.imports[idx].arity;
+        for (arg, ty) in args.iter_mut().zip(types.iter_mut()).skip(arity) {
+            if *ty == Type::F32 {
+                *arg = Type::F32.to_float(*arg).to_bits();
+                *ty = Type::F64;
+            }
+        }
+        (args, types)
+    }
+
+    // NOTE : SysV split the struct into eightbyte, Win64 pass the bigger one by reference
+    fn lower_aggregate(
+        &self,
+        args: Vec<u64>,
+        types: Vec<Type>,
+    ) -> Result<(Vec<u64>, Vec<Type>), VMError> {
+        if !types.iter().any(Type::is_aggregate) {
+            return Ok((args, types));
+        }
+
+        if cfg!(windows) {
+            let (mut lowered, mut lowered_types) = (vec![], vec![]);
+            for (i, arg) in args.into_iter().enumerate() {
+                let ty = types.get(i).cloned().unwrap_or(Type::U64);
+                if !ty.is_aggregate() {
+                    lowered.push(arg);
+                    lowered_types.push(ty);
+                    continue;
+                }
+                match ty.size() {
+                    size @ (1 | 2 | 4 | 8) => lowered.push(self.memory.load_at(arg, size)?),
+                    _ => lowered.push(arg),
+                }
+                lowered_types.push(Type::U64);
+            }
+            return Ok((lowered, lowered_types));
+        }
+
+        // NOTE : A struct over 16 byte or one that does not fit in the remaining
+        // register goes on the stack whole, like the native SysV backend
+        let (mut ints, mut floats, mut stack) = (vec![], vec![], vec![]);
+        for (i, arg) in args.into_iter().enumerate() {
+            let ty = types.get(i).cloned().unwrap_or(Type::U64);
+            let eightbytes = if ty.is_aggregate() {
+                let scalars = ty.scalars();
+                (0..ty.size().div_ceil(8))
+                    .map(|k| {
+                        let size = (ty.size() - k * 8).min(8);
+                        let float = scalars
+                            .iter()
+                            .filter(|(offset, _)| offset / 8 == k)
+                            .all(|(_, ty)| ty.is_float());
+                        let ty = if float { Type::F64 } else { Type::U64 };
+                        Ok((self.memory.load_at(arg + k as u64 * 8, size)?, ty))
+                    })
+                    .collect::<Result<Vec<_>, VMError>>()?
+            } else {
+                vec![(arg, ty.clone())]
+            };
+
+            let vectors = eightbytes.iter().filter(|(_, ty)| ty.is_float()).count();
+            let fit = ints.len() + eightbytes.len() - vectors <= 6 && floats.len() + vectors <= 8;
+            if ty.size() > 16 || !fit {
+                stack.extend(eightbytes.into_iter().map(|(value, _)| value));
+                continue;
+            }
+            for (value, ty) in eightbytes {
+                match ty.is_float() {
+                    true => floats.push((value, ty)),
+                    false => ints.push((value, ty)),
+                }
+            }
+        }
+
+        // NOTE : Ffi only spill the integer past the sixth one, so the stack
+        // word follow the padded integer register
+        if !stack.is_empty() {
+            ints.resize(6, (0, Type::U64));
+            ints.extend(stack.into_iter().map(|value| (value, Type::U64)));
+        }
+        Ok(ints.into_iter().chain(floats).unzip())
+    }
+
+    fn operands(&self, instruction: &Instruction) -> Vec<String> {
+        let args = match instruction {
+            Instruction::Assign { arg, .. }
+            | Instruction::UnaryNot { arg, .. }
+            | Instruction::JmpIfNot { arg, .. }
+            | Instruction::BoundsCheck { index: arg, .. }
+            | Instruction::Ret(Some(arg)) => vec![arg],
+            Instruction::Load { ptr, .. } => vec![ptr],
+            Instruction::BinOp { lhs, rhs, .. }
+            | Instruction::Store {
+                ptr: lhs, arg: rhs, ..
+            }
+            | Instruction::Element {
+                base: lhs,
+                index: rhs,
+                ..
+            } => vec![lhs, rhs],
+            Instruction::Call { args, .. } => args.iter().collect(),
+            Instruction::ParamAssign { offset, .. } => {
+                let value = self.frame().params.get(*offset).copied().unwrap_or(0);
+                return vec![format!("Param({:#04x})={}", offset, value as i64)];
+            }
+            Instruction::Ret(None) | Instruction::Jmp(_) | Instruction::AddrOf { .. } => vec![],
+        };
+        args.into_iter()
+            .filter_map(|arg| match arg {
+                Arg::Local(_) => Some(format!(
+                    "{}={}",
+                    dump_args(arg),
+                    self.read(arg).ok()? as i64
+                )),
+                Arg::DataOffset(_) => {
+                    Some(format!("{}={:#x}", dump_args(arg), self.read(arg).ok()?))
+                }
+                Arg::Literal(_) => None,
+            })
+            .collect()
+    }
+
+    fn trace(&mut self, pc: usize, instruction: &Instruction, operands: Vec<String>) {
+        let result = match instruction {
+            Instruction::Assign { offset, .. }
+            | Instruction::UnaryNot { offset, .. }
+            | Instruction::BinOp { offset, .. }
+            | Instruction::AddrOf { offset, .. }
+            | Instruction::Load { offset, .. }
+            | Instruction::Element { offset, .. }
+            | Instruction::ParamAssign {
+                arg: Arg::Local(offset),
+                ..
+            }
+            | Instruction::Call {
+                result: offset,
+                target: CallTarget::Import(_),
+                ..
+            } => self
+                .read(&Arg::Local(*offset))
+                .ok()
+                .map(|value| format!("{}={}", dump_args(&Arg::Local(*offset)), value as i64)),
+            _ => None,
+        };
+        let function = match self.program.function_at(pc) {
+            Some(idx) => self.program.functions[idx].name.clone(),
+            None => "__global".to_owned(),
+        };
+        let text = self.program.dump(instruction);
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.instruction(
+                self.frames.len().saturating_sub(1),
+                &function,
+                pc,
+                text,
+                operands,
+                result,
+            );
+        }
+    }
+
+    fn execute(&mut self, base: usize) -> Result<u64, VMError> {
+        loop {
+            if let Some(value) = self.execute_one(base)? {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn execute_one(&mut self, base: usize) -> Result<Option<u64>, VMError> {
+        if let Some(fuel) = self.fuel.as_mut() {
+            if *fuel == 0 {
+                return Err(VMError::OutOfFuel {
+                    budget: self.limits.fuel.unwrap_or(0),
+                });
+            }
+            *fuel -= 1;
+        }
+        let pc = self.pc;
+        let instruction = self
+            .program
+            .code
+            .get(pc)
+            .ok_or(VMError::InvalidOperation {
+                message: format!("Program counter out of bound {}", pc),
+            })?
+            .clone();
+        if !self
+            .tracer
+            .as_ref()
+            .is_some_and(|tracer| tracer.instructions)
+        {
+            return self.dispatch(instruction, base);
+        }
+
+        let operands = self.operands(&instruction);
+        // NOTE : Call and offer leave the frame, so they are traced before being executed
+        if matches!(
+            instruction,
+            Instruction::Call {
+                target: CallTarget::Function(_),
+                ..
+            } | Instruction::Ret(_)
+        ) {
+            self.trace(pc, &instruction, operands);
+            return self.dispatch(instruction, base);
+        }
+        let value = self.dispatch(instruction.clone(), base);
+        self.trace(pc, &instruction, operands);
+        value
+    }
+
+    fn dispatch(&mut self, instruction: Instruction, base: usize) -> Result<Option<u64>, VMError> {
+        self.pc += 1;
+        match instruction {
+            Instruction::Assign { offset, arg } => {
+                let value = self.read(&arg)?;
+                self.write(offset, value)?;
+            }
+            Instruction::UnaryNot { offset, arg } => {
+                let value = self.read(&arg)?;
+                self.write(offset, (value == 0) as u64)?;
+            }
+            Instruction::AddrOf { offset, local } => {
+                let address = self.memory.address(self.slot(local)?);
+                self.write(offset, address)?;
+            }
+            Instruction::Load { offset, ty, ptr } => {
+                let address = self.read(&ptr)?;
+                let value = self.memory.load_at(address, ty.size())?;
+                self.write(offset, ty.normalize(value))?;
+            }
+            Instruction::Store { ty, ptr, arg } => {
+                let address = self.read(&ptr)?;
+                let value = self.read(&arg)?;
+                self.memory.store_at(address, ty.size(), value)?;
+            }
+            Instruction::Element {
+                offset,
+                base,
+                index,
+                size,
+            } => {
+                let base = self.read(&base)?;
+                let index = self.read(&index)?;
+                let address = base.wrapping_add(index.wrapping_mul(size as u64));
+                self.write(offset, address)?;
+            }
+            Instruction::BoundsCheck { index, length } => {
+                let index = self.read(&index)?;
+                if index >= length as u64 {
+                    return Err(VMError::IndexOutOfBounds {
+                        index: index as i64,
+                        length,
+                    });
+                }
+            }
+            Instruction::BinOp {
+                binop,
+                ty,
+                offset,
+                lhs,
+                rhs,
+            } => {
+                let lhs = self.read(&lhs)?;
+                let rhs = self.read(&rhs)?;
+                let signed = ty.is_signed();
+                let value = if ty.is_float() {
+                    float_binop(&binop, &ty, lhs, rhs)
+                } else {
+                    match binop {
+                        BinOp::Add => ty.normalize(lhs.wrapping_add(rhs)),
+                        BinOp::Sub => ty.normalize(lhs.wrapping_sub(rhs)),
+                        BinOp::Mul => ty.normalize(lhs.wrapping_mul(rhs)),
+                        BinOp::Div => {
+                            if rhs == 0 {
+                                return Err(VMError::DivisionByZero);
+                            }
+                            match signed {
+                                true => ty.normalize((lhs as i64).wrapping_div(rhs as i64) as u64),
+                                false => ty.normalize(lhs / rhs),
+                            }
+                        }
+                        BinOp::Equal => (lhs == rhs) as u64,
+                        BinOp::Greater if signed => ((lhs as i64) > (rhs as i64)) as u64,
+                        BinOp::Greater => (lhs > rhs) as u64,
+                        BinOp::Less if signed => ((lhs as i64) < (rhs as i64)) as u64,
+                        BinOp::Less => (lhs < rhs) as u64,
+                        BinOp::And | BinOp::Or => {
+                            unreachable!("Logical operator is lowered into jump")
+                        }
+                    }
+                };
+                self.write(offset, value)?;
+            }
+            Instruction::ParamAssign { offset, arg } => {
+                let value = self.frame().params.get(offset).copied().unwrap_or(0);
+                match arg {
+                    Arg::Local(local) => self.write(local, value)?,
+                    _ => {
+                        return Err(VMError::InvalidOperation {
+                            message: "Function parameter can only assign on local variable"
+                                .to_owned(),
+                        });
+                    }
+                }
+            }
+            Instruction::Call {
+                result,
+                target,
+                args,
+                types,
+            } => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.read(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                match target {
+                    CallTarget::Function(idx) => {
+                        self.enter(idx, args, Some(result), self.pc)?;
+                    }
+                    CallTarget::Import(idx) => {
+                        let (args, types) = self.promote_variadic(idx, args, types);
+                        let (args, types) = self.lower_aggregate(args, types)?;
+                        let value = self.call_import(idx, &args, &types)?;
+                        self.write(result, value)?;
+                    }
+                }
+            }
+            Instruction::Ret(arg) => {
+                let value = match arg {
+                    Some(arg) => self.read(&arg)?,
+                    None => 0,
+                };
+                if self.frame().function.is_none() {
+                    return Ok(Some(value));
+                }
+
+                let frame = self.frames.pop().expect("VM should always have a frame");
+                self.sp = frame.sp;
+                self.pc = frame.return_pc;
+                if let (Some(tracer), Some(idx)) = (self.tracer.as_mut(), frame.function) {
+                    let depth = self.frames.len().saturating_sub(1);
+                    tracer.exit(depth, &self.program.functions[idx].name, value);
+                }
+                if self.frames.len() <= base {
+                    return Ok(Some(value));
+                }
+                if let Some(result) = frame.result {
+                    self.write(result, value)?;
+                }
+            }
+            Instruction::Jmp(target) => self.pc = target,
+            Instruction::JmpIfNot { target, arg } => {
+                if self.read(&arg)? == 0 {
+                    self.pc = target;
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+fn float_binop(binop: &BinOp, ty: &Type, lhs: u64, rhs: u64) -> u64 {
+    let (lhs, rhs) = (ty.to_float(lhs), ty.to_float(rhs));
+    match binop {
+        BinOp::Add => ty.from_float(lhs + rhs),
+        BinOp::Sub => ty.from_float(lhs - rhs),
+        BinOp::Mul => ty.from_float(lhs * rhs),
+        BinOp::Div => ty.from_float(lhs / rhs),
+        BinOp::Equal => (lhs == rhs) as u64,
+        BinOp::Greater => (lhs > rhs) as u64,
+        BinOp::Less => (lhs < rhs) as u64,
+        BinOp::And | BinOp::Or => unreachable!("Logical operator is lowered into jump"),
+    }
+}
@@ -0,0 +1,152 @@
+use super::VMError;
+
+pub const STACK_SIZE: usize = 1024 * 1024;
+pub const HEAP_SIZE: usize = 64 * 1024;
+
+// NOTE : [ stack | data | free | heap ] is allocated once so address stay valid,
+// data grow into the free space when more code is loaded and heap grow down
+pub struct Memory {
+    bytes: Vec<u8>,
+    data_start: usize,
+    data_end: usize,
+    heap_start: usize,
+}
+
+impl Memory {
+    pub fn new(data: &[u8], stack_size: usize, heap_size: usize) -> Self {
+        let mut bytes = vec![0; stack_size + data.len() + heap_size];
+        bytes[stack_size..stack_size + data.len()].copy_from_slice(data);
+        Self {
+            heap_start: bytes.len(),
+            bytes,
+            data_start: stack_size,
+            data_end: stack_size + data.len(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn stack_start(&self) -> usize {
+        0
+    }
+
+    pub fn stack_end(&self) -> usize {
+        self.data_start
+    }
+
+    pub fn data(&self, offset: usize) -> u64 {
+        self.address(self.data_start + offset)
+    }
+
+    pub fn data_len(&self) -> usize {
+        self.data_end - self.data_start
+    }
+
+    pub fn append_data(&mut self, data: &[u8]) -> Result<(), VMError> {
+        let end = self.data_end + data.len();
+        if end > self.heap_start {
+            return Err(VMError::InvalidOperation {
+                message: "Not enough memory to load the data segment".to_owned(),
+            });
+        }
+        self.bytes[self.data_end..end].copy_from_slice(data);
+        self.data_end = end;
+        Ok(())
+    }
+
+    pub fn heap_mark(&self) -> usize {
+        self.heap_start
+    }
+
+    // NOTE : Free everything allocated since the mark, the heap is a stack of string
+    pub fn release(&mut self, mark: usize) {
+        self.heap_start = self.heap_start.max(mark.min(self.bytes.len()));
+    }
+
+    pub fn alloc_cstr(&mut self, str: &str) -> Result<u64, VMError> {
+        let len = str.len() + 1;
+        let start = self
+            .heap_start
+            .checked_sub(len)
+            .map(|start| start & !7)
+            .filter(|start| *start >= self.data_end)
+            .ok_or(VMError::MemoryLimitExceeded {
+                requested: self.bytes.len() - self.heap_start + len,
+                limit: self.bytes.len() - self.data_start,
+            })?;
+        self.bytes[start..start + str.len()].copy_from_slice(str.as_bytes());
+        self.bytes[start + str.len()] = 0;
+        self.heap_start = start;
+        Ok(self.address(start))
+    }
+
+    pub fn move_block(&mut self, start: usize, end: usize, dest: usize) {
+        self.bytes.copy_within(start..end, dest);
+        let cleared = if dest > start {
+            start..dest.min(end)
+        } else {
+            (dest + end - start).max(start)..end
+        };
+        self.bytes[cleared].fill(0);
+    }
+
+    pub fn address(&self, index: usize) -> u64 {
+        self.bytes.as_ptr() as u64 + index as u64
+    }
+
+    pub fn index(&self, address: u64, len: usize) -> Result<usize, VMError> {
+        let base = self.bytes.as_ptr() as u64;
+        match address.checked_sub(base) {
+            Some(index) if index as usize + len <= self.bytes.len() => Ok(index as usize),
+            _ => Err(VMError::InvalidAddress { address }),
+        }
+    }
+
+    pub fn load(&self, index: usize) -> Result<u64, VMError> {
+        let bytes = self
+            .bytes
+            .get(index..index + 8)
+            .ok_or(VMError::InvalidAddress {
+                address: self.address(index),
+            })?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    pub fn store(&mut self, index: usize, value: u64) -> Result<(), VMError> {
+        let address = self.address(index);
+        let bytes = self
+            .bytes
+            .get_mut(index..index + 8)
+            .ok_or(VMError::InvalidAddress { address })?;
+        bytes.copy_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    pub fn load_at(&self, address: u64, size: usize) -> Result<u64, VMError> {
+        let index = self.index(address, size)?;
+        let mut bytes = [0; 8];
+        bytes[..size].copy_from_slice(&self.bytes[index..index + size]);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    pub fn store_at(&mut self, address: u64, size: usize, value: u64) -> Result<(), VMError> {
+        let index = self.index(address, size)?;
+        self.bytes[index..index + size].copy_from_slice(&value.to_le_bytes()[..size]);
+        Ok(())
+    }
+
+    pub fn read_cstr(&self, address: u64) -> Result<&[u8], VMError> {
+        let start = self.index(address, 0)?;
+        let len = self.bytes[start..]
+            .iter()
+            .position(|b| *b == 0)
+            .ok_or(VMError::InvalidAddress { address })?;
+        Ok(&self.bytes[start..start + len])
+    }
+}
@@ -0,0 +1,23 @@
+mod bytecode;
+mod disasm;
+mod error;
+mod ffi;
+mod host;
+mod limits;
+mod machine;
+mod memory;
+mod program;
+mod trace;
+
+pub use bytecode::*;
+pub use error::*;
+pub use ffi::*;
+pub use host::*;
+pub use limits::*;
+pub use machine::*;
+pub use memory::*;
+pub use program::*;
+pub use trace::*;
+
+#[cfg(test)]
+mod test;
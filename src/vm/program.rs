@@ -0,0 +1,314 @@
+use std::collections::HashMap;
+
+use crate::{
+    ast::BinOp,
+    commons::Loc,
+    compiler::{Compiler, Type},
+    op::{Arg, Op},
+};
+
+use super::VMError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CallTarget {
+    Function(usize),
+    Import(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction {
+    Assign {
+        offset: usize,
+        arg: Arg,
+    },
+    UnaryNot {
+        offset: usize,
+        arg: Arg,
+    },
+    BinOp {
+        binop: BinOp,
+        ty: Type,
+        offset: usize,
+        lhs: Arg,
+        rhs: Arg,
+    },
+    ParamAssign {
+        offset: usize,
+        arg: Arg,
+    },
+    Call {
+        result: usize,
+        target: CallTarget,
+        args: Vec<Arg>,
+        types: Vec<Type>,
+    },
+    AddrOf {
+        offset: usize,
+        local: usize,
+    },
+    Load {
+        offset: usize,
+        ty: Type,
+        ptr: Arg,
+    },
+    Store {
+        ty: Type,
+        ptr: Arg,
+        arg: Arg,
+    },
+    Element {
+        offset: usize,
+        base: Arg,
+        index: Arg,
+        size: usize,
+    },
+    BoundsCheck {
+        index: Arg,
+        length: usize,
+    },
+    Ret(Option<Arg>),
+    Jmp(usize),
+    JmpIfNot {
+        target: usize,
+        arg: Arg,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Import {
+    pub name: String,
+    pub return_type: Type,
+    // Count of the declared parameter, the rest of the argument is variadic
+    pub arity: usize,
+    pub variadic: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub entry: usize,
+    pub frame_size: usize,
+    pub arity: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Program {
+    pub data: Vec<u8>,
+    pub imports: Vec<Import>,
+    pub functions: Vec<Function>,
+    pub global_frame_size: usize,
+    pub code: Vec<Instruction>,
+    pub lines: Vec<(usize, Loc)>,
+}
+
+struct Section {
+    name: Option<String>,
+    ops: Vec<Op>,
+}
+
+impl Program {
+    pub fn new(compiler: &Compiler, ops: Vec<Op>) -> Result<Self, VMError> {
+        let mut program = Program {
+            data: compiler.eternal_value.clone(),
+            global_frame_size: compiler
+                .spellcard_scope
+                .get("__global")
+                .map_or(0, |scope| scope.local_count()),
+            ..Default::default()
+        };
+
+        let mut sections = vec![Section {
+            name: None,
+            ops: vec![],
+        }];
+        for op in ops {
+            match op {
+                Op::Invite { name } => {
+                    if !program.imports.iter().any(|import| import.name == name) {
+                        let return_type = compiler
+                            .checker
+                            .function(&name)
+                            .map_or(Type::I32, |f| f.return_type.clone());
+                        if return_type.is_aggregate() {
+                            return Err(VMError::InvalidOperation {
+                                message: format!(
+                                    "Cannot return a struct or enum by value from {}",
+                                    name
+                                ),
+                            });
+                        }
+                        let arity = compiler
+                            .checker
+                            .function(&name)
+                            .and_then(|f| f.params.as_ref())
+                            .map_or(0, Vec::len);
+                        let variadic = compiler.spellcard.get(&name).is_some_and(|f| f.variadic);
+                        program.imports.push(Import {
+                            name,
+                            return_type,
+                            arity,
+                            variadic,
+                        });
+                    }
+                }
+                Op::Function(name) => sections.push(Section {
+                    name: Some(name),
+                    ops: vec![],
+                }),
+                op => sections.last_mut().unwrap().ops.push(op),
+            }
+        }
+
+        let mut functions = HashMap::new();
+        for (i, section) in sections.iter().skip(1).enumerate() {
+            let name = section.name.clone().unwrap();
+            functions.insert(name.clone(), i);
+            program.functions.push(Function {
+                frame_size: compiler
+                    .spellcard_scope
+                    .get(&name)
+                    .map_or(0, |scope| scope.local_count()),
+                arity: compiler.spellcard.get(&name).map_or(0, |f| f.args.len()),
+                name,
+                entry: 0,
+            });
+        }
+
+        for section in sections {
+            let entry = program.code.len();
+            if let Some(name) = &section.name {
+                program.functions[functions[name]].entry = entry;
+            }
+
+            let mut labels = HashMap::new();
+            let mut pc = entry;
+            for op in section.ops.iter() {
+                match op {
+                    Op::Label(name) => {
+                        labels.insert(name.clone(), pc);
+                    }
+                    Op::StackAlloc(_) | Op::Loc(_) => {}
+                    _ => pc += 1,
+                }
+            }
+
+            let label = |name: &String| {
+                labels
+                    .get(name)
+                    .copied()
+                    .ok_or(VMError::UnknownLabel { name: name.clone() })
+            };
+            for op in section.ops {
+                let instruction = match op {
+                    Op::Label(_) | Op::StackAlloc(_) | Op::Invite { .. } | Op::Function(_) => {
+                        continue;
+                    }
+                    Op::Loc(loc) => {
+                        let pc = program.code.len();
+                        match program.lines.last_mut() {
+                            Some(line) if line.0 == pc => line.1 = loc,
+                            _ => program.lines.push((pc, loc)),
+                        }
+                        continue;
+                    }
+                    Op::EternalAssign { offset, arg } => Instruction::Assign { offset, arg },
+                    Op::UnaryNot { offset, arg } => Instruction::UnaryNot { offset, arg },
+                    Op::BinOp {
+                        binop,
+                        ty,
+                        offset,
+                        lhs,
+                        rhs,
+                    } => Instruction::BinOp {
+                        binop,
+                        ty,
+                        offset,
+                        lhs,
+                        rhs,
+                    },
+                    Op::ParamAssign { offset, arg } => Instruction::ParamAssign { offset, arg },
+                    Op::Call {
+                        result,
+                        name,
+                        args,
+                        types,
+                    } => {
+                        let (target, types) = if let Some(idx) = functions.get(&name) {
+                            (CallTarget::Function(*idx), vec![])
+                        } else if let Some(idx) =
+                            program.imports.iter().position(|i| i.name == name)
+                        {
+                            // NOTE : SysV pass the bigger struct on the stack, but AArch64
+                            // pass a copy by reference
+                            if cfg!(all(not(windows), not(target_arch = "x86_64")))
+                                && types.iter().any(|ty| ty.is_aggregate() && ty.size() > 16)
+                            {
+                                return Err(VMError::InvalidOperation {
+                                    message: format!(
+                                        "Cannot pass a struct or enum bigger than 16 byte by value to {}",
+                                        name
+                                    ),
+                                });
+                            }
+                            (CallTarget::Import(idx), types)
+                        } else {
+                            return Err(VMError::UnknownFunction { name });
+                        };
+                        Instruction::Call {
+                            result,
+                            target,
+                            args,
+                            types,
+                        }
+                    }
+                    Op::AddrOf { offset, local } => Instruction::AddrOf { offset, local },
+                    Op::Load { offset, ty, ptr } => Instruction::Load { offset, ty, ptr },
+                    Op::Store { ty, ptr, arg } => Instruction::Store { ty, ptr, arg },
+                    Op::Element {
+                        offset,
+                        base,
+                        index,
+                        size,
+                    } => Instruction::Element {
+                        offset,
+                        base,
+                        index,
+                        size,
+                    },
+                    Op::BoundsCheck { index, length } => Instruction::BoundsCheck { index, length },
+                    Op::Ret(arg) => Instruction::Ret(arg),
+                    Op::Jmp { name } => Instruction::Jmp(label(&name)?),
+                    Op::JmpIfNot { name, arg } => Instruction::JmpIfNot {
+                        target: label(&name)?,
+                        arg,
+                    },
+                };
+                program.code.push(instruction);
+            }
+            // NOTE : Falling off the end of the section should not run into the next one
+            program.code.push(Instruction::Ret(None));
+        }
+
+        Ok(program)
+    }
+
+    pub fn function(&self, name: &str) -> Option<usize> {
+        self.functions.iter().position(|f| f.name == name)
+    }
+
+    pub fn line(&self, pc: usize) -> Option<Loc> {
+        self.lines
+            .binary_search_by_key(&pc, |(pc, _)| *pc)
+            .ok()
+            .map(|idx| self.lines[idx].1)
+    }
+
+    pub fn function_at(&self, pc: usize) -> Option<usize> {
+        self.functions
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| f.entry <= pc)
+            .max_by_key(|(_, f)| f.entry)
+            .map(|(idx, _)| idx)
+    }
+}
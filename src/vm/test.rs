@@ -0,0 +1,1104 @@
+use std::{cell::RefCell, io::Write, rc::Rc};
+
+use crate::{compiler::Compiler, lexer::Lexer, op::Arg, parser::parser::Parser, value::Value};
+
+use super::{BytecodeError, Instruction, Limits, Memory, Program, Tracer, VM, VMError, format};
+
+fn setup_program(body: &str) -> Program {
+    let chars = body.chars().collect::<Vec<_>>();
+    let lexer = Lexer::new(&chars);
+    let mut parser = Parser::new(lexer);
+    let ast = parser.parse().expect("Should parse correctly");
+    let mut compiler = Compiler::new();
+    let ops = compiler.compile(ast).expect("Should compile ast correctly");
+    Program::new(&compiler, ops).expect("Should assemble correctly")
+}
+
+fn setup(body: &str) -> VM {
+    VM::new(setup_program(body))
+}
+
+#[derive(Clone, Default)]
+struct Output(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Output {
+    fn lines(&self) -> Vec<String> {
+        String::from_utf8(self.0.borrow().clone())
+            .unwrap()
+            .lines()
+            .map(|line| line.to_owned())
+            .collect()
+    }
+}
+
+#[test]
+fn run_simple_source_code() {
+    let body = "
+spellcard main() i32 {
+    offer 69;
+}";
+
+    let mut vm = setup(body);
+    assert_eq!(vm.run().expect("Should run correctly"), Some(69));
+}
+
+#[test]
+fn run_until() {
+    let body = "
+spellcard main() i32 {
+    vow a = 0;
+    vow b = 1;
+    vow c = 0;
+    until a < 1000 {
+        c = a + b;
+        a = b;
+        b = c;
+    }
+    offer a;
+}";
+
+    let mut vm = setup(body);
+    assert_eq!(vm.run().expect("Should run correctly"), Some(1597));
+}
+
+#[test]
+fn run_foreseen_otherwise() {
+    let body = "
+spellcard main() i32 {
+    eternal a = 69;
+    foreseen a > 70 {
+        offer 1;
+    } otherwise {
+        offer 2;
+    }
+}";
+
+    let mut vm = setup(body);
+    assert_eq!(vm.run().expect("Should run correctly"), Some(2));
+}
+
+#[test]
+fn run_function_call() {
+    let body = "
+spellcard add_number(a: i32, b: i32) i32 {
+    offer a + b;
+}
+
+spellcard main() i32 {
+    vow a = add_number(35, 34);
+    offer a * 2;
+}";
+
+    let mut vm = setup(body);
+    assert_eq!(vm.run().expect("Should run correctly"), Some(138));
+}
+
+#[test]
+fn run_recursion() {
+    let body = "
+spellcard main() i32 {
+    offer fib(10) + is_even(7) * 10 + is_odd(7);
+}
+
+spellcard fib(n: i32) i32 {
+    foreseen n < 2 {
+        offer n;
+    }
+    offer fib(n - 1) + fib(n - 2);
+}
+
+spellcard is_even(n: i32) i32 {
+    foreseen n == 0 {
+        offer 1;
+    }
+    offer is_odd(n - 1);
+}
+
+spellcard is_odd(n: i32) i32 {
+    foreseen n == 0 {
+        offer 0;
+    }
+    offer is_even(n - 1);
+}";
+
+    let mut vm = setup(body);
+    assert_eq!(vm.run().expect("Should run correctly"), Some(56));
+}
+
+#[test]
+fn run_bool() {
+    let body = "
+spellcard positive(a: i32) bool {
+    offer a > 0;
+}
+
+spellcard main() i32 {
+    vow a = 0;
+    vow running = true;
+    until running {
+        a = a + 1;
+        running = !(a == 3);
+    }
+    foreseen positive(a) == true {
+        offer a;
+    }
+    offer 0;
+}";
+
+    let program = Program::decode(&setup_program(body).encode()).expect("Should decode correctly");
+    let mut vm = VM::new(program);
+    assert_eq!(vm.run().expect("Should run correctly"), Some(3));
+}
+
+#[test]
+fn run_sized_integer() {
+    let body = "
+spellcard wrap(a: u8) u8 {
+    offer a + 200;
+}
+
+spellcard main() i32 {
+    vow result = 0;
+    vow b = wrap(100);
+    foreseen b == 44 {
+        result = result + 1;
+    }
+    vow big: u32 = 4000000000;
+    foreseen big / 2 == 2000000000 {
+        result = result + 10;
+    }
+    foreseen big > 1 {
+        result = result + 100;
+    }
+    vow small: i8 = -100;
+    foreseen small / 3 == -33 {
+        result = result + 1000;
+    }
+    offer result;
+}";
+
+    let program = Program::decode(&setup_program(body).encode()).expect("Should decode correctly");
+    let mut vm = VM::new(program);
+    assert_eq!(vm.run().expect("Should run correctly"), Some(1111));
+}
+
+#[test]
+fn run_float() {
+    let body = "
+spellcard area(radius: f32) f32 {
+    offer radius * radius * 3.5;
+}
+
+spellcard main() i32 {
+    vow result = 0;
+    foreseen area(2.0) == 14.0 {
+        result = result + 1;
+    }
+    vow ratio = 1.0 / 3.0;
+    foreseen ratio < 0.34 {
+        result = result + 10;
+    }
+    foreseen 1.0 / 0.0 > 1000000.0 {
+        result = result + 100;
+    }
+    offer result;
+}";
+
+    let program = Program::decode(&setup_program(body).encode()).expect("Should decode correctly");
+    let mut vm = VM::new(program);
+    assert_eq!(vm.run().expect("Should run correctly"), Some(111));
+}
+
+#[test]
+fn run_float_nan() {
+    let body = "
+spellcard main() i32 {
+    vow result = 0;
+    vow nan = 0.0 / 0.0;
+    foreseen nan == nan {
+        result = result + 1;
+    }
+    foreseen nan < 1.0 {
+        result = result + 10;
+    }
+    foreseen nan > 1.0 {
+        result = result + 100;
+    }
+    foreseen 1.0 == 1.0 {
+        result = result + 1000;
+    }
+    offer result;
+}";
+
+    let mut vm = setup(body);
+    assert_eq!(vm.run().expect("Should run correctly"), Some(1000));
+}
+
+#[test]
+fn run_pointer() {
+    let body = "
+spellcard bump(counter: *i8) void {
+    *counter = *counter + 1;
+}
+
+spellcard main() i32 {
+    vow result = 0;
+    vow small: i8 = 127;
+    bump(&small);
+    foreseen small == -128 {
+        result = result + 1;
+    }
+    vow second: i64 = 20;
+    vow first: i64 = 10;
+    vow cursor = &first;
+    *cursor = *cursor + 1;
+    foreseen *(cursor + 1) - *cursor == 9 {
+        result = result + 10;
+    }
+    vow missing: *i32 = null;
+    foreseen missing == null {
+        result = result + 100;
+    }
+    offer result;
+}";
+
+    let program = Program::decode(&setup_program(body).encode()).expect("Should decode correctly");
+    let mut vm = VM::new(program);
+    assert_eq!(vm.run().expect("Should run correctly"), Some(111));
+}
+
+#[test]
+fn run_array() {
+    let body = "
+spellcard sum(values: *[i16; 4]) i16 {
+    vow total: i16 = 0;
+    vow i = 0;
+    until i < 4 {
+        total = total + (*values)[i];
+        i = i + 1;
+    }
+    offer total;
+}
+
+spellcard main() i32 {
+    vow a: [i16; 4] = [1, 2, 3, -4];
+    a[3] = 4;
+    vow copy = a;
+    copy[0] = 100;
+    vow grid: [[i32; 3]; 2] = [[1, 2, 3], [4, 5, 6]];
+    grid[1][2] = grid[1][2] * 10;
+    vow second = &a[1];
+    *second = *second + 10;
+    vow result = 0;
+    foreseen sum(&a) == 20 {
+        result = result + 1;
+    }
+    foreseen copy[0] + copy[1] == 102 {
+        result = result + 10;
+    }
+    foreseen grid[1][2] + grid[0][0] == 61 {
+        result = result + 100;
+    }
+    offer result;
+}";
+
+    let program = Program::decode(&setup_program(body).encode()).expect("Should decode correctly");
+    let mut vm = VM::new(program);
+    assert_eq!(vm.run().expect("Should run correctly"), Some(111));
+}
+
+#[test]
+fn run_struct() {
+    let body = "
+struct Color { r: u8, g: u8, b: u8, a: u8 }
+struct Rect { x: i32, y: i32, color: Color, size: [i64; 2] }
+
+spellcard brightness(color: Color) u8 {
+    color.r = 0;
+    offer color.g + color.b + color.r;
+}
+
+spellcard area(rect: *Rect) i64 {
+    offer rect.size[0] * rect.size[1];
+}
+
+spellcard main() i32 {
+    vow rect = Rect { x: 1, y: 2, color: Color { r: 10, g: 20, b: 30, a: 40 }, size: [3, 4] };
+    vow color = rect.color;
+    rect.color.g = 1;
+    vow ptr = &rect;
+    ptr.y = 7;
+    vow result = 0;
+    foreseen brightness(color) + color.r == 60 {
+        result = result + 1;
+    }
+    foreseen rect.color.g == 1 {
+        foreseen rect.x + rect.y == 8 {
+            result = result + 10;
+        }
+    }
+    foreseen area(&rect) == 12 {
+        result = result + 100;
+    }
+    offer result;
+}";
+
+    let program = Program::decode(&setup_program(body).encode()).expect("Should decode correctly");
+    let mut vm = VM::new(program);
+    assert_eq!(vm.run().expect("Should run correctly"), Some(111));
+}
+
+#[test]
+fn run_match() {
+    let body = "
+struct Point { x: i32, y: i32 }
+enum Shape { Circle(Point, i32), Rect(i16, i64), Empty }
+
+spellcard area(shape: Shape) i64 {
+    match shape {
+        Shape::Circle(_, radius) => {
+            offer 3;
+        }
+        Shape::Rect(width, height) => {
+            offer height * 2;
+        }
+        Shape::Empty => {
+            offer 0;
+        }
+    }
+}
+
+spellcard grade(score: u8) i32 {
+    vow result = 0;
+    match score {
+        0..50 => { result = 1; }
+        50..=79 => { result = 2; }
+        80 => { result = 3; }
+        _ => { result = 4; }
+    }
+    offer result;
+}
+
+spellcard main() i32 {
+    vow shape = Shape::Circle(Point { x: 1, y: 2 }, 5);
+    vow result = 0;
+    match shape {
+        Shape::Circle(center, radius) => {
+            foreseen center.y + radius == 7 {
+                result = result + 1;
+            }
+        }
+        _ => {}
+    }
+    shape = Shape::Rect(2, 21);
+    foreseen area(shape) == 42 {
+        result = result + 10;
+    }
+    foreseen grade(10) + grade(50) * 10 + grade(80) * 100 + grade(81) * 1000 == 4321 {
+        result = result + 100;
+    }
+    offer result;
+}";
+
+    let program = Program::decode(&setup_program(body).encode()).expect("Should decode correctly");
+    let mut vm = VM::new(program);
+    assert_eq!(vm.run().expect("Should run correctly"), Some(111));
+}
+
+#[test]
+fn run_match_binding_shadow_outer_local() {
+    let body = "
+enum Shape { Circle(i32), Empty }
+
+spellcard main() i32 {
+    vow radius = 1.5;
+    vow result = 0;
+    match Shape::Circle(7) {
+        Shape::Circle(radius) => {
+            result = radius;
+        }
+        Shape::Empty => {}
+    }
+    foreseen radius * 2.0 == 3.0 {
+        result = result + 10;
+    }
+    offer result;
+}";
+
+    let mut vm = setup(body);
+    assert_eq!(vm.run().expect("Should run correctly"), Some(17));
+}
+
+#[test]
+fn run_generic_spellcard() {
+    let body = "
+struct Point { x: i32, y: i32 }
+
+spellcard max<T>(a: T, b: T) T {
+    foreseen a > b {
+        offer a;
+    }
+    offer b;
+}
+
+spellcard read<T>(p: *T) T {
+    vow value: T = *p;
+    offer value;
+}
+
+spellcard swap<T>(a: *T, b: *T) void {
+    vow tmp = *a;
+    *a = *b;
+    *b = tmp;
+}
+
+spellcard main() i32 {
+    vow result = 0;
+    vow big: i64 = 5000000000;
+    foreseen max(big, 2) == 5000000000 {
+        result = result + 1;
+    }
+    vow point = Point { x: 3, y: 7 };
+    swap(&point.x, &point.y);
+    foreseen read(&point.x) == max(point.x, point.y) {
+        result = result + 10;
+    }
+    vow a = 1.5;
+    vow b = 2.5;
+    swap(&a, &b);
+    foreseen max(a, b) - read(&b) == 1.0 {
+        result = result + 100;
+    }
+    offer result;
+}";
+
+    let program = Program::decode(&setup_program(body).encode()).expect("Should decode correctly");
+    let mut vm = VM::new(program);
+    assert_eq!(vm.run().expect("Should run correctly"), Some(111));
+}
+
+#[test]
+fn run_generic_spellcard_beside_mangled_name() {
+    let body = "
+spellcard max<T>(a: T, b: T) T {
+    foreseen a > b {
+        offer a;
+    }
+    offer b;
+}
+
+spellcard max__i32(a: i32, b: i32) i32 {
+    offer 4;
+}
+
+spellcard main() i32 {
+    vow first = max(1, 3);
+    vow small = 2;
+    vow second = *max(&small, &small);
+    offer first + second;
+}";
+
+    let mut vm = setup(body);
+    assert_eq!(vm.run().expect("Should run correctly"), Some(5));
+}
+
+#[test]
+fn run_method() {
+    let body = "
+struct Counter { count: i32 }
+struct Square { side: i32 }
+
+trait Shape {
+    spellcard area(self: *Self) i32;
+}
+
+impl Counter {
+    spellcard bump(self: *Self, by: i32) void {
+        self.count = self.count + by;
+    }
+    spellcard get(self: Self) i32 {
+        offer self.count;
+    }
+}
+
+impl Square: Shape {
+    spellcard area(self: *Self) i32 {
+        offer self.side * self.side;
+    }
+}
+
+impl Counter: Shape {
+    spellcard area(self: *Counter) i32 {
+        offer self.count;
+    }
+}
+
+spellcard total<T: Shape>(a: *T, b: *T) i32 {
+    offer a.area() + b.area();
+}
+
+spellcard main() i32 {
+    vow result = 0;
+    vow counter = Counter { count: 0 };
+    counter.bump(40);
+    vow p = &counter;
+    p.bump(2);
+    foreseen counter.get() == 42 {
+        result = result + 1;
+    }
+    vow small = Square { side: 2 };
+    vow big = Square { side: 3 };
+    foreseen total(&small, &big) == 13 {
+        result = result + 10;
+    }
+    foreseen total(p, &counter) == 84 {
+        result = result + 100;
+    }
+    offer result;
+}";
+
+    let program = Program::decode(&setup_program(body).encode()).expect("Should decode correctly");
+    let mut vm = VM::new(program);
+    assert_eq!(vm.run().expect("Should run correctly"), Some(111));
+}
+
+#[test]
+fn run_logical_short_circuit() {
+    let body = "
+spellcard touch(count: *i32) bool {
+    *count = *count + 1;
+    offer true;
+}
+
+spellcard main() i32 {
+    vow count = 0;
+    vow x = 3;
+    vow result = 0;
+    foreseen x > 0 && x < 2 || x == 3 {
+        result = result + 1;
+    }
+    foreseen false && touch(&count) {
+        result = result + 50;
+    }
+    foreseen x > 0 || touch(&count) {
+        result = result + 10;
+    }
+    foreseen true and touch(&count) or touch(&count) {
+        result = result + 100;
+    }
+    foreseen count == 1 {
+        result = result + 1000;
+    }
+    offer result;
+}";
+
+    let mut vm = VM::new(setup_program(body));
+    assert_eq!(vm.run().expect("Should run correctly"), Some(1111));
+}
+
+#[test]
+fn array_bounds_check_in_debug() {
+    let body = "
+spellcard main() i32 {
+    vow a = [1, 2, 3];
+    vow i = 3;
+    offer a[i];
+}";
+
+    let chars = body.chars().collect::<Vec<_>>();
+    let ast = Parser::new(Lexer::new(&chars))
+        .parse()
+        .expect("Should parse correctly");
+    let mut compiler = Compiler::new();
+    compiler.debug = true;
+    let ops = compiler.compile(ast).expect("Should compile ast correctly");
+    let mut vm = VM::new(Program::new(&compiler, ops).expect("Should assemble correctly"));
+    assert!(matches!(
+        vm.run(),
+        Err(VMError::IndexOutOfBounds {
+            index: 3,
+            length: 3
+        })
+    ));
+}
+
+#[test]
+fn call_spellcard_by_name() {
+    let body = "
+spellcard sub_number(a: i32, b: i32) i32 {
+    offer a - b;
+}";
+
+    let mut vm = setup(body);
+    vm.init().expect("Should run correctly");
+    assert_eq!(vm.call("sub_number", &[10, 3]).unwrap(), 7);
+}
+
+#[test]
+fn format_printf() {
+    let memory = Memory::new(b"%d %s|%5d|%-3c|%x%%\0Remi\0", 0, 0);
+    let out = format(
+        &memory,
+        memory.data(0),
+        &[(-69i64) as u64, memory.data(20), 42, 'a' as u64, 255],
+    )
+    .expect("Should format correctly");
+    assert_eq!(out, b"-69 Remi|   42|a  |ff%");
+
+    let memory = Memory::new(b"%99999999999999999999d\0%.5000d\0", 0, 0);
+    for offset in [0, 23] {
+        assert!(matches!(
+            format(&memory, memory.data(offset), &[1]),
+            Err(VMError::InvalidOperation { .. })
+        ));
+    }
+}
+
+#[test]
+fn format_printf_float() {
+    let memory = Memory::new(b"%f|%.2lf|%8.3f|%+e|%g|%g|%G\0", 0, 0);
+    let out = format(
+        &memory,
+        memory.data(0),
+        &[
+            1.5f64.to_bits(),
+            (-2.345f64).to_bits(),
+            3.14259f64.to_bits(),
+            12345.678f64.to_bits(),
+            0.0001f64.to_bits(),
+            1234567.0f64.to_bits(),
+            f64::INFINITY.to_bits(),
+        ],
+    )
+    .expect("Should format correctly");
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "1.500000|-2.35|   3.143|+1.234568e+04|0.0001|1.23457e+06|INF"
+    );
+}
+
+#[test]
+fn run_printf_float() {
+    let body = "
+invite printf(fmt: cstr, ...) i32;
+
+spellcard main() i32 {
+    vow x: f32 = 2.5;
+    vow y = x * 3.0;
+    printf(\"%f %.1lf\\n\", x, 0.25);
+    printf(\"%g\\n\", y);
+    offer 0;
+}";
+
+    let output = Output::default();
+    let mut vm = setup(body);
+    let captured = output.clone();
+    vm.register(
+        "printf",
+        Box::new(move |memory, args| {
+            let out = format(memory, args[0], &args[1..])?;
+            captured.0.borrow_mut().extend_from_slice(&out);
+            Ok(out.len() as u64)
+        }),
+    );
+    assert_eq!(vm.run().expect("Should run correctly"), Some(0));
+    assert_eq!(output.lines(), vec!["2.500000 0.2", "7.5"]);
+}
+
+#[test]
+fn bytecode_roundtrip() {
+    let body = "
+invite printf;
+
+spellcard add_number(a: i32, b: i32) i32 {
+    offer a + b;
+}
+
+spellcard main() i32 {
+    vow a = add_number(35, 34);
+    foreseen a > 69 {
+        printf(\"%d\\n\", a);
+    }
+    offer a;
+}";
+
+    let program = setup_program(body);
+    let bytes = program.encode();
+    assert!(Program::is_bytecode(&bytes));
+    let decoded = Program::decode(&bytes).expect("Should decode correctly");
+    assert_eq!(program, decoded);
+
+    let mut vm = VM::new(decoded);
+    assert_eq!(vm.run().expect("Should run correctly"), Some(69));
+}
+
+#[test]
+fn line_table_with_debug_info() {
+    let body = "
+spellcard main() i32 {
+    vow a = 0;
+    until a < 3 {
+        a = a + 1;
+    }
+    offer a;
+}";
+
+    let chars = body.chars().collect::<Vec<_>>();
+    let ast = Parser::new(Lexer::new(&chars))
+        .parse()
+        .expect("Should parse correctly");
+    let mut compiler = Compiler::new();
+    compiler.debug = true;
+    let ops = compiler.compile(ast).expect("Should compile ast correctly");
+    let program = Program::new(&compiler, ops).expect("Should assemble correctly");
+
+    let rows = program
+        .lines
+        .iter()
+        .map(|(_, loc)| loc.row)
+        .collect::<Vec<_>>();
+    assert_eq!(rows, vec![3, 4, 5, 7]);
+    assert_eq!(program.line(program.lines[1].0).map(|loc| loc.row), Some(4));
+
+    let decoded = Program::decode(&program.encode()).expect("Should decode correctly");
+    assert_eq!(program, decoded);
+
+    let mut vm = VM::new(decoded);
+    assert_eq!(vm.run().expect("Should run correctly"), Some(3));
+}
+
+#[test]
+fn bytecode_reject_invalid_file() {
+    assert!(matches!(
+        Program::decode(b"ELF"),
+        Err(BytecodeError::InvalidMagic)
+    ));
+
+    let mut bytes = Program::default().encode();
+    bytes[4] = 0xff;
+    assert!(matches!(
+        Program::decode(&bytes),
+        Err(BytecodeError::UnsupportedVersion { .. })
+    ));
+
+    let bytes = Program::default().encode();
+    assert!(matches!(
+        Program::decode(&bytes[..bytes.len() - 1]),
+        Err(BytecodeError::UnexpectedEof)
+    ));
+
+    let program = Program {
+        code: vec![Instruction::Assign {
+            offset: 0xffffff,
+            arg: Arg::Literal(Value::I32(1)),
+        }],
+        ..Default::default()
+    };
+    assert!(matches!(
+        Program::decode(&program.encode()),
+        Err(BytecodeError::OutOfBound { kind: "local", .. })
+    ));
+    assert!(matches!(
+        VM::new(program).run(),
+        Err(VMError::InvalidAddress { .. })
+    ));
+}
+
+#[test]
+fn disassemble_program() {
+    let body = "
+invite printf;
+
+spellcard main() i32 {
+    vow a = 0;
+    until a < 3 {
+        printf(\"Hi\\n\");
+        a = a + 1;
+    }
+    offer 0;
+}";
+
+    let program = Program::decode(&setup_program(body).encode()).expect("Should decode correctly");
+    let dump = program.disassemble();
+    let expected = [
+        "    0x00: printf",
+        "    main(): arity 0, frame 0x02",
+        "    .L0x0002:",
+        "        0x0003: Jne(.L0x0008, Local(0x01))",
+        "        0x0004: Call(0x01, printf (import), [DataOffset(0x00)])",
+        "        0x0007: Jmp(.L0x0002)",
+    ];
+    for line in expected {
+        assert!(
+            dump.lines().any(|l| l == line),
+            "missing `{}` in\n{}",
+            line,
+            dump
+        );
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn call_foreign_function() {
+    let body = "
+invite strlen;
+invite labs;
+
+spellcard main() i32 {
+    vow len = strlen(\"Remilia Scarlet\");
+    offer len + labs(-10);
+}";
+
+    let mut vm = setup(body);
+    assert_eq!(vm.run().expect("Should run correctly"), Some(25));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn call_foreign_float_function() {
+    let body = "
+invite sqrt(x: f64) f64;
+invite sqrtf(x: f32) f32;
+invite ldexp(x: f64, exp: i32) f64;
+
+spellcard main() i32 {
+    vow result = 0;
+    foreseen sqrt(16.0) == 4.0 {
+        result = result + 1;
+    }
+    foreseen sqrtf(2.25) == 1.5 {
+        result = result + 10;
+    }
+    foreseen ldexp(1.5, 3) == 12.0 {
+        result = result + 100;
+    }
+    offer result;
+}";
+
+    let mut vm = setup(body);
+    vm.load_library("libm.so.6")
+        .expect("Should load libm correctly");
+    assert_eq!(vm.run().expect("Should run correctly"), Some(111));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn call_foreign_variadic_with_float() {
+    let body = "
+invite snprintf(buf: *[u8; 16], size: u64, format: cstr, ...) i32;
+invite strcmp(a: *[u8; 16], b: cstr) i32;
+
+spellcard main() i32 {
+    vow buf: [u8; 16] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    vow len = snprintf(&buf, 16, \"%g|%d|%g\", 0.125, 69, 2.5);
+    foreseen strcmp(&buf, \"0.125|69|2.5\") == 0 {
+        offer len;
+    }
+    offer -1;
+}";
+
+    let mut vm = setup(body);
+    assert_eq!(vm.run().expect("Should run correctly"), Some(12));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn call_foreign_function_with_struct() {
+    let body = "
+struct InAddr { s_addr: u32 }
+invite inet_ntoa(addr: InAddr) cstr;
+invite strcmp(a: cstr, b: cstr) i32;
+
+spellcard main() i32 {
+    vow addr = InAddr { s_addr: 16777343 };
+    offer strcmp(inet_ntoa(addr), \"127.0.0.1\");
+}";
+
+    let mut vm = setup(body);
+    assert_eq!(vm.run().expect("Should run correctly"), Some(0));
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn call_foreign_function_with_big_struct() {
+    let body = "
+struct Big { x: i64, y: i64, z: i64 }
+invite snprintf(buf: *[u8; 32], size: u64, format: cstr, big: Big, a: i64, b: i64, c: i64, d: i64) i32;
+invite strcmp(a: *[u8; 32], b: cstr) i32;
+
+spellcard main() i32 {
+    vow buf: [u8; 32] = [
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0
+    ];
+    vow big = Big { x: 4, y: 5, z: 6 };
+    snprintf(&buf, 32, \"%ld %ld %ld %ld %ld %ld %ld\", big, 1, 2, 3, 7);
+    offer strcmp(&buf, \"1 2 3 4 5 6 7\");
+}";
+
+    let mut vm = setup(body);
+    assert_eq!(vm.run().expect("Should run correctly"), Some(0));
+}
+
+#[test]
+fn unresolved_foreign_function() {
+    let body = "
+invite remi_does_not_exist;
+
+spellcard main() i32 {
+    offer remi_does_not_exist();
+}";
+
+    let mut vm = setup(body);
+    assert!(matches!(vm.run(), Err(VMError::UnknownFunction { .. })));
+}
+
+#[test]
+fn trace_calls() {
+    let body = "
+spellcard add_number(a: i32, b: i32) i32 {
+    offer a + b;
+}
+
+spellcard main() i32 {
+    offer add_number(35, 34);
+}";
+
+    let out = Output::default();
+    let mut vm = setup(body);
+    vm.set_tracer(Tracer::new(false, true, Box::new(out.clone())));
+    assert_eq!(vm.run().expect("Should run correctly"), Some(69));
+    assert_eq!(
+        out.lines(),
+        vec![
+            "call main()",
+            "  call add_number(35, 34)",
+            "  offer add_number = 69",
+            "offer main = 69",
+        ]
+    );
+}
+
+#[test]
+fn trace_instructions() {
+    let body = "
+spellcard main() i32 {
+    vow a = 35;
+    offer a + 34;
+}";
+
+    let out = Output::default();
+    let mut vm = setup(body);
+    vm.set_tracer(Tracer::new(true, false, Box::new(out.clone())));
+    assert_eq!(vm.run().expect("Should run correctly"), Some(69));
+    assert_eq!(
+        out.lines(),
+        vec![
+            "[__global] 0x0000: Ret(void)",
+            "  [main] 0x0001: EternalAssign(0x00, Literal(35)) -> Local(0x00)=35",
+            "  [main] 0x0002: BinOp<i32>(0x01 Local(0x00) + Literal(34)) | Local(0x00)=35 -> Local(0x01)=69",
+            "  [main] 0x0003: Ret(Local(0x01)) | Local(0x01)=69",
+        ]
+    );
+}
+
+#[test]
+fn limit_fuel() {
+    let body = "
+spellcard main() i32 {
+    vow a = 0;
+    until true {
+        a = a + 1;
+    }
+    offer a;
+}";
+
+    let limits = Limits {
+        fuel: Some(1000),
+        ..Default::default()
+    };
+    let mut vm = VM::with_limits(setup_program(body), limits).expect("Should create VM");
+    assert!(matches!(vm.run(), Err(VMError::OutOfFuel { budget: 1000 })));
+    assert_eq!(vm.fuel(), Some(0));
+}
+
+#[test]
+fn limit_call_depth() {
+    let body = "
+spellcard two() i32 {
+    offer 2;
+}
+
+spellcard one() i32 {
+    offer two() - 1;
+}
+
+spellcard main() i32 {
+    offer one();
+}";
+
+    let mut vm = setup(body);
+    assert_eq!(vm.run().expect("Should run correctly"), Some(1));
+
+    let limits = Limits {
+        max_call_depth: Some(2),
+        ..Default::default()
+    };
+    let mut vm = VM::with_limits(setup_program(body), limits).expect("Should create VM");
+    assert!(matches!(
+        vm.run(),
+        Err(VMError::CallDepthExceeded { limit: 2 })
+    ));
+}
+
+#[test]
+fn limit_memory() {
+    let body = "
+vow a = \"Remilia Scarlet\";
+";
+
+    let limits = Limits {
+        max_memory: Some(8),
+        ..Default::default()
+    };
+    assert!(matches!(
+        VM::with_limits(setup_program(body), limits),
+        Err(VMError::MemoryLimitExceeded {
+            requested: 16,
+            limit: 8
+        })
+    ));
+}
+
+#[test]
+fn limit_allowed_imports() {
+    let body = "
+invite puts;
+invite system;
+
+spellcard main() i32 {
+    system(\"echo pwned\");
+    offer 0;
+}";
+
+    let limits = Limits {
+        allowed_imports: Some(vec!["puts".to_owned()]),
+        ..Default::default()
+    };
+    let mut vm = VM::with_limits(setup_program(body), limits).expect("Should create VM");
+    assert!(matches!(
+        vm.run(),
+        Err(VMError::ImportNotAllowed { name }) if name == "system"
+    ));
+}
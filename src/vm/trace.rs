@@ -0,0 +1,66 @@
+use std::io::Write;
+
+pub struct Tracer {
+    pub instructions: bool,
+    pub calls: bool,
+    out: Box<dyn Write>,
+}
+
+impl Tracer {
+    pub fn new(instructions: bool, calls: bool, out: Box<dyn Write>) -> Self {
+        Self {
+            instructions,
+            calls,
+            out,
+        }
+    }
+
+    pub fn stderr(instructions: bool, calls: bool) -> Self {
+        Self::new(instructions, calls, Box::new(std::io::stderr()))
+    }
+
+    fn line(&mut self, depth: usize, line: String) {
+        let _ = writeln!(self.out, "{}{}", "  ".repeat(depth), line);
+    }
+
+    pub fn instruction(
+        &mut self,
+        depth: usize,
+        function: &str,
+        pc: usize,
+        instruction: String,
+        operands: Vec<String>,
+        result: Option<String>,
+    ) {
+        if !self.instructions {
+            return;
+        }
+        let mut line = format!("[{}] {:#06x}: {}", function, pc, instruction);
+        if !operands.is_empty() {
+            line.push_str(&format!(" | {}", operands.join(", ")));
+        }
+        if let Some(result) = result {
+            line.push_str(&format!(" -> {}", result));
+        }
+        self.line(depth, line);
+    }
+
+    pub fn enter(&mut self, depth: usize, function: &str, args: &[u64]) {
+        if !self.calls {
+            return;
+        }
+        let args = args
+            .iter()
+            .map(|arg| (*arg as i64).to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        self.line(depth, format!("call {}({})", function, args));
+    }
+
+    pub fn exit(&mut self, depth: usize, function: &str, value: u64) {
+        if !self.calls {
+            return;
+        }
+        self.line(depth, format!("offer {} = {}", function, value as i64));
+    }
+}